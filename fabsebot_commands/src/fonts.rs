@@ -0,0 +1,133 @@
+use fabsebot_core::{
+	config::{
+		constants::FONTS,
+		types::{Error, SContext},
+	},
+	utils::{
+		helpers::url_bytes,
+		image::fonts::{FONT_EXTENSIONS, FONT_MAX_BYTES, FONT_MAX_MIB, forget_font, validate_font},
+	},
+};
+use fabsebot_db::guild::{delete_font, fetch_font_names, font_count, font_exists, insert_font};
+use poise::CreateReply;
+use serenity::all::{Attachment, CreateAutocompleteResponse};
+
+use crate::quote_options::{QuoteOption, name_choices};
+
+async fn autocomplete_font<'a>(
+	ctx: SContext<'_>,
+	partial: &'a str,
+) -> CreateAutocompleteResponse<'a> {
+	let Some(guild_id) = ctx.guild_id() else {
+		return CreateAutocompleteResponse::new();
+	};
+	let names = fetch_font_names(i64::from(guild_id), &ctx.data().db)
+		.await
+		.unwrap_or_default();
+	name_choices(names, partial)
+}
+
+/// Fonts of the server's own to pick from when quoting
+#[expect(clippy::unused_async)]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	subcommands("upload", "delete"),
+	subcommand_required
+)]
+pub async fn quote_font(_ctx: SContext<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Add a font for quotes, replacing any font with the same name
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_permissions = "MANAGE_GUILD"
+)]
+async fn upload(
+	ctx: SContext<'_>,
+	#[description = "Name to pick the font by"] name: String,
+	#[description = "TTF or OTF file"] font: Attachment,
+) -> Result<(), Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let guild_id_i64 = i64::from(guild_id);
+	let db = &ctx.data().db;
+	let name = name.trim();
+	let filename = font.filename.to_lowercase();
+	let rejection =
+		if let Some(rejection) = QuoteOption::Font.name_rejection(name, FONTS.contains_key(name)) {
+			Some(rejection)
+		} else if !FONT_EXTENSIONS
+			.iter()
+			.any(|extension| filename.ends_with(extension))
+		{
+			Some("Fonts have to be TTF or OTF files".to_owned())
+		} else if font.size > FONT_MAX_BYTES {
+			Some(format!("Fonts can be at most {FONT_MAX_MIB} MiB"))
+		} else {
+			QuoteOption::Font.limit_rejection(
+				font_exists(guild_id_i64, name, db).await?,
+				font_count(guild_id_i64, db).await?,
+			)
+		};
+	if let Some(rejection) = rejection {
+		ctx.send(CreateReply::new().content(rejection).ephemeral(true))
+			.await?;
+		return Ok(());
+	}
+
+	ctx.defer().await?;
+	let bytes = url_bytes(&font.url).await?.to_vec();
+	// The reported size doesn't have to match what actually got downloaded
+	if !u32::try_from(bytes.len()).is_ok_and(|len| len <= FONT_MAX_BYTES) {
+		ctx.reply(format!("Fonts can be at most {FONT_MAX_MIB} MiB"))
+			.await?;
+		return Ok(());
+	}
+	if let Err(err) = validate_font(bytes.clone()).await {
+		ctx.reply(format!("That font can't be used since {err}"))
+			.await?;
+		return Ok(());
+	}
+	insert_font(guild_id_i64, name, &bytes, i64::from(ctx.author().id), db).await?;
+	forget_font(guild_id, name);
+	ctx.reply(format!(
+		"Added **{name}**, pick it from the font menu of a quote"
+	))
+	.await?;
+	Ok(())
+}
+
+/// Remove a font of the server
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_permissions = "MANAGE_GUILD"
+)]
+async fn delete(
+	ctx: SContext<'_>,
+	#[description = "Name of the font"]
+	#[autocomplete = "autocomplete_font"]
+	#[rest]
+	name: String,
+) -> Result<(), Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let name = name.trim();
+	let response = if delete_font(i64::from(guild_id), name, &ctx.data().db)
+		.await?
+		.rows_affected()
+		> 0
+	{
+		forget_font(guild_id, name);
+		format!("Removed the **{name}** font")
+	} else {
+		format!("No font called **{name}** found")
+	};
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
@@ -9,12 +9,20 @@ use poise::Command;
 use serenity::all::Permissions;
 
 mod api_calls;
+mod fonts;
 mod funny;
 mod games;
 mod info;
+mod library;
 mod misc;
 mod music;
+mod party;
+mod playlist;
+mod quote_options;
+mod recording;
 mod settings;
+mod soundboard;
+mod themes;
 
 pub async fn command_permissions(ctx: &SContext<'_>) -> AResult<()> {
 	if let Some(guild_id) = ctx.guild_id()
@@ -44,12 +52,14 @@ pub fn commands() -> Vec<Command<Data, Error>> {
 		api_calls::urban(),
 		api_calls::waifu(),
 		api_calls::wiki(),
+		fonts::quote_font(),
 		funny::anonymous(),
 		funny::user_dm(),
 		funny::user_misuse(),
 		games::rps(),
 		info::server_info(),
 		info::user_info(),
+		library::library(),
 		misc::birthday(),
 		misc::bot_control(),
 		misc::bot_personalize(),
@@ -60,25 +70,38 @@ pub fn commands() -> Vec<Command<Data, Error>> {
 		misc::leaderboard(),
 		misc::ohitsyou(),
 		misc::quote(),
+		misc::quote_conversation(),
 		misc::quote_menu(),
 		misc::register_commands(),
 		misc::respond(),
 		misc::slow_mode(),
 		misc::word_count(),
+		misc::wrapped(),
+		music::filter(),
 		music::join_voice(),
 		music::join_voice_old(),
 		music::leave_voice(),
+		music::music_stats(),
 		music::play_song(),
 		music::play_file(),
 		music::play_song_old(),
+		music::seek(),
 		music::text_to_voice(),
+		music::volume(),
+		party::party(),
+		playlist::playlist(),
+		recording::record(),
 		settings::configure_server_settings(),
 		settings::reset_user_settings(),
 		settings::set_afk(),
 		settings::set_chatbot_options(),
+		settings::set_music_options(),
 		settings::set_prefix(),
 		settings::set_user_ping(),
 		settings::set_word_react(),
 		settings::set_word_track(),
+		soundboard::sound(),
+		soundboard::soundboard(),
+		themes::quote_theme(),
 	]
 }
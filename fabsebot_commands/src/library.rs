@@ -0,0 +1,162 @@
+use std::{fmt::Write as _, time::Duration};
+
+use fabsebot_core::{
+	config::{
+		constants::{FAILED_SONG_FETCH, QUEUEING_MSG},
+		types::{Error, SContext},
+	},
+	utils::voice::{
+		format_timestamp,
+		library::{add_local_track, cover_file_name, scan_library},
+		try_voice,
+	},
+};
+use fabsebot_db::music::{LibraryTrack, fetch_library_file, search_library};
+use poise::CreateReply;
+use serenity::all::{AutocompleteChoice, CreateAttachment, CreateAutocompleteResponse};
+use sqlx::types::Uuid;
+
+const SEARCH_LIMIT: i64 = 25;
+/// Discord caps autocomplete choice names at 100 characters
+const CHOICE_NAME_LIMIT: usize = 100;
+
+fn track_label(track: &LibraryTrack) -> String {
+	format!("{} - {}", track.artist, track.title)
+}
+
+async fn autocomplete_library<'a>(
+	ctx: SContext<'_>,
+	partial: &'a str,
+) -> CreateAutocompleteResponse<'a> {
+	let choices: Vec<_> = search_library(partial.trim(), SEARCH_LIMIT, &ctx.data().db)
+		.await
+		.unwrap_or_default()
+		.iter()
+		.map(|track| {
+			let label: String = track_label(track).chars().take(CHOICE_NAME_LIMIT).collect();
+			AutocompleteChoice::new(label, track.track_uuid.to_string())
+		})
+		.collect();
+	CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+/// Music stored on the bot's own disk
+#[expect(clippy::unused_async)]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	subcommands("search", "play", "scan"),
+	subcommand_required
+)]
+pub async fn library(_ctx: SContext<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Search the library by title, artist or album
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn search(
+	ctx: SContext<'_>,
+	#[description = "Title, artist or album"]
+	#[rest]
+	query: String,
+) -> Result<(), Error> {
+	let tracks = search_library(query.trim(), SEARCH_LIMIT, &ctx.data().db).await?;
+	if tracks.is_empty() {
+		ctx.reply("Nothing in the library matches that").await?;
+		return Ok(());
+	}
+	let mut response = format!("# Library results for {}\n", query.trim());
+	for track in &tracks {
+		write!(response, "- **{}**", track_label(track))?;
+		if let Some(album) = &track.album {
+			write!(response, " *({album})*")?;
+		}
+		writeln!(
+			response,
+			" `{}`",
+			format_timestamp(Duration::from_secs(track.duration_sec.cast_unsigned()))
+		)?;
+	}
+	ctx.reply(response).await?;
+	Ok(())
+}
+
+/// Queue a song from the library
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | SEND_MESSAGES_IN_THREADS | SPEAK | \
+	                            CONNECT"
+)]
+async fn play(
+	ctx: SContext<'_>,
+	#[description = "Song to play"]
+	#[autocomplete = "autocomplete_library"]
+	#[rest]
+	song: String,
+) -> Result<(), Error> {
+	let db = &ctx.data().db;
+	let track_uuid = match Uuid::parse_str(song.trim()) {
+		Ok(track_uuid) => Some(track_uuid),
+		Err(_) => search_library(song.trim(), 1, db)
+			.await?
+			.first()
+			.map(|track| track.track_uuid),
+	};
+	let file = match track_uuid {
+		Some(track_uuid) => fetch_library_file(track_uuid, db).await?,
+		None => None,
+	};
+	let Some(file) = file else {
+		ctx.reply("Nothing in the library matches that").await?;
+		return Ok(());
+	};
+
+	let Some((_typing, guild_id, handler_lock)) = try_voice(ctx).await? else {
+		return Ok(());
+	};
+	let mut queueing = CreateReply::new().content(QUEUEING_MSG).reply(true);
+	if let Some(cover_art) = &file.cover_art {
+		queueing = queueing.attachment(CreateAttachment::bytes(
+			cover_art.clone(),
+			cover_file_name(cover_art),
+		));
+	}
+	let reply = ctx.send(queueing).await?;
+	let msg = reply.message().await?;
+	if let Err(err) = add_local_track(
+		file,
+		&handler_lock,
+		guild_id,
+		msg.id,
+		msg.channel_id,
+		ctx.author().id,
+		db,
+	)
+	.await
+	{
+		reply
+			.edit(ctx, CreateReply::new().content(FAILED_SONG_FETCH))
+			.await?;
+		return Err(err);
+	}
+
+	Ok(())
+}
+
+/// Index the music directory again, picking up added and removed files
+#[poise::command(prefix_command, slash_command, owners_only)]
+async fn scan(ctx: SContext<'_>) -> Result<(), Error> {
+	ctx.defer().await?;
+	let response = match scan_library(&ctx.data().db).await? {
+		Some(scan) => format!(
+			"Indexed {} file(s), {} missing file(s) got removed from the library",
+			scan.indexed, scan.unlisted
+		),
+		None => "No music directory is configured".to_owned(),
+	};
+	ctx.reply(response).await?;
+	Ok(())
+}
@@ -1,4 +1,6 @@
-use std::{borrow::Cow, fmt::Write as _, io::Cursor, mem::take, time::Duration};
+use std::{
+	borrow::Cow, fmt::Write as _, io::Cursor, iter::once, mem::take, sync::Arc, time::Duration,
+};
 
 use ab_glyph::FontArc;
 use anyhow::{Context as _, Result as AResult};
@@ -6,7 +8,7 @@ use fabsebot_core::{
 	config::{
 		constants::{
 			ANIMATED_QUOTE_VEC, AUTHOR_FONT, CONTENT_FONT, DEFAULT_THEME, EMPTY_REPLY_MSG, FONTS,
-			MESSAGE_LIMIT, MISSING_REPLY_MSG, STATIC_QUOTE_VEC, THEMES,
+			MESSAGE_LIMIT, MISSING_REPLY_MSG, SELECT_MENU_LIMIT, STATIC_QUOTE_VEC, THEMES,
 		},
 		types::{AIChatMessage, Error, SContext, SYSTEM_STATS, utils_config},
 	},
@@ -14,16 +16,30 @@ use fabsebot_core::{
 	utils::{
 		ai::ai_response,
 		helpers::{
-			default_mentions, image_uri, media_gallery, reply_container, text_display,
-			thumbnail_section, url_bytes,
+			default_mentions, image_uri, media_gallery, message_link_ids, reply_container,
+			text_display, thumbnail_section, url_bytes,
 		},
 		image::{
-			ImageType, QuoteImageConfig, TextLayout, avatar_position, get_theme, quote_image,
-			resize_avatar,
+			ATTACHMENT_MAX_BYTES, ImageType, QuoteImageConfig, TextLayout, WrappedCard,
+			avatar_position,
+			conversation::{
+				ConversationMessage, MAX_CONVERSATION_MESSAGES, conversation_image, fetch_avatars,
+			},
+			fonts::guild_font,
+			get_theme,
+			inline::InlineText,
+			quote_image, resize_avatar,
+			theme::{QuoteTheme, TextColours},
+			wrapped_image,
 		},
 	},
 };
-use image::{ImageBuffer, Rgba};
+use fabsebot_db::{
+	guild::{fetch_font_names, fetch_quote_theme, fetch_quote_theme_names},
+	music::{fetch_play_summary, fetch_top_tracks},
+	user::{fetch_top_words, fetch_yearly_stats, record_quote},
+};
+use image::{ImageBuffer, ImageFormat, Rgba, RgbaImage, load_from_memory};
 use poise::{ChoiceParameter, CreateReply, ReplyHandle, builtins::register_globally};
 use rayon::spawn;
 use serenity::{
@@ -33,17 +49,22 @@ use serenity::{
 		CreateAutocompleteResponse, CreateButton, CreateComponent, CreateContainer,
 		CreateInteractionResponse, CreateMessage, CreateSelectMenu, CreateSelectMenuKind,
 		CreateSelectMenuOption, DataUri, EditChannel, EditCurrentMember, EditMessage,
-		GenericChannelId, GuildChannel, GuildId, Message, MessageId, OnlineStatus, User,
+		GenericChannelId, GetMessages, GuildChannel, GuildId, Message, MessageId, OnlineStatus,
+		User,
 	},
 	builder::{CreateContainerComponent, CreateMediaGallery, CreateSection},
 	futures::StreamExt as _,
 	nonmax::NonMaxU16,
 };
-use sqlx::{query, query_as, query_scalar};
+use sqlx::{
+	query, query_as, query_scalar,
+	types::time::{Date, OffsetDateTime},
+};
 use systemstat::{Platform as _, saturating_sub_bytes};
 use tokio::{
 	sync::oneshot,
 	time::{sleep, timeout},
+	try_join,
 };
 use tracing::warn;
 
@@ -491,6 +512,124 @@ pub async fn leaderboard(ctx: SContext<'_>) -> Result<(), Error> {
 	Ok(())
 }
 
+#[derive(Clone, Copy, Default, ChoiceParameter)]
+enum WrappedFormat {
+	#[default]
+	#[name = "avif"]
+	Avif,
+	#[name = "png"]
+	Png,
+}
+
+impl WrappedFormat {
+	const fn image_format(self) -> (ImageFormat, &'static str) {
+		match self {
+			Self::Avif => (ImageFormat::Avif, "wrapped.avif"),
+			Self::Png => (ImageFormat::Png, "wrapped.png"),
+		}
+	}
+}
+
+#[expect(clippy::unused_async)]
+async fn autocomplete_theme<'a>(
+	_ctx: SContext<'_>,
+	partial: &'a str,
+) -> CreateAutocompleteResponse<'a> {
+	let choices: Vec<_> = THEMES
+		.keys()
+		.copied()
+		.chain(["random"])
+		.filter(move |theme| theme.starts_with(partial))
+		.map(AutocompleteChoice::from)
+		.collect();
+	CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+/// Your year in this server as a shareable card
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | SEND_MESSAGES_IN_THREADS | \
+	                            ATTACH_FILES"
+)]
+pub async fn wrapped(
+	ctx: SContext<'_>,
+	#[description = "User to look at, defaults to you"] user: Option<User>,
+	#[description = "Card theme"]
+	#[autocomplete = "autocomplete_theme"]
+	theme: Option<String>,
+	#[description = "Image format"] format: Option<WrappedFormat>,
+) -> Result<(), Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let guild_name = ctx.guild().unwrap().name.to_string();
+	let user = user.as_ref().unwrap_or_else(|| ctx.author());
+	let guild_id_i64 = i64::from(guild_id);
+	let user_id_i64 = i64::from(user.id);
+	let _typing = ctx.defer_or_broadcast().await;
+
+	let year = OffsetDateTime::now_utc().year();
+	let since = Date::from_ordinal_date(year, 1)?.midnight().assume_utc();
+	let db = &ctx.data().db;
+	let yearly = fetch_yearly_stats(guild_id_i64, user_id_i64, year, db).await?;
+	let top_words = fetch_top_words(guild_id_i64, user_id_i64, year, db).await?;
+	let top_songs = fetch_top_tracks(guild_id_i64, Some(user_id_i64), Some(since), db).await?;
+	let plays = fetch_play_summary(guild_id_i64, Some(user_id_i64), Some(since), db).await?;
+
+	let card = WrappedCard {
+		username: user.display_name().to_owned(),
+		guild_name,
+		year,
+		messages: yearly.as_ref().map_or(0, |stats| stats.message_count),
+		quotes: yearly.as_ref().map_or(0, |stats| stats.quotes_made),
+		minutes: plays.listening_secs.saturating_div(60),
+		top_words: top_words
+			.into_iter()
+			.map(|word| format!("{} ({})", word.word, word.count))
+			.collect(),
+		top_songs: top_songs
+			.into_iter()
+			.take(3)
+			.map(|track| format!("{} - {}", track.artist, track.title))
+			.collect(),
+	};
+
+	let avatar_url = user.static_face();
+	let avatar = url_bytes(&avatar_url).await?;
+	let theme = theme.unwrap_or_else(|| DEFAULT_THEME.to_owned());
+	let (image_format, filename) = format.unwrap_or_default().image_format();
+	let title_font = FONTS.get(AUTHOR_FONT).unwrap().clone();
+	let content_font = FONTS.get(CONTENT_FONT).unwrap().clone();
+
+	let (tx, rx) = oneshot::channel();
+	spawn(move || {
+		let result = wrapped_image(
+			&card,
+			&avatar,
+			&title_font,
+			&content_font,
+			&theme,
+			image_format,
+			Vec::with_capacity(STATIC_QUOTE_VEC),
+		);
+		if tx.send(result).is_err() {
+			warn!("Sender failed to send result");
+		}
+	});
+	let buffer = rx
+		.await
+		.context("Rayon task for wrapped image panicked")??;
+
+	ctx.send(
+		CreateReply::new()
+			.reply(true)
+			.attachment(CreateAttachment::bytes(buffer, filename)),
+	)
+	.await?;
+
+	Ok(())
+}
+
 /// Oh it's you
 #[poise::command(
 	prefix_command,
@@ -527,12 +666,13 @@ pub async fn ohitsyou(ctx: SContext<'_>) -> Result<(), Error> {
 
 struct ImageInfo {
 	author_name: String,
-	content: String,
+	content: InlineText,
+	attachment: Option<Arc<RgbaImage>>,
 	new_font: bool,
 	config: QuoteImageConfig,
 	content_font: (String, FontArc),
 	author_font: FontArc,
-	text_colour: Rgba<u8>,
+	colours: TextColours,
 	img: ImageBuffer<Rgba<u8>, Vec<u8>>,
 	text_layout: TextLayout,
 	buffer: Vec<u8>,
@@ -547,16 +687,18 @@ impl ImageInfo {
 		avatar_image: Vec<u8>,
 		author_name: String,
 		content: String,
+		attachment: Option<Vec<u8>>,
 		is_animated: bool,
 	) -> AResult<Self> {
 		let content_font = FONTS.get(CONTENT_FONT).unwrap();
 		let author_font = FONTS.get(AUTHOR_FONT).unwrap();
+		let content = InlineText::new(&content).await;
 		let author_name_clone = author_name.clone();
 		let content_clone = content.clone();
 		let content_font_clone = content_font.clone();
 		let author_font_clone = author_font.clone();
 
-		let (img, text_colour) = get_theme(DEFAULT_THEME);
+		let (img, colours) = get_theme(DEFAULT_THEME)?;
 		let img_clone = img.clone();
 		let avatar_position = avatar_position(false);
 
@@ -564,10 +706,13 @@ impl ImageInfo {
 
 		let mut text_layout = TextLayout::default();
 
-		let (text_layout, image, output) = {
+		let (text_layout, image, attachment, output) = {
 			let (tx, rx) = oneshot::channel();
 			let avatar_image_clone = avatar_image.clone();
 			spawn(move || {
+				let attachment = attachment
+					.and_then(|bytes| load_from_memory(&bytes).ok())
+					.map(|attachment| Arc::new(attachment.to_rgba8()));
 				let (mut cursor, mut image, mut buffer) = if is_animated {
 					(
 						Cursor::new(avatar_image_clone),
@@ -587,9 +732,10 @@ impl ImageInfo {
 					&mut image,
 					&author_name_clone,
 					&content_clone,
+					attachment.as_deref(),
 					&author_font_clone,
 					&content_font_clone,
-					text_colour,
+					colours,
 					img_clone,
 					&mut text_layout,
 					avatar_position,
@@ -603,14 +749,17 @@ impl ImageInfo {
 				} else {
 					cursor.into_inner()
 				};
-				if tx.send((result, text_layout, image, buffer)).is_err() {
+				if tx
+					.send((result, text_layout, image, attachment, buffer))
+					.is_err()
+				{
 					warn!("Sender failed to send result");
 				}
 			});
-			let (result, text_layout, image, buffer) =
+			let (result, text_layout, image, attachment, buffer) =
 				rx.await.context("Rayon task for quote image panicked")?;
 			result?;
-			(text_layout, image, buffer)
+			(text_layout, image, attachment, buffer)
 		};
 
 		let filename = if is_animated {
@@ -627,6 +776,7 @@ impl ImageInfo {
 			},
 			author_name,
 			content,
+			attachment,
 			config: image_config,
 			author_font: author_font.clone(),
 			content_font: (CONTENT_FONT.to_owned(), content_font.clone()),
@@ -634,7 +784,7 @@ impl ImageInfo {
 			new_font: false,
 			buffer: output,
 			img,
-			text_colour,
+			colours,
 			avatar_position,
 			current_theme_name: DEFAULT_THEME.to_owned(),
 			filename,
@@ -658,8 +808,7 @@ impl ImageInfo {
 	}
 
 	async fn random_theme(&mut self) -> Result<(), Error> {
-		(self.img, self.text_colour) = get_theme("random");
-		self.image_gen().await
+		self.apply_theme(QuoteTheme::random()).await
 	}
 
 	async fn new_font(&mut self, font_name: &str, new_font: FontArc) -> Result<(), Error> {
@@ -672,20 +821,32 @@ impl ImageInfo {
 		Ok(())
 	}
 
-	async fn new_theme(&mut self, theme_name: &str) -> Result<(), Error> {
+	async fn new_theme(&mut self, theme_name: &str, theme: QuoteTheme) -> Result<(), Error> {
 		theme_name.clone_into(&mut self.current_theme_name);
-		(self.img, self.text_colour) = get_theme(theme_name);
+		self.apply_theme(theme).await
+	}
+
+	async fn apply_theme(&mut self, theme: QuoteTheme) -> Result<(), Error> {
+		let (tx, rx) = oneshot::channel();
+		spawn(move || {
+			let result = theme.render().map(|img| (img, theme.colours));
+			if tx.send(result).is_err() {
+				warn!("Sender failed to send result");
+			}
+		});
+		(self.img, self.colours) = rx.await.context("Rayon task for quote theme panicked")??;
 		self.image_gen().await
 	}
 
 	async fn image_gen(&mut self) -> Result<(), Error> {
 		let author_name = self.author_name.clone();
 		let content = self.content.clone();
+		let attachment = self.attachment.clone();
 		let author_font = self.author_font.clone();
 		let content_font = self.content_font.clone();
 		let mut text_layout = take(&mut self.text_layout);
 		let config = self.config;
-		let text_colour = self.text_colour;
+		let colours = self.colours;
 		let img = self.img.clone();
 		let avatar_position = self.avatar_position;
 		let new_font = self.new_font;
@@ -710,9 +871,10 @@ impl ImageInfo {
 				&mut image_clone,
 				&author_name,
 				&content,
+				attachment.as_deref(),
 				&author_font,
 				&content_font.1,
-				text_colour,
+				colours,
 				img,
 				&mut text_layout,
 				avatar_position,
@@ -742,6 +904,10 @@ impl ImageInfo {
 	}
 }
 
+const DEFAULT_CONVERSATION_MESSAGES: u8 = 5;
+/// Marks select menu values that name a theme or font saved by the guild
+const GUILD_OPTION_PREFIX: &str = "guild:";
+
 enum MessageTypes<'a> {
 	Reply(ReplyHandle<'a>),
 	Message(Box<Message>),
@@ -761,21 +927,42 @@ async fn quote_internal(
 				let member = guild_id.member(&ctx.http(), reply.author.id).await?;
 				(member.face(), member.user.name)
 			};
-			(url, format!("- {name}"), reply.content.to_string())
+			(url, format!("- {name}"), reply.content_safe(ctx.cache()))
 		} else {
 			(
 				msg.author.face(),
 				format!("- {}", msg.author.name),
-				msg.content.to_string(),
+				msg.content_safe(ctx.cache()),
 			)
 		};
 		let (avatar_image, is_animated) = (
 			url_bytes(&avatar_url).await?.to_vec(),
 			avatar_url.contains(".gif") || avatar_url.contains("format=gif"),
 		);
+		let quoted = reply.map_or(msg, |(reply, _)| reply);
+		let attachment = match quoted.attachments.iter().find(|attachment| {
+			attachment.size <= ATTACHMENT_MAX_BYTES
+				&& attachment
+					.content_type
+					.as_deref()
+					.is_some_and(|content_type| content_type.starts_with("image"))
+		}) {
+			Some(attachment) => Some(url_bytes(&attachment.url).await?.to_vec()),
+			None => None,
+		};
 
-		ImageInfo::new(avatar_image, author_name, text, is_animated).await?
+		ImageInfo::new(avatar_image, author_name, text, attachment, is_animated).await?
 	};
+	if let Some(guild_id) = ctx.guild_id()
+		&& let Err(err) = record_quote(
+			i64::from(guild_id),
+			i64::from(ctx.author().id),
+			&ctx.data().db,
+		)
+		.await
+	{
+		warn!("Failed to record quote: {err}");
+	}
 	let attachment = CreateAttachment::bytes(image_handle.buffer.clone(), image_handle.filename);
 	let buttons = [
 		CreateButton::new("bw")
@@ -791,11 +978,26 @@ async fn quote_internal(
 			.style(ButtonStyle::Primary)
 			.label("🎲"),
 	];
-	let mut font_select: Vec<CreateSelectMenuOption> = Vec::with_capacity(FONTS.len());
-
-	for font in FONTS.iter() {
-		font_select.push(CreateSelectMenuOption::new(*font.0, *font.0));
-	}
+	let (guild_fonts, guild_themes) = match ctx.guild_id() {
+		Some(guild_id) => {
+			let guild_id_i64 = i64::from(guild_id);
+			let db = &ctx.data().db;
+			try_join!(
+				fetch_font_names(guild_id_i64, db),
+				fetch_quote_theme_names(guild_id_i64, db)
+			)?
+		}
+		None => (Vec::new(), Vec::new()),
+	};
+	let font_select: Vec<CreateSelectMenuOption> = FONTS
+		.keys()
+		.map(|font| CreateSelectMenuOption::new(*font, *font))
+		.chain(guild_fonts.iter().map(|font| {
+			CreateSelectMenuOption::new(font.as_str(), format!("{GUILD_OPTION_PREFIX}{font}"))
+				.description("Server font")
+		}))
+		.take(SELECT_MENU_LIMIT)
+		.collect();
 
 	let font_menu = CreateSelectMenu::new(
 		"font_option",
@@ -807,11 +1009,15 @@ async fn quote_internal(
 	.min_values(1)
 	.max_values(1);
 
-	let mut theme_select: Vec<CreateSelectMenuOption> = Vec::with_capacity(THEMES.len());
-
-	for theme in THEMES.iter() {
-		theme_select.push(CreateSelectMenuOption::new(*theme.0, *theme.0));
-	}
+	let theme_select: Vec<CreateSelectMenuOption> = THEMES
+		.keys()
+		.map(|theme| CreateSelectMenuOption::new(*theme, *theme))
+		.chain(guild_themes.iter().map(|theme| {
+			CreateSelectMenuOption::new(theme.as_str(), format!("{GUILD_OPTION_PREFIX}{theme}"))
+				.description("Server theme")
+		}))
+		.take(SELECT_MENU_LIMIT)
+		.collect();
 
 	let theme_menu = CreateSelectMenu::new(
 		"theme_option",
@@ -903,14 +1109,33 @@ async fn quote_internal(
 		};
 
 		if let Some(menu_choice) = menu_choice_opt {
-			if let Some(new_font) = FONTS.get(menu_choice.as_str())
-				&& *menu_choice != image_handle.content_font.0
-			{
-				image_handle.new_font(menu_choice, new_font.clone()).await?;
-			} else if THEMES.contains_key(menu_choice.as_str())
-				&& *menu_choice != image_handle.current_theme_name
-			{
-				image_handle.new_theme(menu_choice).await?;
+			if interaction.data.custom_id == "font_option" {
+				let new_font = if *menu_choice == image_handle.content_font.0 {
+					None
+				} else if let Some(name) = menu_choice.strip_prefix(GUILD_OPTION_PREFIX)
+					&& let Some(guild_id) = ctx.guild_id()
+				{
+					guild_font(guild_id, name, &ctx.data().db).await?
+				} else {
+					FONTS.get(menu_choice.as_str()).cloned()
+				};
+				if let Some(new_font) = new_font {
+					image_handle.new_font(menu_choice, new_font).await?;
+				}
+			} else if *menu_choice != image_handle.current_theme_name {
+				let theme = if let Some(name) = menu_choice.strip_prefix(GUILD_OPTION_PREFIX)
+					&& let Some(guild_id) = ctx.guild_id()
+				{
+					fetch_quote_theme(i64::from(guild_id), name, &ctx.data().db)
+						.await?
+						.map(QuoteTheme::try_from)
+						.transpose()?
+				} else {
+					THEMES.get(menu_choice.as_str()).cloned()
+				};
+				if let Some(theme) = theme {
+					image_handle.new_theme(menu_choice, theme).await?;
+				}
 			}
 		} else if interaction.data.custom_id == "bw" {
 			image_handle.toggle_bw().await?;
@@ -1003,6 +1228,175 @@ pub async fn quote(ctx: SContext<'_>) -> Result<(), Error> {
 	Ok(())
 }
 
+/// Messages from the start link on, or leading up to the replied message
+async fn conversation_messages(
+	ctx: SContext<'_>,
+	count: u8,
+	start: Option<&str>,
+	end: Option<&str>,
+) -> AResult<Result<Vec<Message>, &'static str>> {
+	if let Some(start) = start {
+		let Some((guild_id, channel_id, start_id)) = message_link_ids(start) else {
+			return Ok(Err("The start has to be a message link"));
+		};
+		// The quote gets posted here, so links can't reach into channels the
+		// invoker might not be able to read
+		if ctx.guild_id() != Some(guild_id) || channel_id != ctx.channel_id() {
+			return Ok(Err("The start has to link a message in this channel"));
+		}
+		let end_id = match end.map(message_link_ids) {
+			Some(Some((end_guild, end_channel, end_id)))
+				if end_guild == guild_id && end_channel == channel_id && end_id >= start_id =>
+			{
+				Some(end_id)
+			}
+			Some(_) => {
+				return Ok(Err(
+					"The end has to link a later message in the same channel"
+				));
+			}
+			None => None,
+		};
+		let first = channel_id.message(&ctx.http(), start_id).await?;
+		let mut later = channel_id
+			.messages(
+				ctx.http(),
+				GetMessages::new()
+					.after(start_id)
+					.limit(MAX_CONVERSATION_MESSAGES),
+			)
+			.await?;
+		later.sort_by_key(|message| message.id);
+		let limit = if end_id.is_some() {
+			MAX_CONVERSATION_MESSAGES
+		} else {
+			count
+		};
+		return Ok(Ok(once(first)
+			.chain(later)
+			.take_while(|message| end_id.is_none_or(|end_id| message.id <= end_id))
+			.take(usize::from(limit))
+			.collect()));
+	}
+
+	let SContext::Prefix(prefix) = ctx else {
+		return Ok(Err(
+			"Give a start link, or reply to the last message with the prefix command"
+		));
+	};
+	let Some(last) = prefix.msg.referenced_message.clone() else {
+		return Ok(Err(MISSING_REPLY_MSG));
+	};
+	let mut messages = ctx
+		.channel_id()
+		.messages(
+			ctx.http(),
+			GetMessages::new()
+				.before(last.id)
+				.limit(count.saturating_sub(1)),
+		)
+		.await?;
+	messages.sort_by_key(|message| message.id);
+	messages.push(*last);
+	Ok(Ok(messages))
+}
+
+/// When one message is not enough either
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | SEND_MESSAGES_IN_THREADS | \
+	                            ATTACH_FILES | READ_MESSAGE_HISTORY"
+)]
+pub async fn quote_conversation(
+	ctx: SContext<'_>,
+	#[description = "How many messages to quote"]
+	#[min = 2]
+	#[max = 10]
+	count: Option<u8>,
+	#[description = "Link to the first message in this channel, otherwise the replied one is the \
+	                 last"]
+	start: Option<String>,
+	#[description = "Link to the last message"] end: Option<String>,
+) -> Result<(), Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let count = count
+		.unwrap_or(DEFAULT_CONVERSATION_MESSAGES)
+		.clamp(2, MAX_CONVERSATION_MESSAGES);
+	let _typing = ctx.defer_or_broadcast().await;
+
+	let messages = match conversation_messages(ctx, count, start.as_deref(), end.as_deref()).await?
+	{
+		Ok(messages) => messages,
+		Err(rejection) => {
+			ctx.send(CreateReply::new().content(rejection).ephemeral(true))
+				.await?;
+			return Ok(());
+		}
+	};
+	let mut conversation = Vec::with_capacity(messages.len());
+	for message in messages {
+		let content = message.content_safe(ctx.cache());
+		if content.trim().is_empty() {
+			continue;
+		}
+		conversation.push(ConversationMessage {
+			author_id: message.author.id,
+			author_name: message.author.display_name().to_owned(),
+			avatar_url: message.author.face(),
+			sent_at: message.timestamp.unix_timestamp(),
+			content: InlineText::new(&content).await,
+		});
+	}
+	let messages = conversation;
+	if messages.is_empty() {
+		ctx.reply(EMPTY_REPLY_MSG).await?;
+		return Ok(());
+	}
+
+	let avatars = fetch_avatars(&messages).await?;
+	let font = FONTS.get(CONTENT_FONT).unwrap().clone();
+	let (tx, rx) = oneshot::channel();
+	spawn(move || {
+		let result = conversation_image(
+			&messages,
+			&avatars,
+			&font,
+			Vec::with_capacity(STATIC_QUOTE_VEC),
+		);
+		if tx.send(result).is_err() {
+			warn!("Sender failed to send result");
+		}
+	});
+	let image = rx
+		.await
+		.context("Rayon task for conversation image panicked")??;
+	let filename = if image.animated {
+		"conversation.gif"
+	} else {
+		"conversation.avif"
+	};
+
+	if let Err(err) = record_quote(
+		i64::from(guild_id),
+		i64::from(ctx.author().id),
+		&ctx.data().db,
+	)
+	.await
+	{
+		warn!("Failed to record quote: {err}");
+	}
+	ctx.send(
+		CreateReply::new()
+			.reply(true)
+			.attachment(CreateAttachment::bytes(image.bytes, filename)),
+	)
+	.await?;
+
+	Ok(())
+}
+
 #[poise::command(
 	prefix_command,
 	guild_only,
@@ -1,20 +1,34 @@
+use std::{fmt::Write as _, time::Duration};
+
 use fabsebot_core::{
 	config::{
-		constants::{FAILED_SONG_FETCH, MISSING_REPLY_MSG, QUEUEING_MSG},
+		constants::{FAILED_SONG_FETCH, MESSAGE_LIMIT, MISSING_REPLY_MSG, QUEUEING_MSG},
 		types::{ContextType, Error, SContext},
 	},
 	errors::commands::AIError,
 	utils::{
 		ai::ai_voice,
-		helpers::url_bytes,
+		helpers::{paginate_container, text_display, url_bytes},
 		voice::{
-			ALREADY_IN_VOICE_CHAN_MSG, PayloadType, add_payload, add_youtube_song,
-			check_in_channel, lavalink_play, lavalink_try_join, remove_handler, try_voice,
+			ALREADY_IN_VOICE_CHAN_MSG, AudioFilter, PayloadType, SeekType, add_payload, add_song,
+			check_in_channel, control_denial, format_timestamp, lavalink_play, lavalink_try_join,
+			leave_voice_channel, seek_current, set_filter, set_volume, try_voice,
 		},
 	},
 };
-use poise::CreateReply;
-use serenity::{all::MessageId, model::channel::Attachment};
+use fabsebot_db::{
+	guild::fetch_music_settings,
+	music::{
+		fetch_busiest_hours, fetch_play_summary, fetch_top_artists, fetch_top_requesters,
+		fetch_top_tracks,
+	},
+};
+use poise::{ChoiceParameter, CreateReply};
+use serenity::{
+	all::{Colour, CreateContainer, MessageId, User},
+	model::channel::Attachment,
+};
+use sqlx::types::time::{Duration as TimeDuration, OffsetDateTime};
 
 /// Text to voice, duh
 #[poise::command(
@@ -38,7 +52,7 @@ pub async fn text_to_voice(ctx: SContext<'_>, input: Option<String>) -> Result<(
 		ctx.reply(MISSING_REPLY_MSG).await?;
 		return Ok(());
 	};
-	let Some((_typing, guild_id, handler_lock)) = try_voice(ctx, false).await? else {
+	let Some((_typing, guild_id, handler_lock)) = try_voice(ctx).await? else {
 		return Ok(());
 	};
 	let bytes = match ai_voice(&payload).await {
@@ -68,17 +82,12 @@ pub async fn text_to_voice(ctx: SContext<'_>, input: Option<String>) -> Result<(
 	required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | SEND_MESSAGES_IN_THREADS | SPEAK | \
 	                            CONNECT"
 )]
-pub async fn join_voice_old(
-	ctx: SContext<'_>,
-	#[description = "Allow music playback across guilds"]
-	#[flag]
-	global: bool,
-) -> Result<(), Error> {
+pub async fn join_voice_old(ctx: SContext<'_>) -> Result<(), Error> {
 	if check_in_channel(ctx, false).is_none() {
 		ctx.reply(ALREADY_IN_VOICE_CHAN_MSG).await?;
 		return Ok(());
 	}
-	try_voice(ctx, global).await?;
+	try_voice(ctx).await?;
 	Ok(())
 }
 
@@ -92,7 +101,7 @@ pub async fn join_voice_old(
 )]
 pub async fn leave_voice(ctx: SContext<'_>) -> Result<(), Error> {
 	let guild_id = ctx.guild_id().unwrap();
-	if let Err(err) = remove_handler(guild_id).await {
+	if let Err(err) = leave_voice_channel(guild_id).await {
 		ctx.reply(
 			"Bruh, I'm not even in a voice channel!\nUse join_voice-command in a voice channel \
 			 first",
@@ -116,16 +125,17 @@ pub async fn leave_voice(ctx: SContext<'_>) -> Result<(), Error> {
 )]
 pub async fn play_song_old(
 	ctx: SContext<'_>,
-	#[description = "YouTube link or query to search"]
+	#[description = "Song link (YouTube, SoundCloud, Bandcamp, Spotify, Apple Music, Deezer) or \
+	                 query"]
 	#[rest]
 	url: String,
 ) -> Result<(), Error> {
-	let Some((_typing, guild_id, handler_lock)) = try_voice(ctx, false).await? else {
+	let Some((_typing, guild_id, handler_lock)) = try_voice(ctx).await? else {
 		return Ok(());
 	};
 	let reply = ctx.reply(QUEUEING_MSG).await?;
 	let msg = reply.message().await?;
-	if let Err(err) = add_youtube_song(
+	if let Err(err) = add_song(
 		url,
 		&handler_lock,
 		guild_id,
@@ -158,7 +168,13 @@ pub async fn join_voice(ctx: SContext<'_>) -> Result<(), Error> {
 		ctx.reply(ALREADY_IN_VOICE_CHAN_MSG).await?;
 		return Ok(());
 	};
-	lavalink_try_join(ContextType::Poise(ctx), guild_id, ctx.author().id).await?;
+	lavalink_try_join(
+		ContextType::Poise(ctx),
+		guild_id,
+		ctx.channel_id(),
+		ctx.author().id,
+	)
+	.await?;
 	Ok(())
 }
 
@@ -172,13 +188,19 @@ pub async fn join_voice(ctx: SContext<'_>) -> Result<(), Error> {
 )]
 pub async fn play_song(
 	ctx: SContext<'_>,
-	#[description = "YouTube link to song or playlist OR query to search"]
+	#[description = "Song or playlist link (YouTube, SoundCloud, Spotify, Deezer, etc.) OR query \
+	                 to search"]
 	#[rest]
 	url: String,
 ) -> Result<(), Error> {
 	let guild_id = ctx.guild_id().unwrap();
-	let Some((_typing, player_context)) =
-		lavalink_try_join(ContextType::Poise(ctx), guild_id, ctx.author().id).await?
+	let Some((_typing, player_context)) = lavalink_try_join(
+		ContextType::Poise(ctx),
+		guild_id,
+		ctx.channel_id(),
+		ctx.author().id,
+	)
+	.await?
 	else {
 		return Ok(());
 	};
@@ -191,6 +213,8 @@ pub async fn play_song(
 		msg.channel_id,
 		ctx.author().id,
 		&url,
+		PayloadType::Lavalink,
+		false,
 		player_context,
 		&ctx.data().db,
 	)
@@ -215,7 +239,7 @@ pub async fn play_file(ctx: SContext<'_>, attachment: Attachment) -> Result<(),
 	if let Some(content_type) = attachment.content_type.as_deref()
 		&& content_type.starts_with("audio")
 	{
-		let Some((_typing, guild_id, handler_lock)) = try_voice(ctx, false).await? else {
+		let Some((_typing, guild_id, handler_lock)) = try_voice(ctx).await? else {
 			return Ok(());
 		};
 		if let Ok(bytes) = url_bytes(&attachment.url).await {
@@ -230,3 +254,248 @@ pub async fn play_file(ctx: SContext<'_>, attachment: Attachment) -> Result<(),
 
 	Ok(())
 }
+
+#[derive(Clone, Copy, Default, ChoiceParameter)]
+enum StatsPeriod {
+	#[name = "7d"]
+	Week,
+	#[name = "30d"]
+	Month,
+	#[default]
+	#[name = "all"]
+	All,
+}
+
+impl StatsPeriod {
+	fn since(self) -> Option<OffsetDateTime> {
+		let days = match self {
+			Self::Week => 7,
+			Self::Month => 30,
+			Self::All => return None,
+		};
+		Some(OffsetDateTime::now_utc().saturating_sub(TimeDuration::days(days)))
+	}
+}
+
+/// Listening statistics of this server or a member
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "SEND_MESSAGES | SEND_MESSAGES_IN_THREADS"
+)]
+pub async fn music_stats(
+	ctx: SContext<'_>,
+	#[description = "Time period to look at"] period: Option<StatsPeriod>,
+	#[description = "Only count songs requested by this user"] user: Option<User>,
+) -> Result<(), Error> {
+	let guild_id = i64::from(ctx.guild_id().unwrap());
+	let user_id = user.as_ref().map(|user| i64::from(user.id));
+	let period = period.unwrap_or_default();
+	let since = period.since();
+	let db = &ctx.data().db;
+
+	let summary = fetch_play_summary(guild_id, user_id, since, db).await?;
+	if summary.plays == 0 {
+		ctx.reply("No songs have been played in this period")
+			.await?;
+		return Ok(());
+	}
+
+	let subject = user.as_ref().map_or_else(
+		|| "this server".to_owned(),
+		|user| format!("<@{}>", user.id),
+	);
+	let mut pages = Vec::with_capacity(5);
+
+	let mut overview = format!("# Music stats for {subject} ({})\n", period.name());
+	writeln!(overview, "**Songs played:** {}", summary.plays)?;
+	let minutes = summary.listening_secs.saturating_div(60);
+	let hours = minutes.saturating_div(60);
+	writeln!(
+		overview,
+		"**Listening time:** {hours}h {}m",
+		minutes.saturating_sub(hours.saturating_mul(60))
+	)?;
+	pages.push(overview);
+
+	let mut tracks = String::from("# Top songs\n");
+	for (rank, track) in fetch_top_tracks(guild_id, user_id, since, db)
+		.await?
+		.into_iter()
+		.enumerate()
+	{
+		writeln!(
+			tracks,
+			"{}. **{}** by {} - {} play(s)",
+			rank.saturating_add(1),
+			track.title,
+			track.artist,
+			track.plays
+		)?;
+	}
+	pages.push(tracks);
+
+	let mut artists = String::from("# Top artists\n");
+	for (rank, artist) in fetch_top_artists(guild_id, user_id, since, db)
+		.await?
+		.into_iter()
+		.enumerate()
+	{
+		writeln!(
+			artists,
+			"{}. **{}** - {} play(s)",
+			rank.saturating_add(1),
+			artist.artist,
+			artist.plays
+		)?;
+	}
+	pages.push(artists);
+
+	if user_id.is_none() {
+		let mut requesters = String::from("# Top requesters\n");
+		for (rank, requester) in fetch_top_requesters(guild_id, since, db)
+			.await?
+			.into_iter()
+			.enumerate()
+		{
+			writeln!(
+				requesters,
+				"{}. <@{}> - {} song(s)",
+				rank.saturating_add(1),
+				requester.requested_by,
+				requester.plays
+			)?;
+		}
+		pages.push(requesters);
+	}
+
+	let mut busiest_hours = String::from("# Busiest hours (UTC)\n");
+	for hour in fetch_busiest_hours(guild_id, user_id, since, db).await? {
+		writeln!(
+			busiest_hours,
+			"**{:02}:00-{:02}:59** - {} play(s)",
+			hour.hour, hour.hour, hour.plays
+		)?;
+	}
+	pages.push(busiest_hours);
+
+	paginate_container(
+		ctx,
+		&pages,
+		Duration::from_mins(2),
+		|page, idx, len| async move {
+			let mut text = page.clone();
+			write!(text, "\n-# Page {} of {len}", idx.saturating_add(1)).unwrap();
+			text.truncate(MESSAGE_LIMIT);
+			CreateContainer::new(vec![text_display(text)]).accent_colour(Colour::BLUE)
+		},
+	)
+	.await?;
+
+	Ok(())
+}
+
+/// Replies with the reason if the author may not control the player
+async fn control_denied(ctx: SContext<'_>) -> Result<bool, Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let settings = fetch_music_settings(i64::from(guild_id), &ctx.data().db)
+		.await?
+		.unwrap_or_default();
+	let member = ctx.author_member().await;
+	if let Some(denial) = control_denial(member.as_deref(), ctx.author().id, &settings, guild_id) {
+		ctx.reply(denial).await?;
+		return Ok(true);
+	}
+	Ok(false)
+}
+
+/// Seek to a timestamp or jump forward and backwards in the current song
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "SEND_MESSAGES | SEND_MESSAGES_IN_THREADS"
+)]
+pub async fn seek(
+	ctx: SContext<'_>,
+	#[description = "Timestamp like 1:23, or a jump like +30s or -1m"]
+	#[rest]
+	position: String,
+) -> Result<(), Error> {
+	let Some(seek_type) = SeekType::parse(&position) else {
+		ctx.reply("Couldn't read that, try something like 1:23, +30s or -1m")
+			.await?;
+		return Ok(());
+	};
+	if control_denied(ctx).await? {
+		return Ok(());
+	}
+	if let Some(position) = seek_current(&ctx.data(), ctx.guild_id().unwrap(), &seek_type).await? {
+		ctx.reply(format!("Jumped to {}", format_timestamp(position)))
+			.await?;
+	} else {
+		ctx.reply("Nothing is playing right now").await?;
+	}
+
+	Ok(())
+}
+
+/// Change the music volume for the rest of the session
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "SEND_MESSAGES | SEND_MESSAGES_IN_THREADS"
+)]
+pub async fn volume(
+	ctx: SContext<'_>,
+	#[description = "Volume in percent, 100 is the default"]
+	#[min = 0]
+	#[max = 200]
+	percent: u16,
+) -> Result<(), Error> {
+	if control_denied(ctx).await? {
+		return Ok(());
+	}
+	let percent = percent.min(200);
+	if set_volume(&ctx.data(), ctx.guild_id().unwrap(), percent).await? {
+		ctx.reply(format!("Volume set to {percent}%")).await?;
+	} else {
+		ctx.reply("Bruh, I'm not even playing anything").await?;
+	}
+
+	Ok(())
+}
+
+/// Apply an audio filter for the rest of the session
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "SEND_MESSAGES | SEND_MESSAGES_IN_THREADS"
+)]
+pub async fn filter(
+	ctx: SContext<'_>,
+	#[description = "Filter to apply, filters of a different kind stack"] filter: AudioFilter,
+) -> Result<(), Error> {
+	if control_denied(ctx).await? {
+		return Ok(());
+	}
+	match set_filter(&ctx.data(), ctx.guild_id().unwrap(), filter).await? {
+		Some(active) if active.is_empty() => {
+			ctx.reply("Cleared every filter").await?;
+		}
+		Some(active) => {
+			let names: Vec<_> = active.iter().map(|filter| filter.name()).collect();
+			ctx.reply(format!("Active filters: {}", names.join(", ")))
+				.await?;
+		}
+		None => {
+			ctx.reply("Filters only work with songs queued through play_song")
+				.await?;
+		}
+	}
+
+	Ok(())
+}
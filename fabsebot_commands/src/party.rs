@@ -0,0 +1,157 @@
+use std::fmt::Write as _;
+
+use fabsebot_core::{
+	config::types::{Error, SContext},
+	utils::voice::{
+		party::{PartyLeave, create_party, join_party, leave_party, party_info, sync_with_host},
+		party_denial,
+	},
+};
+use fabsebot_db::guild::fetch_music_settings;
+use poise::CreateReply;
+use serenity::all::GuildId;
+use tracing::warn;
+
+const NOT_CONNECTED_MSG: &str = "I have to be in a voice channel here first";
+
+fn guild_name(ctx: &SContext<'_>, guild_id: GuildId) -> String {
+	ctx.cache()
+		.guild(guild_id)
+		.map_or_else(|| guild_id.to_string(), |guild| guild.name.to_string())
+}
+
+/// Replies with the reason if the author may not manage the party
+async fn party_denied(ctx: &SContext<'_>) -> Result<bool, Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let settings = fetch_music_settings(i64::from(guild_id), &ctx.data().db)
+		.await?
+		.unwrap_or_default();
+	let member = ctx.author_member().await;
+	if let Some(denial) = party_denial(member.as_deref(), ctx.author().id, &settings, guild_id) {
+		ctx.send(CreateReply::new().content(denial).ephemeral(true))
+			.await?;
+		return Ok(true);
+	}
+	Ok(false)
+}
+
+/// Listen along with other servers, sharing one queue
+#[expect(clippy::unused_async)]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	subcommands("start", "join", "leave", "info"),
+	subcommand_required
+)]
+pub async fn party(_ctx: SContext<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Host a listening party other servers can join with a code
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn start(ctx: SContext<'_>) -> Result<(), Error> {
+	if party_denied(&ctx).await? {
+		return Ok(());
+	}
+	let guild_id = ctx.guild_id().unwrap();
+	let Some(guild_cache) = ctx
+		.data()
+		.guilds
+		.get(&guild_id)
+		.filter(|guild_cache| !guild_cache.music_data.is_disconnected())
+	else {
+		ctx.reply(NOT_CONNECTED_MSG).await?;
+		return Ok(());
+	};
+	let response = match create_party(&guild_cache, guild_id) {
+		Ok(code) => format!(
+			"Started a listening party! Other servers can join with `/party join {code}`\nOnly \
+			 this server controls the music, but everyone can queue songs"
+		),
+		Err(reason) => reason.to_owned(),
+	};
+	ctx.reply(response).await?;
+	Ok(())
+}
+
+/// Join the listening party of another server
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn join(
+	ctx: SContext<'_>,
+	#[description = "Join code shared by the host"] code: String,
+) -> Result<(), Error> {
+	if party_denied(&ctx).await? {
+		return Ok(());
+	}
+	let guild_id = ctx.guild_id().unwrap();
+	let Some(guild_cache) = ctx
+		.data()
+		.guilds
+		.get(&guild_id)
+		.filter(|guild_cache| !guild_cache.music_data.is_disconnected())
+	else {
+		ctx.reply(NOT_CONNECTED_MSG).await?;
+		return Ok(());
+	};
+	let response = match join_party(&guild_cache, guild_id, &code) {
+		Ok(host) => {
+			if let Err(err) = sync_with_host(ctx.data().as_ref(), guild_id, host).await {
+				warn!("Failed to sync with the listening party host: {err}");
+			}
+			format!(
+				"Joined the listening party of **{}**, now playing whatever they're playing",
+				guild_name(&ctx, host)
+			)
+		}
+		Err(reason) => reason.to_owned(),
+	};
+	ctx.reply(response).await?;
+	Ok(())
+}
+
+/// Leave the listening party, closing it if this server hosts it
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn leave(ctx: SContext<'_>) -> Result<(), Error> {
+	if party_denied(&ctx).await? {
+		return Ok(());
+	}
+	let response = match leave_party(ctx.guild_id().unwrap()) {
+		Some(PartyLeave::Disbanded(guests)) => format!(
+			"Closed the listening party, {} other server(s) got dropped",
+			guests.len()
+		),
+		Some(PartyLeave::Left) => "Left the listening party".to_owned(),
+		None => "This server isn't in a listening party".to_owned(),
+	};
+	ctx.reply(response).await?;
+	Ok(())
+}
+
+/// Show the join code and members of the listening party
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn info(ctx: SContext<'_>) -> Result<(), Error> {
+	let Some((code, host, guests)) = party_info(ctx.guild_id().unwrap()) else {
+		ctx.send(
+			CreateReply::new()
+				.content("This server isn't in a listening party")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	};
+	let mut response = format!(
+		"# Listening party `{code}`\n**Host:** {}\n",
+		guild_name(&ctx, host)
+	);
+	if guests.is_empty() {
+		response.push_str("*No other servers joined yet*");
+	} else {
+		response.push_str("**Guests:**\n");
+		for guest in guests {
+			writeln!(response, "- {}", guild_name(&ctx, guest))?;
+		}
+	}
+	ctx.reply(response).await?;
+	Ok(())
+}
@@ -0,0 +1,581 @@
+use std::{fmt::Write as _, time::Duration};
+
+use fabsebot_core::{
+	config::{
+		constants::{FAILED_SONG_FETCH, MESSAGE_LIMIT, QUEUEING_MSG},
+		types::{ContextType, Error, SContext},
+	},
+	utils::{
+		helpers::{paginate_container, text_display, url_bytes},
+		voice::{current_track_uuid, lavalink_play_many, lavalink_try_join, resolve_tracks},
+	},
+};
+use fabsebot_db::music::{
+	PlaylistOwner, add_playlist_tracks, create_playlist, delete_playlist, fetch_playlist_id,
+	fetch_playlist_tracks, fetch_playlists,
+};
+use poise::{ChoiceParameter, CreateReply};
+use serenity::all::{
+	Attachment, AutocompleteChoice, Colour, CreateAttachment, CreateAutocompleteResponse,
+	CreateContainer,
+};
+use sqlx::types::Uuid;
+use tracing::warn;
+
+const PLAYLIST_TRACK_LIMIT: i64 = 500;
+const IMPORT_LINE_LIMIT: usize = 100;
+const IMPORT_SIZE_LIMIT: u32 = 64 * 1024;
+const PLAYLIST_NAME_LIMIT: usize = 64;
+const PLAYLISTS_PER_PAGE: usize = 20;
+const AUTOCOMPLETE_LIMIT: usize = 25;
+
+#[derive(Clone, Copy, Default, ChoiceParameter)]
+enum PlaylistScope {
+	#[default]
+	#[name = "personal"]
+	Personal,
+	#[name = "server"]
+	Server,
+}
+
+#[derive(Clone, Copy, Default, ChoiceParameter)]
+enum PlaylistFormat {
+	#[default]
+	#[name = "m3u"]
+	M3u,
+	#[name = "plain"]
+	Plain,
+}
+
+fn playlist_owner(ctx: &SContext<'_>, scope: Option<PlaylistScope>) -> PlaylistOwner {
+	match scope.unwrap_or_default() {
+		PlaylistScope::Personal => PlaylistOwner::User(i64::from(ctx.author().id)),
+		PlaylistScope::Server => PlaylistOwner::Guild(i64::from(ctx.guild_id().unwrap())),
+	}
+}
+
+async fn can_edit(ctx: &SContext<'_>, owner: PlaylistOwner) -> Result<bool, Error> {
+	if matches!(owner, PlaylistOwner::User(_)) {
+		return Ok(true);
+	}
+	let allowed = ctx
+		.author_member()
+		.await
+		.and_then(|member| member.permissions)
+		.is_some_and(|permissions| permissions.manage_guild());
+	if !allowed {
+		ctx.send(
+			CreateReply::new()
+				.content("Only server managers can edit server playlists")
+				.ephemeral(true),
+		)
+		.await?;
+	}
+	Ok(allowed)
+}
+
+async fn existing_playlist(
+	ctx: &SContext<'_>,
+	owner: PlaylistOwner,
+	name: &str,
+) -> Result<Option<i64>, Error> {
+	let playlist_id = fetch_playlist_id(owner, name, &ctx.data().db).await?;
+	if playlist_id.is_none() {
+		ctx.send(
+			CreateReply::new()
+				.content(format!("No playlist called **{name}** found"))
+				.ephemeral(true),
+		)
+		.await?;
+	}
+	Ok(playlist_id)
+}
+
+/// Trims the name, telling the user off if it's empty or too long
+async fn valid_name<'a>(ctx: &SContext<'_>, name: &'a str) -> Result<Option<&'a str>, Error> {
+	let name = name.trim();
+	if name.is_empty() || name.chars().count() > PLAYLIST_NAME_LIMIT {
+		ctx.send(
+			CreateReply::new()
+				.content(format!(
+					"Playlist names must be 1-{PLAYLIST_NAME_LIMIT} characters long"
+				))
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(None);
+	}
+	Ok(Some(name))
+}
+
+async fn append_tracks(
+	ctx: &SContext<'_>,
+	playlist_id: i64,
+	track_uuids: &[Uuid],
+) -> Result<usize, Error> {
+	if track_uuids.is_empty() {
+		return Ok(0);
+	}
+	let added = add_playlist_tracks(
+		playlist_id,
+		track_uuids,
+		PLAYLIST_TRACK_LIMIT,
+		&ctx.data().db,
+	)
+	.await?;
+	Ok(usize::try_from(added).unwrap_or(usize::MAX))
+}
+
+async fn autocomplete_playlist<'a>(
+	ctx: SContext<'_>,
+	partial: &'a str,
+) -> CreateAutocompleteResponse<'a> {
+	let db = &ctx.data().db;
+	let mut owners = vec![PlaylistOwner::User(i64::from(ctx.author().id))];
+	if let Some(guild_id) = ctx.guild_id() {
+		owners.push(PlaylistOwner::Guild(i64::from(guild_id)));
+	}
+	let mut names: Vec<String> = Vec::new();
+	for owner in owners {
+		if let Ok(playlists) =
+			fetch_playlists(owner, partial, i64::try_from(AUTOCOMPLETE_LIMIT).ok(), db).await
+		{
+			for playlist in playlists {
+				if !names.contains(&playlist.name) {
+					names.push(playlist.name);
+				}
+			}
+		}
+	}
+	let choices: Vec<_> = names
+		.into_iter()
+		.take(AUTOCOMPLETE_LIMIT)
+		.map(AutocompleteChoice::from)
+		.collect();
+	CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+/// Saved personal and server playlists
+#[expect(clippy::unused_async)]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	subcommands(
+		"create",
+		"add_current",
+		"add_url",
+		"list",
+		"delete",
+		"queue",
+		"import",
+		"export"
+	),
+	subcommand_required
+)]
+pub async fn playlist(_ctx: SContext<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Create a new empty playlist
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn create(
+	ctx: SContext<'_>,
+	#[description = "Name of the playlist"] name: String,
+	#[description = "Personal or server playlist"] scope: Option<PlaylistScope>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	if !can_edit(&ctx, owner).await? {
+		return Ok(());
+	}
+	let Some(name) = valid_name(&ctx, &name).await? else {
+		return Ok(());
+	};
+	let response = if create_playlist(owner, name, &ctx.data().db)
+		.await?
+		.is_some()
+	{
+		format!("Created playlist **{name}**")
+	} else {
+		format!("A playlist called **{name}** already exists")
+	};
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
+
+/// Add the song that's currently playing to a playlist
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn add_current(
+	ctx: SContext<'_>,
+	#[description = "Name of the playlist"]
+	#[autocomplete = "autocomplete_playlist"]
+	name: String,
+	#[description = "Personal or server playlist"] scope: Option<PlaylistScope>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	if !can_edit(&ctx, owner).await? {
+		return Ok(());
+	}
+	let Some(playlist_id) = existing_playlist(&ctx, owner, &name).await? else {
+		return Ok(());
+	};
+	let Some(track_uuid) = current_track_uuid(ctx.data().as_ref(), ctx.guild_id().unwrap()).await
+	else {
+		ctx.send(
+			CreateReply::new()
+				.content("Nothing saveable is playing right now")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	};
+	let response = if append_tracks(&ctx, playlist_id, &[track_uuid]).await? == 0 {
+		format!("**{name}** is full ({PLAYLIST_TRACK_LIMIT} songs)")
+	} else {
+		format!("Added the current song to **{name}**")
+	};
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
+
+/// Add a song, YouTube playlist or search result to a playlist
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn add_url(
+	ctx: SContext<'_>,
+	#[description = "Name of the playlist"]
+	#[autocomplete = "autocomplete_playlist"]
+	name: String,
+	#[description = "YouTube link to song or playlist OR query to search"] url: String,
+	#[description = "Personal or server playlist"] scope: Option<PlaylistScope>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	if !can_edit(&ctx, owner).await? {
+		return Ok(());
+	}
+	let Some(playlist_id) = existing_playlist(&ctx, owner, &name).await? else {
+		return Ok(());
+	};
+	ctx.defer_ephemeral().await?;
+	let Ok(track_uuids) = resolve_tracks(ctx.data().as_ref(), ctx.guild_id().unwrap(), &url).await
+	else {
+		ctx.send(
+			CreateReply::new()
+				.content(FAILED_SONG_FETCH)
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	};
+	let added = append_tracks(&ctx, playlist_id, &track_uuids).await?;
+	ctx.send(
+		CreateReply::new()
+			.content(format!("Added {added} song(s) to **{name}**"))
+			.ephemeral(true),
+	)
+	.await?;
+	Ok(())
+}
+
+/// List saved playlists
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn list(
+	ctx: SContext<'_>,
+	#[description = "Personal or server playlists"] scope: Option<PlaylistScope>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	let playlists = fetch_playlists(owner, "", None, &ctx.data().db).await?;
+	if playlists.is_empty() {
+		ctx.send(
+			CreateReply::new()
+				.content("No playlists saved yet, create one with /playlist create")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	}
+	let pages: Vec<String> = playlists
+		.chunks(PLAYLISTS_PER_PAGE)
+		.map(|chunk| {
+			let mut text = String::from("# Playlists\n");
+			for playlist in chunk {
+				writeln!(
+					text,
+					"**{}** - {} song(s)",
+					playlist.name, playlist.track_count
+				)
+				.unwrap();
+			}
+			text
+		})
+		.collect();
+	paginate_container(
+		ctx,
+		&pages,
+		Duration::from_mins(2),
+		|page, idx, len| async move {
+			let mut text = page.clone();
+			write!(text, "\n-# Page {} of {len}", idx.saturating_add(1)).unwrap();
+			text.truncate(MESSAGE_LIMIT);
+			CreateContainer::new(vec![text_display(text)]).accent_colour(Colour::BLUE)
+		},
+	)
+	.await?;
+	Ok(())
+}
+
+/// Delete a playlist
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn delete(
+	ctx: SContext<'_>,
+	#[description = "Name of the playlist"]
+	#[autocomplete = "autocomplete_playlist"]
+	name: String,
+	#[description = "Personal or server playlist"] scope: Option<PlaylistScope>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	if !can_edit(&ctx, owner).await? {
+		return Ok(());
+	}
+	let response = if delete_playlist(owner, &name, &ctx.data().db)
+		.await?
+		.rows_affected()
+		== 0
+	{
+		format!("No playlist called **{name}** found")
+	} else {
+		format!("Deleted playlist **{name}**")
+	};
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
+
+/// Add every song of a playlist to the queue
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | SEND_MESSAGES_IN_THREADS | SPEAK | \
+	                            CONNECT"
+)]
+async fn queue(
+	ctx: SContext<'_>,
+	#[description = "Name of the playlist"]
+	#[autocomplete = "autocomplete_playlist"]
+	name: String,
+	#[description = "Personal or server playlist"] scope: Option<PlaylistScope>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	let Some(playlist_id) = existing_playlist(&ctx, owner, &name).await? else {
+		return Ok(());
+	};
+	let tracks = fetch_playlist_tracks(playlist_id, &ctx.data().db).await?;
+	if tracks.is_empty() {
+		ctx.send(
+			CreateReply::new()
+				.content(format!("**{name}** doesn't have any songs yet"))
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	}
+	let guild_id = ctx.guild_id().unwrap();
+	let Some((_typing, player_context)) = lavalink_try_join(
+		ContextType::Poise(ctx),
+		guild_id,
+		ctx.channel_id(),
+		ctx.author().id,
+	)
+	.await?
+	else {
+		return Ok(());
+	};
+	let reply = ctx.reply(QUEUEING_MSG).await?;
+	let msg = reply.message().await?;
+	let urls: Vec<&str> = tracks
+		.iter()
+		.map(|track| track.source_url.as_str())
+		.collect();
+	let failed = match lavalink_play_many(
+		ctx.serenity_context(),
+		guild_id,
+		msg.id,
+		msg.channel_id,
+		ctx.author().id,
+		&urls,
+		player_context,
+		&ctx.data().db,
+	)
+	.await
+	{
+		Ok(failed) => failed,
+		Err(err) => {
+			warn!("Failed to queue playlist {name}: {err}");
+			tracks.len()
+		}
+	};
+	if failed == tracks.len() {
+		reply
+			.edit(ctx, CreateReply::new().content(FAILED_SONG_FETCH))
+			.await?;
+	} else if failed > 0 {
+		reply
+			.edit(
+				ctx,
+				CreateReply::new().content(format!(
+					"Queued **{name}**, but {failed} song(s) couldn't be loaded"
+				)),
+			)
+			.await?;
+	}
+	Ok(())
+}
+
+fn parse_import(content: &str) -> Vec<&str> {
+	content
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.take(IMPORT_LINE_LIMIT)
+		.collect()
+}
+
+/// Import songs into a playlist from a plain URL list or M3U file
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn import(
+	ctx: SContext<'_>,
+	#[description = "Name of the playlist, created if missing"]
+	#[autocomplete = "autocomplete_playlist"]
+	name: String,
+	#[description = "Text or M3U file with one link per line"] file: Attachment,
+	#[description = "Personal or server playlist"] scope: Option<PlaylistScope>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	if !can_edit(&ctx, owner).await? {
+		return Ok(());
+	}
+	let Some(name) = valid_name(&ctx, &name).await? else {
+		return Ok(());
+	};
+	if file.size > IMPORT_SIZE_LIMIT {
+		ctx.send(
+			CreateReply::new()
+				.content("That file is too big to be a playlist")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	}
+	ctx.defer_ephemeral().await?;
+	let Ok(bytes) = url_bytes(&file.url).await else {
+		ctx.send(
+			CreateReply::new()
+				.content("Failed to fetch attachment :/")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	};
+	let Ok(content) = str::from_utf8(&bytes) else {
+		ctx.send(
+			CreateReply::new()
+				.content("That file isn't a text or M3U playlist")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	};
+	let lines = parse_import(content);
+	if lines.is_empty() {
+		ctx.send(
+			CreateReply::new()
+				.content("That file doesn't have any links in it")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	}
+	let db = &ctx.data().db;
+	let playlist_id = match fetch_playlist_id(owner, name, db).await? {
+		Some(playlist_id) => playlist_id,
+		None => {
+			let Some(playlist_id) = create_playlist(owner, name, db).await? else {
+				ctx.send(
+					CreateReply::new()
+						.content(format!("Failed to create playlist **{name}** :/"))
+						.ephemeral(true),
+				)
+				.await?;
+				return Ok(());
+			};
+			playlist_id
+		}
+	};
+	let guild_id = ctx.guild_id().unwrap();
+	let mut track_uuids = Vec::new();
+	let mut failed = 0_usize;
+	for line in lines {
+		match resolve_tracks(ctx.data().as_ref(), guild_id, line).await {
+			Ok(uuids) => track_uuids.extend(uuids),
+			Err(_) => failed = failed.saturating_add(1),
+		}
+	}
+	let added = append_tracks(&ctx, playlist_id, &track_uuids).await?;
+	let mut response = format!("Imported {added} song(s) into **{name}**");
+	if failed > 0 {
+		write!(response, ", {failed} line(s) couldn't be loaded").unwrap();
+	}
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
+
+/// Export a playlist as an M3U or plain URL list
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn export(
+	ctx: SContext<'_>,
+	#[description = "Name of the playlist"]
+	#[autocomplete = "autocomplete_playlist"]
+	name: String,
+	#[description = "Personal or server playlist"] scope: Option<PlaylistScope>,
+	#[description = "File format"] format: Option<PlaylistFormat>,
+) -> Result<(), Error> {
+	let owner = playlist_owner(&ctx, scope);
+	let Some(playlist_id) = existing_playlist(&ctx, owner, &name).await? else {
+		return Ok(());
+	};
+	let tracks = fetch_playlist_tracks(playlist_id, &ctx.data().db).await?;
+	let (content, extension) = match format.unwrap_or_default() {
+		PlaylistFormat::M3u => {
+			let mut content = String::from("#EXTM3U\n");
+			for track in &tracks {
+				writeln!(
+					content,
+					"#EXTINF:{},{} - {}\n{}",
+					track.duration_sec, track.artist, track.title, track.source_url
+				)
+				.unwrap();
+			}
+			(content, "m3u")
+		}
+		PlaylistFormat::Plain => {
+			let mut content = String::new();
+			for track in &tracks {
+				writeln!(content, "{}", track.source_url).unwrap();
+			}
+			(content, "txt")
+		}
+	};
+	ctx.send(
+		CreateReply::new()
+			.attachment(CreateAttachment::bytes(
+				content.into_bytes(),
+				format!("{name}.{extension}"),
+			))
+			.ephemeral(true),
+	)
+	.await?;
+	Ok(())
+}
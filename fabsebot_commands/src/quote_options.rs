@@ -0,0 +1,59 @@
+use fabsebot_core::config::constants::{
+	GUILD_OPTION_LIMIT, GUILD_OPTION_NAME_LIMIT, SELECT_MENU_LIMIT,
+};
+use serenity::all::{AutocompleteChoice, CreateAutocompleteResponse};
+
+/// Fonts and themes servers add to the quote's select menus
+#[derive(Clone, Copy)]
+pub enum QuoteOption {
+	Font,
+	Theme,
+}
+
+impl QuoteOption {
+	const fn name(self) -> &'static str {
+		match self {
+			Self::Font => "font",
+			Self::Theme => "theme",
+		}
+	}
+
+	/// Why the name can't be given to a new one, if anything
+	pub fn name_rejection(self, name: &str, built_in: bool) -> Option<String> {
+		if name.is_empty() || name.chars().count() > GUILD_OPTION_NAME_LIMIT {
+			let kind = match self {
+				Self::Font => "Font",
+				Self::Theme => "Theme",
+			};
+			Some(format!(
+				"{kind} names must be 1-{GUILD_OPTION_NAME_LIMIT} characters long"
+			))
+		} else if built_in {
+			Some(format!("**{name}** is already a built-in {}", self.name()))
+		} else {
+			None
+		}
+	}
+
+	/// Turns away new ones once the server has as many as it may, replacing
+	/// an existing one is always fine
+	pub fn limit_rejection(self, exists: bool, count: i64) -> Option<String> {
+		(!exists && count >= GUILD_OPTION_LIMIT).then(|| {
+			format!(
+				"This server already has {GUILD_OPTION_LIMIT} {}s, delete one first",
+				self.name()
+			)
+		})
+	}
+}
+
+/// Suggests the saved names starting with what was typed so far
+pub fn name_choices<'a>(names: Vec<String>, partial: &str) -> CreateAutocompleteResponse<'a> {
+	let choices: Vec<_> = names
+		.into_iter()
+		.filter(|name| name.starts_with(partial))
+		.take(SELECT_MENU_LIMIT)
+		.map(AutocompleteChoice::from)
+		.collect();
+	CreateAutocompleteResponse::new().set_choices(choices)
+}
@@ -0,0 +1,41 @@
+use fabsebot_core::{
+	config::types::{Error, SContext},
+	utils::voice::recording::{start_recording, stop_recording},
+};
+use poise::CreateReply;
+
+/// Record the voice channel, only for those who consent
+#[expect(clippy::unused_async)]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	subcommands("start", "stop"),
+	subcommand_required
+)]
+pub async fn record(_ctx: SContext<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Start recording the voice channel I'm in
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | SEND_MESSAGES_IN_THREADS | \
+	                            ATTACH_FILES"
+)]
+async fn start(ctx: SContext<'_>) -> Result<(), Error> {
+	start_recording(ctx).await?;
+	Ok(())
+}
+
+/// Stop the recording and upload it
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn stop(ctx: SContext<'_>) -> Result<(), Error> {
+	let response = stop_recording(ctx.guild_id().unwrap(), ctx.author().id)
+		.unwrap_or("Stopping the recording, hold on");
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
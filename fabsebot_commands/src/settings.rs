@@ -15,14 +15,16 @@ use fabsebot_core::{
 		thumbnail_section,
 	},
 };
-use fabsebot_db::guild::{reset_guild, set_music_channel, set_spoiler_channel};
+use fabsebot_db::guild::{
+	MusicSettings, reset_guild, set_music_channel, set_spoiler_channel, update_music_settings,
+};
 use poise::CreateReply;
 use serde::Serialize;
 use serenity::{
 	all::{
 		ButtonStyle, Colour, ComponentInteractionCollector, ComponentInteractionDataKind,
 		CreateActionRow, CreateButton, CreateComponent, CreateContainer, CreateInteractionResponse,
-		CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, GuildId,
+		CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, GuildId, Role,
 	},
 	builder::{CreateContainerComponent, CreateSection},
 	futures::StreamExt as _,
@@ -531,6 +533,74 @@ pub async fn set_chatbot_options(
 	Ok(())
 }
 
+/// Configure who may control the music player and how; an empty field forces
+/// the default value
+#[poise::command(
+	slash_command,
+	guild_only,
+	required_permissions = "ADMINISTRATOR | MODERATE_MEMBERS",
+	required_bot_permissions = "SEND_MESSAGES | SEND_MESSAGES_IN_THREADS"
+)]
+pub async fn set_music_options(
+	ctx: SContext<'_>,
+	#[description = "Role allowed to use every music control; if not set, everyone in voice can"]
+	dj_role: Option<Role>,
+	#[description = "Whether requesters may skip their own songs without a vote"]
+	requester_skip: Option<bool>,
+	#[description = "Share of listeners in percent needed to vote skip a song"]
+	#[min = 1]
+	#[max = 100]
+	vote_skip_percent: Option<u8>,
+	#[description = "Minutes before leaving when the queue is empty or nobody listens; 0 never \
+	                 leaves"]
+	#[max = 120]
+	idle_timeout: Option<u8>,
+	#[description = "Seconds the seek buttons jump forward or backwards"]
+	#[min = 1]
+	#[max = 300]
+	seek_step: Option<u16>,
+	#[description = "Whether the queue gets resumed after the bot restarts"]
+	resume_sessions: Option<bool>,
+	#[description = "Loudness in LUFS that uploaded files and text to voice get evened out to"]
+	#[min = -40]
+	#[max = -5]
+	target_loudness: Option<i8>,
+	#[description = "Seconds until requests in the music channel get deleted; 0 keeps them"]
+	#[max = 3600]
+	request_cleanup: Option<u16>,
+) -> Result<(), Error> {
+	let defaults = MusicSettings::default();
+	let settings = MusicSettings {
+		music_dj_role: dj_role.map(|role| i64::from(role.id)),
+		music_requester_skip: requester_skip.unwrap_or(defaults.music_requester_skip),
+		music_vote_skip_percent: vote_skip_percent
+			.map_or(defaults.music_vote_skip_percent, i16::from),
+		music_idle_timeout: idle_timeout.map_or(defaults.music_idle_timeout, i16::from),
+		music_seek_step: seek_step
+			.and_then(|seek_step| i16::try_from(seek_step).ok())
+			.unwrap_or(defaults.music_seek_step),
+		music_resume: resume_sessions.unwrap_or(defaults.music_resume),
+		music_target_lufs: target_loudness.map_or(defaults.music_target_lufs, i16::from),
+		music_request_cleanup: request_cleanup
+			.and_then(|request_cleanup| i16::try_from(request_cleanup).ok())
+			.unwrap_or(defaults.music_request_cleanup),
+	};
+	update_music_settings(
+		i64::from(ctx.guild_id().unwrap()),
+		&settings,
+		&ctx.data().db,
+	)
+	.await?;
+	ctx.send(
+		CreateReply::new()
+			.content("Options for music controls set... probably")
+			.ephemeral(true),
+	)
+	.await?;
+
+	Ok(())
+}
+
 async fn set_dead_chat(
 	ctx: SContext<'_>,
 	channel_id: GenericChannelId,
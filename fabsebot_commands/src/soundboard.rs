@@ -0,0 +1,206 @@
+use fabsebot_core::{
+	config::types::{Error, SContext},
+	utils::{
+		helpers::{reply_container, url_bytes},
+		voice::{
+			soundboard::{
+				SOUND_LIMIT, SOUND_MAX_BYTES, SOUND_MAX_DURATION, SOUND_MAX_MIB, SOUND_NAME_LIMIT,
+				forget_sound, play_sound, soundboard_container, validate_sound,
+			},
+			try_voice,
+		},
+	},
+};
+use fabsebot_db::music::{delete_sound, fetch_sounds, insert_sound, sound_count, sound_exists};
+use poise::CreateReply;
+use serenity::{
+	all::{Attachment, AutocompleteChoice, CreateAutocompleteResponse},
+	builder::CreateComponent,
+};
+
+async fn autocomplete_sound<'a>(
+	ctx: SContext<'_>,
+	partial: &'a str,
+) -> CreateAutocompleteResponse<'a> {
+	let Some(guild_id) = ctx.guild_id() else {
+		return CreateAutocompleteResponse::new();
+	};
+	let choices: Vec<_> = fetch_sounds(i64::from(guild_id), &ctx.data().db)
+		.await
+		.unwrap_or_default()
+		.into_iter()
+		.filter(|sound| sound.name.starts_with(partial))
+		.take(25)
+		.map(|sound| AutocompleteChoice::from(sound.name))
+		.collect();
+	CreateAutocompleteResponse::new().set_choices(choices)
+}
+
+/// Play a clip from the soundboard over the music, except while playing through Lavalink
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_bot_permissions = "VIEW_CHANNEL | SEND_MESSAGES | SEND_MESSAGES_IN_THREADS | SPEAK | \
+	                            CONNECT"
+)]
+pub async fn sound(
+	ctx: SContext<'_>,
+	#[description = "Name of the clip"]
+	#[autocomplete = "autocomplete_sound"]
+	#[rest]
+	name: String,
+) -> Result<(), Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let connected = ctx
+		.data()
+		.guilds
+		.get(&guild_id)
+		.is_some_and(|guild_cache| !guild_cache.music_data.is_disconnected());
+	if !connected && try_voice(ctx).await?.is_none() {
+		return Ok(());
+	}
+	let response = play_sound(&ctx.data(), guild_id, name.trim(), ctx.author().id)
+		.await?
+		.map_or_else(|| format!("Playing **{}**", name.trim()), ToOwned::to_owned);
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
+
+/// Short clips anyone in voice can play over the music, join_voice_old playback only
+#[expect(clippy::unused_async)]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	subcommands("upload", "delete", "show"),
+	subcommand_required
+)]
+pub async fn soundboard(_ctx: SContext<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Add a clip to the soundboard, replacing any clip with the same name
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_permissions = "MANAGE_GUILD"
+)]
+async fn upload(
+	ctx: SContext<'_>,
+	#[description = "Name to play the clip with"] name: String,
+	#[description = "Audio file of at most 10 seconds"] clip: Attachment,
+) -> Result<(), Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let guild_id_i64 = i64::from(guild_id);
+	let db = &ctx.data().db;
+	let name = name.trim();
+	let rejection = if name.is_empty() || name.chars().count() > SOUND_NAME_LIMIT {
+		Some(format!(
+			"Sound names must be 1-{SOUND_NAME_LIMIT} characters long"
+		))
+	} else if !clip
+		.content_type
+		.as_deref()
+		.is_some_and(|content_type| content_type.starts_with("audio"))
+	{
+		Some("Why you give me an invalid audio format >:(".to_owned())
+	} else if clip.size > SOUND_MAX_BYTES {
+		Some(format!("Clips can be at most {SOUND_MAX_MIB} MiB"))
+	} else if !sound_exists(guild_id_i64, name, db).await?
+		&& sound_count(guild_id_i64, db).await? >= SOUND_LIMIT
+	{
+		Some(format!(
+			"The soundboard is full, delete one of the {SOUND_LIMIT} clips first"
+		))
+	} else {
+		None
+	};
+	if let Some(rejection) = rejection {
+		ctx.send(CreateReply::new().content(rejection).ephemeral(true))
+			.await?;
+		return Ok(());
+	}
+
+	ctx.defer().await?;
+	let audio = url_bytes(&clip.url).await?;
+	let duration = match validate_sound(audio.clone()).await {
+		Ok(duration) => duration,
+		Err(err) => {
+			ctx.reply(format!(
+				"That clip can't be used since {err}, clips have to be decodable audio of at most \
+				 {} seconds",
+				SOUND_MAX_DURATION.as_secs()
+			))
+			.await?;
+			return Ok(());
+		}
+	};
+	insert_sound(
+		guild_id_i64,
+		name,
+		&audio,
+		i32::try_from(duration.as_millis()).unwrap_or(i32::MAX),
+		i64::from(ctx.author().id),
+		db,
+	)
+	.await?;
+	forget_sound(guild_id, name);
+	ctx.reply(format!(
+		"Added **{name}** ({:.1}s) to the soundboard",
+		duration.as_secs_f32()
+	))
+	.await?;
+	Ok(())
+}
+
+/// Remove a clip from the soundboard
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_permissions = "MANAGE_GUILD"
+)]
+async fn delete(
+	ctx: SContext<'_>,
+	#[description = "Name of the clip"]
+	#[autocomplete = "autocomplete_sound"]
+	#[rest]
+	name: String,
+) -> Result<(), Error> {
+	let guild_id = ctx.guild_id().unwrap();
+	let name = name.trim();
+	let response = if delete_sound(i64::from(guild_id), name, &ctx.data().db)
+		.await?
+		.rows_affected()
+		> 0
+	{
+		forget_sound(guild_id, name);
+		format!("Removed **{name}** from the soundboard")
+	} else {
+		format!("No sound called **{name}** found")
+	};
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
+
+/// Post the soundboard with a button for every clip
+#[poise::command(prefix_command, slash_command, guild_only)]
+async fn show(ctx: SContext<'_>) -> Result<(), Error> {
+	let names: Vec<String> = fetch_sounds(i64::from(ctx.guild_id().unwrap()), &ctx.data().db)
+		.await?
+		.into_iter()
+		.map(|sound| sound.name)
+		.collect();
+	if names.is_empty() {
+		ctx.reply("No sounds yet, add some with /soundboard upload")
+			.await?;
+		return Ok(());
+	}
+	let component = [CreateComponent::Container(soundboard_container(&names))];
+	ctx.send(reply_container(&component)).await?;
+	Ok(())
+}
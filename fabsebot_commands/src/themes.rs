@@ -0,0 +1,223 @@
+use anyhow::Context as _;
+use fabsebot_core::{
+	config::{
+		constants::THEMES,
+		types::{Error, SContext},
+	},
+	utils::{
+		helpers::url_bytes,
+		image::theme::{
+			MAX_THEME_BLUR, THEME_IMAGE_MAX_BYTES, THEME_IMAGE_MAX_MIB, parse_hex_colour,
+			prepare_theme_background,
+		},
+	},
+};
+use fabsebot_db::guild::{
+	GuildQuoteTheme, delete_quote_theme, fetch_quote_theme_names, insert_quote_theme,
+	quote_theme_count, quote_theme_exists,
+};
+use poise::{ChoiceParameter, CreateReply};
+use rayon::spawn;
+use serenity::all::{Attachment, CreateAutocompleteResponse};
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::quote_options::{QuoteOption, name_choices};
+
+#[derive(Clone, Copy, PartialEq, Eq, ChoiceParameter)]
+enum ThemeStyle {
+	#[name = "Solid colour"]
+	Solid,
+	#[name = "Linear gradient"]
+	Linear,
+	#[name = "Radial gradient"]
+	Radial,
+	#[name = "Image"]
+	Image,
+}
+
+impl ThemeStyle {
+	const fn db_name(self) -> &'static str {
+		match self {
+			Self::Solid => "solid",
+			Self::Linear => "linear",
+			Self::Radial => "radial",
+			Self::Image => "image",
+		}
+	}
+}
+
+async fn autocomplete_guild_theme<'a>(
+	ctx: SContext<'_>,
+	partial: &'a str,
+) -> CreateAutocompleteResponse<'a> {
+	let Some(guild_id) = ctx.guild_id() else {
+		return CreateAutocompleteResponse::new();
+	};
+	let names = fetch_quote_theme_names(i64::from(guild_id), &ctx.data().db)
+		.await
+		.unwrap_or_default();
+	name_choices(names, partial)
+}
+
+/// Backgrounds of the server's own to pick from when quoting
+#[expect(clippy::unused_async)]
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	subcommands("save", "delete"),
+	subcommand_required
+)]
+pub async fn quote_theme(_ctx: SContext<'_>) -> Result<(), Error> {
+	Ok(())
+}
+
+/// Save a quote theme, replacing any theme with the same name
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_permissions = "MANAGE_GUILD"
+)]
+async fn save(
+	ctx: SContext<'_>,
+	#[description = "Name to pick the theme by"] name: String,
+	#[description = "What the background looks like"] style: ThemeStyle,
+	#[description = "Text colour as hex, like #ffffff"] text_colour: String,
+	#[description = "Background colour, or where gradients start"] colour: Option<String>,
+	#[description = "Where gradients end"] second_colour: Option<String>,
+	#[description = "Direction of linear gradients in degrees, 0 runs left to right"]
+	#[max = 359]
+	angle: Option<u16>,
+	#[description = "Picture for image backgrounds"] image: Option<Attachment>,
+	#[description = "How much to blur the picture"]
+	#[min = 0]
+	#[max = 50]
+	blur: Option<f32>,
+	#[description = "How much to darken the picture in percent"]
+	#[max = 100]
+	dim: Option<u8>,
+	#[description = "Colour outlining the text, none by default"] outline_colour: Option<String>,
+) -> Result<(), Error> {
+	let guild_id_i64 = i64::from(ctx.guild_id().unwrap());
+	let db = &ctx.data().db;
+	let name = name.trim();
+	let text_colour = parse_hex_colour(&text_colour);
+	let primary_colour = match (style, colour.as_deref()) {
+		(_, Some(colour)) => parse_hex_colour(colour),
+		(ThemeStyle::Image, None) => Some(0),
+		(_, None) => None,
+	};
+	let secondary_colour = second_colour.as_deref().map(parse_hex_colour);
+	let outline_colour = outline_colour.as_deref().map(parse_hex_colour);
+	let rejection = if let Some(rejection) =
+		QuoteOption::Theme.name_rejection(name, THEMES.contains_key(name) || name == "random")
+	{
+		Some(rejection)
+	} else if text_colour.is_none()
+		|| primary_colour.is_none()
+		|| secondary_colour == Some(None)
+		|| outline_colour == Some(None)
+	{
+		Some("Colours have to be hex codes like #ff8800".to_owned())
+	} else if matches!(style, ThemeStyle::Linear | ThemeStyle::Radial) && secondary_colour.is_none()
+	{
+		Some("Gradients need both a colour and a second colour".to_owned())
+	} else if style == ThemeStyle::Image
+		&& !image.as_ref().is_some_and(|image| {
+			image
+				.content_type
+				.as_deref()
+				.is_some_and(|content_type| content_type.starts_with("image"))
+		}) {
+		Some("Image themes need a picture attached".to_owned())
+	} else if image
+		.as_ref()
+		.is_some_and(|image| image.size > THEME_IMAGE_MAX_BYTES)
+	{
+		Some(format!("Pictures can be at most {THEME_IMAGE_MAX_MIB} MiB"))
+	} else {
+		QuoteOption::Theme.limit_rejection(
+			quote_theme_exists(guild_id_i64, name, db).await?,
+			quote_theme_count(guild_id_i64, db).await?,
+		)
+	};
+	if let Some(rejection) = rejection {
+		ctx.send(CreateReply::new().content(rejection).ephemeral(true))
+			.await?;
+		return Ok(());
+	}
+
+	ctx.defer().await?;
+	let background = match image.filter(|_| style == ThemeStyle::Image) {
+		Some(image) => {
+			let bytes = url_bytes(&image.url).await?;
+			let (tx, rx) = oneshot::channel();
+			spawn(move || {
+				if tx.send(prepare_theme_background(&bytes)).is_err() {
+					warn!("Sender failed to send result");
+				}
+			});
+			match rx
+				.await
+				.context("Rayon task for theme background panicked")?
+			{
+				Ok(background) => Some(background),
+				Err(err) => {
+					ctx.reply(format!("That picture can't be used since {err}"))
+						.await?;
+					return Ok(());
+				}
+			}
+		}
+		None => None,
+	};
+	let theme = GuildQuoteTheme {
+		name: name.to_owned(),
+		style: style.db_name().to_owned(),
+		primary_colour: primary_colour.unwrap_or_default(),
+		secondary_colour: secondary_colour.flatten(),
+		angle: i16::try_from(angle.unwrap_or_default()).unwrap_or_default(),
+		background,
+		blur: blur.unwrap_or_default().clamp(0.0, MAX_THEME_BLUR),
+		dim: i16::from(dim.unwrap_or_default().min(100)),
+		text_colour: text_colour.unwrap_or_default(),
+		outline_colour: outline_colour.flatten(),
+	};
+	insert_quote_theme(guild_id_i64, &theme, i64::from(ctx.author().id), db).await?;
+	ctx.reply(format!(
+		"Saved **{name}**, pick it from the theme menu of a quote"
+	))
+	.await?;
+	Ok(())
+}
+
+/// Remove a quote theme of the server
+#[poise::command(
+	prefix_command,
+	slash_command,
+	guild_only,
+	required_permissions = "MANAGE_GUILD"
+)]
+async fn delete(
+	ctx: SContext<'_>,
+	#[description = "Name of the theme"]
+	#[autocomplete = "autocomplete_guild_theme"]
+	#[rest]
+	name: String,
+) -> Result<(), Error> {
+	let name = name.trim();
+	let response = if delete_quote_theme(i64::from(ctx.guild_id().unwrap()), name, &ctx.data().db)
+		.await?
+		.rows_affected()
+		> 0
+	{
+		format!("Removed the **{name}** theme")
+	} else {
+		format!("No theme called **{name}** found")
+	};
+	ctx.send(CreateReply::new().content(response).ephemeral(true))
+		.await?;
+	Ok(())
+}
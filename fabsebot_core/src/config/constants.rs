@@ -1,12 +1,18 @@
 use std::{collections::HashMap, sync::LazyLock};
 
 use ab_glyph::FontArc;
-use image::{Rgba, RgbaImage};
+use image::Rgba;
 
-use crate::utils::image::create_solid_theme;
+use crate::utils::image::theme::{QuoteTheme, TextColours, ThemeBackground};
 
 pub const MESSAGE_LIMIT: usize = 4000;
 pub const CONTENT_LIMIT: usize = 2000;
+/// Options a select menu or autocomplete list holds at most
+pub const SELECT_MENU_LIMIT: usize = 25;
+/// Fonts and themes a server may add of each, leaving room for the built-in
+/// ones in the quote's select menus
+pub const GUILD_OPTION_LIMIT: i64 = 20;
+pub const GUILD_OPTION_NAME_LIMIT: usize = 32;
 
 pub const DEFAULT_PREFIX: &str = "!";
 
@@ -45,21 +51,42 @@ pub const ANIMATED_QUOTE_VEC: usize = 1_048_576;
 
 pub const DEFAULT_THEME: &str = "dark";
 
-pub static THEMES: LazyLock<HashMap<&'static str, (RgbaImage, Rgba<u8>)>> = LazyLock::new(|| {
+pub static THEMES: LazyLock<HashMap<&'static str, QuoteTheme>> = LazyLock::new(|| {
 	HashMap::from([
 		(
 			DEFAULT_THEME,
-			(
-				create_solid_theme([0, 0, 0, 255]),
-				Rgba([255, 255, 255, 255]),
-			),
+			QuoteTheme::solid([0, 0, 0, 255], [255, 255, 255, 255]),
 		),
 		(
 			"light",
-			(
-				create_solid_theme([255, 255, 255, 255]),
-				Rgba([0, 0, 0, 255]),
-			),
+			QuoteTheme::solid([255, 255, 255, 255], [0, 0, 0, 255]),
+		),
+		(
+			"sunset",
+			QuoteTheme {
+				background: ThemeBackground::LinearGradient {
+					from: Rgba([255, 94, 77, 255]),
+					to: Rgba([84, 36, 128, 255]),
+					angle: 135,
+				},
+				colours: TextColours {
+					text: Rgba([255, 255, 255, 255]),
+					outline: Some(Rgba([40, 16, 64, 255])),
+				},
+			},
+		),
+		(
+			"spotlight",
+			QuoteTheme {
+				background: ThemeBackground::RadialGradient {
+					inner: Rgba([70, 70, 78, 255]),
+					outer: Rgba([8, 8, 12, 255]),
+				},
+				colours: TextColours {
+					text: Rgba([255, 255, 255, 255]),
+					outline: None,
+				},
+			},
 		),
 	])
 });
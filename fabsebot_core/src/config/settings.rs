@@ -1,3 +1,5 @@
+use std::{mem::take, path::PathBuf};
+
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -14,8 +16,54 @@ pub struct BotConfig {
 	pub owner_id: u64,
 	pub error_webhook: String,
 	pub feedback_webhook: String,
-	pub lavalink_host: String,
-	pub lavalink_password: String,
+	#[serde(default)]
+	pub lavalink_nodes: Vec<LavalinkNodeConfig>,
+	/// Single node configs from before multiple nodes were supported
+	#[serde(default)]
+	pub lavalink_host: Option<String>,
+	#[serde(default)]
+	pub lavalink_password: Option<String>,
+	/// Directory with audio files served by the local library
+	#[serde(default)]
+	pub music_dir: Option<PathBuf>,
+}
+
+impl BotConfig {
+	/// Configured Lavalink nodes, falling back to the legacy
+	/// `lavalink_host`/`lavalink_password` pair as a single node
+	pub fn take_lavalink_nodes(&mut self) -> Vec<LavalinkNodeConfig> {
+		let mut nodes = take(&mut self.lavalink_nodes);
+		if nodes.is_empty()
+			&& let Some(host) = self.lavalink_host.take()
+		{
+			nodes.push(LavalinkNodeConfig {
+				host,
+				password: self.lavalink_password.take().unwrap_or_default(),
+				ssl: false,
+				weight: default_node_weight(),
+				region: None,
+			});
+		}
+		nodes
+	}
+}
+
+const fn default_node_weight() -> u32 {
+	1
+}
+
+#[derive(Deserialize)]
+pub struct LavalinkNodeConfig {
+	pub host: String,
+	pub password: String,
+	#[serde(default)]
+	pub ssl: bool,
+	/// Relative share of players the node gets compared to the other nodes
+	#[serde(default = "default_node_weight")]
+	pub weight: u32,
+	/// Discord voice region like `rotterdam` the node should serve first
+	#[serde(default)]
+	pub region: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -1,12 +1,16 @@
 use std::{
 	borrow::Cow,
-	sync::{Arc, LazyLock, OnceLock, RwLock, atomic::AtomicBool},
+	path::PathBuf,
+	sync::{
+		Arc, LazyLock, OnceLock, RwLock,
+		atomic::{AtomicBool, AtomicU16, Ordering},
+	},
 	time::Duration,
 };
 
 use anyhow::Error as AError;
 use dashmap::DashMap;
-use lavalink_rs::client::LavalinkClient;
+use lavalink_rs::{client::LavalinkClient, model::track::TrackData};
 use mini_moka::sync::Cache;
 use poise::Context as PContext;
 use reqwest::Client;
@@ -32,7 +36,7 @@ use crate::{
 	config::settings::{APIConfig, HTTPAgent, ServerConfig},
 	utils::{
 		ai::{AIQueuePayload, ContentPart, ToolCall},
-		voice::{ConnectionStatus, QueueData, TrackSignal},
+		voice::{AudioFilter, ConnectionStatus, DEFAULT_VOLUME, QueueData, TrackSignal},
 	},
 };
 
@@ -43,9 +47,19 @@ pub type MusicQueue = mpsc::Sender<MusicQueueData>;
 
 pub struct MusicData {
 	pub queue: MusicQueue,
-	pub global: AtomicBool,
+	/// Join code of the listening party the guild is in
+	pub party: RwLock<Option<String>>,
 	pub track_signals: watch::Sender<TrackSignal>,
 	pub connection_signals: watch::Sender<ConnectionStatus>,
+	/// Volume in percent, kept for the whole voice session
+	pub volume: AtomicU16,
+	/// Active Lavalink filters, kept for the whole voice session
+	pub filters: RwLock<Vec<AudioFilter>>,
+	/// Last track Lavalink started, requeued when the player moves nodes
+	pub lavalink_track: RwLock<Option<TrackData>>,
+	/// Whether a copy of the current Lavalink track waits at the front of the
+	/// queue, since Lavalink has no loop mode of its own
+	pub lavalink_looping: AtomicBool,
 }
 
 impl MusicData {
@@ -56,6 +70,14 @@ impl MusicData {
 	}
 
 	pub fn disconnected(&self) {
+		self.volume.store(DEFAULT_VOLUME, Ordering::Relaxed);
+		if let Ok(mut filters) = self.filters.write() {
+			filters.clear();
+		}
+		if let Ok(mut lavalink_track) = self.lavalink_track.write() {
+			*lavalink_track = None;
+		}
+		self.lavalink_looping.store(false, Ordering::Relaxed);
 		if let Err(err) = self.connection_signals.send(ConnectionStatus::Disconnected) {
 			error!("Failed to notify about disconnected status: {err}");
 		}
@@ -218,6 +240,7 @@ pub struct UtilsConfig {
 	pub bot_name: String,
 	pub error_webhook: String,
 	pub feedback_webhook: String,
+	pub music_dir: Option<PathBuf>,
 }
 
 pub static UTILS_CONFIG: OnceLock<UtilsConfig> = OnceLock::new();
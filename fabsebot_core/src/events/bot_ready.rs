@@ -7,6 +7,7 @@ use tracing::{error, info};
 use crate::{
 	config::types::{BOT_CONTEXT, BotContext, Data},
 	periodic_task,
+	utils::voice::{library::scan_library, sessions::resume_sessions},
 };
 
 pub async fn handle_ready(ctx: &SContext, data_about_bot: &Ready) {
@@ -24,6 +25,20 @@ pub async fn handle_ready(ctx: &SContext, data_about_bot: &Ready) {
 			error!("BOT_CONTEXT already initialized");
 		}
 		spawn(async move { periodic_task().await });
+		let ctx = ctx.clone();
+		spawn(async move {
+			if let Err(err) = resume_sessions(&ctx).await {
+				error!("Failed to resume music sessions: {err}");
+			}
+		});
+		let db = bot_data.db.clone();
+		spawn(async move {
+			match scan_library(&db).await {
+				Ok(Some(scan)) => info!("Indexed {} files of the music library", scan.indexed),
+				Ok(None) => {}
+				Err(err) => error!("Failed to scan music library: {err}"),
+			}
+		});
 	}
 
 	let user_count = ctx
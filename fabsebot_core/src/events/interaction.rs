@@ -1,17 +1,23 @@
+use std::sync::Arc;
+
 use anyhow::Result as AResult;
 use serenity::{
 	all::{
 		ComponentInteraction, Context as SContext, CreateComponent, CreateContainer,
-		CreateInputText, CreateInteractionResponse, CreateLabel, CreateModal, CreateModalComponent,
-		CreateTextDisplay, Error, GuildId, InputText, InputTextStyle, Label, LabelComponent,
-		ModalComponent, ModalInteraction, Webhook,
+		CreateInputText, CreateInteractionResponse, CreateInteractionResponseMessage, CreateLabel,
+		CreateModal, CreateModalComponent, CreateTextDisplay, Error, GuildId, InputText,
+		InputTextStyle, Label, LabelComponent, ModalComponent, ModalInteraction, Webhook,
 	},
 	http::Http,
 };
 
 use crate::{
-	config::types::utils_config,
-	utils::{helpers::text_display, webhook::webhook_components},
+	config::types::{Data, utils_config},
+	utils::{
+		helpers::text_display,
+		voice::soundboard::{SOUND_BUTTON_PREFIX, play_sound},
+		webhook::webhook_components,
+	},
 };
 
 pub const FEEDBACK_BUTTON_CUSTOM_ID: &str = "feedback-modal-button";
@@ -91,3 +97,30 @@ pub async fn handle_feedback_modal_button(
 		)
 		.await
 }
+
+pub async fn handle_sound_button(
+	ctx: &SContext,
+	interaction: &ComponentInteraction,
+	guild_id: GuildId,
+) -> AResult<()> {
+	let bot_data: Arc<Data> = ctx.data();
+	let name = interaction
+		.data
+		.custom_id
+		.trim_start_matches(SOUND_BUTTON_PREFIX);
+	if let Some(reason) = play_sound(&bot_data, guild_id, name, interaction.user.id).await? {
+		interaction
+			.create_response(
+				&ctx.http,
+				CreateInteractionResponse::Message(
+					CreateInteractionResponseMessage::new()
+						.content(reason)
+						.ephemeral(true),
+				),
+			)
+			.await?;
+	} else {
+		interaction.defer(&ctx.http).await?;
+	}
+	Ok(())
+}
@@ -1,30 +1,28 @@
-use std::{borrow::Cow, fmt::Write as _, sync::Arc};
+use std::{borrow::Cow, fmt::Write as _, sync::Arc, time::Duration};
 
 use anyhow::Result as AResult;
 use fabsebot_db::{
-	guild::{WordReactions, fetch_guild_settings},
-	user::{PingedLink, UserSettings, fetch_user_settings},
+	guild::{WordReactions, fetch_guild_settings, fetch_music_settings},
+	user::{PingedLink, UserSettings, fetch_user_settings, record_user_message},
 };
 use metrics::counter;
 use serde_json::{Value, to_value};
 use serenity::{
 	all::{
-		Colour, Context as SContext, CreateContainer, EmojiId, ExecuteWebhook, GenericChannelId,
-		GuildId, Message, MessageId, ReactionType,
-	},
-	builder::{
-		CreateComponent, CreateContainerComponent, CreateMediaGallery, CreateSection, EditMessage,
+		Attachment, Colour, Context as SContext, CreateContainer, EmojiId, ExecuteWebhook,
+		GenericChannelId, GuildId, Message, MessageId, ReactionType,
 	},
+	builder::{CreateComponent, CreateContainerComponent, CreateMediaGallery, CreateSection},
 	model::channel::MessageFlags,
 };
 use sqlx::{Pool, Postgres, query, query_as, types::Json};
 use tokio::{sync::mpsc::error::SendError, try_join};
-use tracing::error;
+use tracing::{error, warn};
 use winnow::Parser as _;
 
 use crate::{
 	config::{
-		constants::{DEFAULT_AFK_REASON, FAILED_SONG_FETCH, MESSAGE_LIMIT, QUEUEING_MSG},
+		constants::{DEFAULT_AFK_REASON, MESSAGE_LIMIT},
 		types::{AIQueue, ContextType, Data, WebhookMap, utils_config},
 	},
 	stats::counters::METRICS,
@@ -35,11 +33,17 @@ use crate::{
 			media_gallery, message_container, separator, silent_message, text_display,
 			thumbnail_section,
 		},
-		voice::{lavalink_play, lavalink_try_join},
+		voice::{
+			PayloadType, lavalink_play, lavalink_play_upload, lavalink_try_join,
+			requests::{RequestStatus, clean_up_request, mark_request},
+		},
 		webhook::{spoiler_message, webhook_find},
 	},
 };
 
+/// Lines of a music channel message that get queued, the rest is ignored
+const MAX_CHANNEL_REQUEST_LINES: usize = 10;
+
 async fn check_bot_ping(ctx: &SContext, new_message: &Message) -> AResult<()> {
 	if new_message.mentions_user_id(ctx.cache.current_user().id)
 		&& new_message.referenced_message.is_none()
@@ -105,6 +109,8 @@ async fn easter_eggs(ctx: &SContext, new_message: &Message, webhooks: &WebhookMa
 	Ok(())
 }
 
+/// Queues every line of a message in the music channel plus its audio
+/// attachments, answering with reactions and only replying about failures
 async fn queue_track(
 	ctx: &SContext,
 	new_message: &Message,
@@ -112,27 +118,139 @@ async fn queue_track(
 	guild_id: GuildId,
 ) -> AResult<()> {
 	channel_counter("music");
-	let Some((_typing, player_context)) =
-		lavalink_try_join(ContextType::Serenity(ctx), guild_id, new_message.author.id).await?
-	else {
+	let lines: Vec<&str> = new_message
+		.content
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty())
+		.collect();
+	// Uploads get measured and loaded by Lavalink, so they keep the full set of
+	// player controls and play as loud as everything else
+	let uploads: Vec<&Attachment> = new_message
+		.attachments
+		.iter()
+		.filter(|attachment| {
+			attachment
+				.content_type
+				.as_deref()
+				.is_some_and(|content_type| content_type.starts_with("audio"))
+		})
+		.collect();
+	if lines.is_empty() && uploads.is_empty() {
 		return Ok(());
-	};
-	let mut msg = new_message.reply(&ctx.http, QUEUEING_MSG).await?;
-	if let Err(err) = lavalink_play(
-		ctx,
+	}
+	let Some((_typing, player_context)) = lavalink_try_join(
+		ContextType::Serenity(ctx),
 		guild_id,
-		msg.id,
-		msg.channel_id,
+		new_message.channel_id,
 		new_message.author.id,
-		&new_message.content,
-		player_context,
-		conn,
 	)
-	.await
-	{
-		msg.edit(&ctx.http, EditMessage::new().content(FAILED_SONG_FETCH))
-			.await?;
-		return Err(err);
+	.await?
+	else {
+		return Ok(());
+	};
+	mark_request(
+		new_message.channel_id,
+		new_message.id,
+		RequestStatus::Queued,
+	)
+	.await;
+	let mut queued = false;
+	let mut failed = Vec::new();
+	for line in lines.iter().take(MAX_CHANNEL_REQUEST_LINES) {
+		match lavalink_play(
+			ctx,
+			guild_id,
+			new_message.id,
+			new_message.channel_id,
+			new_message.author.id,
+			line,
+			PayloadType::Lavalink,
+			true,
+			player_context.clone(),
+			conn,
+		)
+		.await
+		{
+			Ok(()) => queued = true,
+			Err(err) => {
+				warn!("Failed to queue {line} from the music channel: {err}");
+				failed.push(*line);
+			}
+		}
+	}
+	for upload in uploads {
+		match lavalink_play_upload(
+			ctx,
+			guild_id,
+			new_message.id,
+			new_message.channel_id,
+			new_message.author.id,
+			upload,
+			player_context.clone(),
+			conn,
+		)
+		.await
+		{
+			Ok(()) => queued = true,
+			Err(err) => {
+				warn!(
+					"Failed to queue {} from the music channel: {err}",
+					upload.filename
+				);
+				failed.push(&upload.filename);
+			}
+		}
+	}
+	if !queued {
+		mark_request(
+			new_message.channel_id,
+			new_message.id,
+			RequestStatus::Failed,
+		)
+		.await;
+	}
+
+	let skipped = lines.len().saturating_sub(MAX_CHANNEL_REQUEST_LINES);
+	let reply = if failed.is_empty() && skipped == 0 {
+		None
+	} else {
+		let mut content = String::new();
+		if !failed.is_empty() {
+			content.push_str("Couldn't queue:");
+			for request in failed {
+				write!(content, "\n- `{}`", request.replace('`', "'"))?;
+			}
+		}
+		if skipped > 0 {
+			write!(
+				content,
+				"\nOnly the first {MAX_CHANNEL_REQUEST_LINES} lines get queued, skipped {skipped}"
+			)?;
+		}
+		let mut content = content.trim_start().to_owned();
+		content.truncate(MESSAGE_LIMIT);
+		Some(
+			new_message
+				.channel_id
+				.send_message(
+					&ctx.http,
+					silent_message(&content).reference_message(new_message),
+				)
+				.await?,
+		)
+	};
+
+	let cleanup = fetch_music_settings(i64::from(guild_id), conn)
+		.await?
+		.unwrap_or_default()
+		.music_request_cleanup;
+	if cleanup > 0 {
+		let delay = Duration::from_secs(cleanup.unsigned_abs().into());
+		clean_up_request(new_message.clone(), delay);
+		if let Some(reply) = reply {
+			clean_up_request(reply, delay);
+		}
 	}
 
 	Ok(())
@@ -559,8 +677,6 @@ async fn db_queries(
 ) -> AResult<()> {
 	let bot_data: Arc<Data> = ctx.data();
 
-	user_queries(ctx, new_message, guild_id_i64, &bot_data.db).await?;
-
 	let words: Vec<String> = new_message
 		.content
 		.split_whitespace()
@@ -572,6 +688,21 @@ async fn db_queries(
 		.filter(|s| !s.is_empty())
 		.collect();
 
+	// Before the message gets counted, so the baseline of a new year leaves it
+	// out like quotes do
+	if let Err(err) = record_user_message(
+		guild_id_i64,
+		i64::from(new_message.author.id),
+		&words,
+		&bot_data.db,
+	)
+	.await
+	{
+		warn!("Failed to record yearly stats: {err}");
+	}
+
+	user_queries(ctx, new_message, guild_id_i64, &bot_data.db).await?;
+
 	let (word_reactions, updated_words) = try_join!(
 		query_as!(
 			WordReactions,
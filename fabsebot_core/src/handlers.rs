@@ -16,14 +16,14 @@ use crate::{
 		bot_ready::handle_ready,
 		interaction::{
 			FEEDBACK_BUTTON_CUSTOM_ID, FEEDBACK_MODAL_CUSTOM_ID, handle_feedback_modal_button,
-			handle_feedback_modal_reply,
+			handle_feedback_modal_reply, handle_sound_button,
 		},
 		message_delete::handle_message_delete,
 		message_sent::handle_message,
 	},
 	log_error,
 	stats::counters::METRICS,
-	utils::helpers::guild_cache,
+	utils::{helpers::guild_cache, voice::soundboard::SOUND_BUTTON_PREFIX},
 };
 
 pub async fn on_error(error: FrameworkError<'_, Data, Error>) {
@@ -166,6 +166,19 @@ impl SEventHandler for EventHandler {
 					counter!(METRICS.feedback_modal_errors.as_str()).increment(1);
 					log_error(output).await;
 				}
+				if let Some(component_interaction) = interaction.as_message_component()
+					&& component_interaction
+						.data
+						.custom_id
+						.starts_with(SOUND_BUTTON_PREFIX)
+					&& let Some(guild_id) = component_interaction.guild_id
+					&& let Err(error) =
+						handle_sound_button(ctx, component_interaction, guild_id).await
+				{
+					let output = format!("# Error handling soundboard button\n{error}");
+					counter!(METRICS.soundboard_errors.as_str()).increment(1);
+					log_error(output).await;
+				}
 				if let Some(modal_interaction) = interaction.as_modal_submit()
 					&& modal_interaction.data.custom_id == FEEDBACK_MODAL_CUSTOM_ID
 					&& let Some(guild_id) = interaction.guild_id()
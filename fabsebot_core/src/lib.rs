@@ -46,7 +46,11 @@ use crate::{
 	stats::counters::METRICS,
 	utils::{
 		helpers::{default_mentions, get_gif, get_waifu},
-		voice::setup_lavalink,
+		voice::{
+			nodes::lavalink_health_task,
+			sessions::{mark_shutdown, save_sessions},
+			setup_lavalink,
+		},
 		webhook::error_hook,
 	},
 };
@@ -78,6 +82,8 @@ where
 		v = s3.recv() => v.unwrap(),
 	);
 
+	mark_shutdown();
+	save_sessions().await;
 	shutdown_trigger();
 }
 
@@ -180,12 +186,13 @@ pub async fn periodic_task() -> ! {
 }
 
 pub async fn bot_start(
-	bot_config: BotConfig,
+	mut bot_config: BotConfig,
 	postgres_pool: Pool<Postgres>,
 	commands: Vec<Command<Data, SError>>,
 ) -> AResult<()> {
 	METRICS.describe_all();
 
+	let lavalink_nodes = bot_config.take_lavalink_nodes();
 	spawn(async move {
 		periodic_ping(&bot_config.uptime_url, &bot_config.uptime_token).await;
 	});
@@ -193,12 +200,11 @@ pub async fn bot_start(
 	let music_manager = Songbird::serenity();
 	music_manager.set_config(Config::default().decode_mode(DecodeMode::Decrypt));
 
-	let lavalink_client = setup_lavalink(
-		bot_config.lavalink_host,
-		bot_config.lavalink_password,
-		UserId::from(bot_config.bot_id),
-	)
-	.await;
+	let lavalink_client = setup_lavalink(lavalink_nodes, UserId::from(bot_config.bot_id)).await?;
+	let health_client = lavalink_client.clone();
+	spawn(async move {
+		lavalink_health_task(health_client).await;
+	});
 
 	let bot_data = Arc::new(Data {
 		db: postgres_pool,
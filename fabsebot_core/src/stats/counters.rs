@@ -1,6 +1,6 @@
 use std::sync::{Arc, LazyLock};
 
-use metrics::describe_counter;
+use metrics::{describe_counter, describe_gauge};
 
 use crate::config::types::utils_config;
 
@@ -26,6 +26,7 @@ pub struct Metrics {
 	pub playback_errors: String,
 	pub feedback_modal_errors: String,
 	pub feedback_reply_errors: String,
+	pub soundboard_errors: String,
 	pub bot_permissions_errors: String,
 	pub user_permissions_errors: String,
 	pub chatbot_errors: String,
@@ -34,6 +35,10 @@ pub struct Metrics {
 	pub lyrics_errors: String,
 	pub gifs_errors: String,
 	pub voice_join_errors: String,
+	pub lavalink_failovers: String,
+	pub lavalink_node_up: String,
+	pub lavalink_node_players: String,
+	pub lavalink_node_load: String,
 }
 
 impl Metrics {
@@ -59,6 +64,7 @@ impl Metrics {
 			playback_errors: format!("{bot_name}_playback_errors_total"),
 			feedback_modal_errors: format!("{bot_name}_feedback_modal_errors_total"),
 			feedback_reply_errors: format!("{bot_name}_feedback_reply_errors_total"),
+			soundboard_errors: format!("{bot_name}_soundboard_errors_total"),
 			bot_permissions_errors: format!("{bot_name}_bot_permissions_errors_total"),
 			user_permissions_errors: format!("{bot_name}_user_permissions_errors_total"),
 			chatbot_errors: format!("{bot_name}_chatbot_errors"),
@@ -67,6 +73,10 @@ impl Metrics {
 			lyrics_errors: format!("{bot_name}_lyrics_errors"),
 			gifs_errors: format!("{bot_name}_gifs_errors"),
 			voice_join_errors: format!("{bot_name}_voice_join_errors"),
+			lavalink_failovers: format!("{bot_name}_lavalink_failovers_total"),
+			lavalink_node_up: format!("{bot_name}_lavalink_node_up"),
+			lavalink_node_players: format!("{bot_name}_lavalink_node_players"),
+			lavalink_node_load: format!("{bot_name}_lavalink_node_load"),
 		}
 	}
 
@@ -114,6 +124,10 @@ impl Metrics {
 			self.feedback_reply_errors.as_str(),
 			"Counter for feedback reply errors"
 		);
+		describe_counter!(
+			self.soundboard_errors.as_str(),
+			"Counter for soundboard button errors"
+		);
 		describe_counter!(
 			self.bot_permissions_errors.as_str(),
 			"Counter for bot permissions errors"
@@ -134,5 +148,21 @@ impl Metrics {
 			self.voice_join_errors.as_str(),
 			"Counter for voice join errors"
 		);
+		describe_counter!(
+			self.lavalink_failovers.as_str(),
+			"Counter for players moved off a dead Lavalink node"
+		);
+		describe_gauge!(
+			self.lavalink_node_up.as_str(),
+			"Whether a Lavalink node is connected"
+		);
+		describe_gauge!(
+			self.lavalink_node_players.as_str(),
+			"Players playing on a Lavalink node"
+		);
+		describe_gauge!(
+			self.lavalink_node_load.as_str(),
+			"Lavalink process CPU load of a node"
+		);
 	}
 }
@@ -2,7 +2,10 @@ use std::{
 	borrow::Cow,
 	fmt::Write as _,
 	io::Cursor,
-	sync::{Arc, RwLock, atomic::AtomicBool},
+	sync::{
+		Arc, RwLock,
+		atomic::{AtomicBool, AtomicU16},
+	},
 	time::Duration,
 };
 
@@ -26,7 +29,8 @@ use serenity::{
 		Context, CreateActionRow, CreateAllowedMentions, CreateButton, CreateComponent,
 		CreateContainer, CreateContainerComponent, CreateMediaGalleryItem, CreateMessage,
 		CreateSectionAccessory, CreateSectionComponent, CreateSeparator, CreateTextDisplay,
-		CreateThumbnail, CreateUnfurledMediaItem, GuildId, MessageFlags, Permissions, ReactionType,
+		CreateThumbnail, CreateUnfurledMediaItem, GenericChannelId, GuildId, MessageFlags,
+		MessageId, Permissions, ReactionType,
 	},
 	builder::{CreateInteractionResponse, EditMessage},
 	collector::ComponentInteractionCollector,
@@ -48,8 +52,9 @@ use tracing::warn;
 use winnow::{
 	ModalResult, Parser as _,
 	ascii::digit1,
-	combinator::{preceded, separated_pair},
+	combinator::{delimited, opt, preceded, repeat, separated_pair},
 	error::{ContextError, ErrMode},
+	token::one_of,
 };
 
 use crate::{
@@ -65,7 +70,7 @@ use crate::{
 	stats::counters::METRICS,
 	utils::{
 		ai::{ContentPart, ai_task, uri_content},
-		voice::{ConnectionStatus, TrackSignal, music_task},
+		voice::{ConnectionStatus, DEFAULT_VOLUME, TrackSignal, music_task},
 	},
 };
 
@@ -322,9 +327,71 @@ struct LyricsEntry {
 		deserialize_with = "non_empty_string"
 	)]
 	plain_lyrics: String,
+	#[serde(rename(deserialize = "syncedLyrics"), default)]
+	synced_lyrics: Option<String>,
+}
+
+pub struct SyncedLine {
+	pub time: Duration,
+	pub text: String,
+}
+
+pub struct Lyrics {
+	pub plain: Cow<'static, str>,
+	/// Lines ordered by timestamp, empty when lrclib has no synced version
+	pub synced: Vec<SyncedLine>,
 }
 
-pub async fn get_lyrics(track_name: &str, artist_name: &str) -> Cow<'static, str> {
+fn lrc_timestamp(input: &mut &str) -> ModalResult<Duration> {
+	let (minutes, (seconds, fraction)) = delimited(
+		'[',
+		separated_pair(
+			digit1.parse_to::<u64>(),
+			':',
+			(
+				digit1.parse_to::<u64>(),
+				opt(preceded(one_of(['.', ':']), digit1)),
+			),
+		),
+		']',
+	)
+	.parse_next(input)?;
+	let millis = fraction.map_or(0, |fraction: &str| {
+		let digits = fraction.get(..3).unwrap_or(fraction);
+		let scale = match digits.len() {
+			1 => 100,
+			2 => 10,
+			_ => 1,
+		};
+		digits.parse::<u64>().unwrap_or(0).saturating_mul(scale)
+	});
+	Ok(Duration::from_millis(
+		minutes
+			.saturating_mul(60_000)
+			.saturating_add(seconds.saturating_mul(1000))
+			.saturating_add(millis),
+	))
+}
+
+/// Parses LRC lyrics, lines without a timestamp like `[ar:...]` tags are
+/// skipped
+fn parse_lrc(lrc: &str) -> Vec<SyncedLine> {
+	let mut lines = Vec::new();
+	for mut line in lrc.lines() {
+		let times: Vec<Duration> = repeat(0.., lrc_timestamp)
+			.parse_next(&mut line)
+			.unwrap_or_default();
+		let text = line.trim();
+		lines.extend(times.into_iter().map(|time| SyncedLine {
+			time,
+			text: text.to_owned(),
+		}));
+	}
+	lines.sort_by_key(|line| line.time);
+	lines
+}
+
+pub async fn get_lyrics(track_name: &str, artist_name: &str) -> Lyrics {
 	match fetch_and_parse::<LyricsResponse>(
 		HTTP_CLIENT
 			.get("https://lrclib.net/api/get")
@@ -333,12 +400,23 @@ pub async fn get_lyrics(track_name: &str, artist_name: &str) -> Cow<'static, str
 	)
 	.await
 	{
-		Ok(payload) => Cow::Owned(payload.0.plain_lyrics),
+		Ok(payload) => Lyrics {
+			synced: payload
+				.0
+				.synced_lyrics
+				.as_deref()
+				.map(parse_lrc)
+				.unwrap_or_default(),
+			plain: Cow::Owned(payload.0.plain_lyrics),
+		},
 		Err(error) => {
 			let output = format!("# Failed to fetch lyrics\n{error}");
 			counter!(METRICS.lyrics_errors.as_str()).increment(1);
 			log_error(output).await;
-			Cow::Borrowed("Not found :(")
+			Lyrics {
+				plain: Cow::Borrowed("Not found :("),
+				synced: Vec::new(),
+			}
 		}
 	}
 }
@@ -408,6 +486,20 @@ pub fn discord_message_link(input: &mut &str) -> ModalResult<DiscordMessageLink>
 	})
 }
 
+/// Guild, channel and message a link points to, if it is a message link
+#[must_use]
+pub fn message_link_ids(mut input: &str) -> Option<(GuildId, GenericChannelId, MessageId)> {
+	let link = discord_message_link
+		.parse_next(&mut input)
+		.ok()
+		.filter(|link| link.guild != 0 && link.channel != 0 && link.message != 0)?;
+	Some((
+		GuildId::new(link.guild),
+		GenericChannelId::new(link.channel),
+		MessageId::new(link.message),
+	))
+}
+
 pub async fn get_emoji(
 	ctx: &Context,
 	emojis: &EmojisMap,
@@ -656,9 +748,13 @@ pub async fn guild_cache(
 		ai_queue: ai_channel.0,
 		music_data: MusicData {
 			queue: music_channel.0,
-			global: AtomicBool::new(false),
+			party: RwLock::new(None),
 			track_signals: music_signal_tx,
 			connection_signals: music_status_tx,
+			volume: AtomicU16::new(DEFAULT_VOLUME),
+			filters: RwLock::new(Vec::new()),
+			lavalink_track: RwLock::new(None),
+			lavalink_looping: AtomicBool::new(false),
 		},
 		prefix: RwLock::new(prefix),
 	});
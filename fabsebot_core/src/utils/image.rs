@@ -1,20 +1,36 @@
-use std::{clone::Clone, io::Cursor, result::Result, sync::LazyLock};
+pub mod conversation;
+pub mod emoji;
+pub mod fonts;
+pub mod inline;
+pub mod theme;
 
-use ab_glyph::{FontArc, PxScale};
+use std::{
+	io::Cursor,
+	result::Result,
+	sync::{Arc, LazyLock},
+};
+
+use ab_glyph::{Font as _, FontArc, PxScale, ScaleFont as _, point};
 use anyhow::Result as AResult;
 use image::{
-	AnimationDecoder as _, Frame, ImageBuffer,
+	AnimationDecoder as _, Frame, ImageBuffer, ImageFormat,
 	ImageFormat::Avif as STATIC_FORMAT,
-	Rgba, RgbaImage,
+	Pixel as _, Rgba, RgbaImage,
 	codecs::gif::{GifDecoder, GifEncoder, Repeat},
-	imageops::{FilterType, overlay, resize},
+	imageops::{FilterType, crop_imm, fast_blur, overlay, replace, resize},
 	load_from_memory,
 };
 use imageproc::drawing::{draw_text_mut, text_size};
 use rayon::prelude::*;
 use textwrap::wrap;
 
-use crate::config::constants::{DEFAULT_THEME, THEMES};
+use crate::{
+	config::constants::{CONTENT_FONT, DEFAULT_THEME, FONTS, THEMES},
+	utils::image::{
+		inline::{InlineText, TextStyle, is_placeholder, marker_style, placeholder_emoji},
+		theme::{QuoteTheme, TextColours},
+	},
+};
 
 const QUOTE_WIDTH: u32 = 1200;
 const QUOTE_HEIGHT: u32 = 630;
@@ -32,6 +48,22 @@ const DEFAULT_WRAP_LENGTH: usize = 80;
 const FONT_SIZE_DECREMENT: f32 = 2.0;
 const WRAP_LENGTH_DECREMENT: usize = 5;
 const LINE_SPACING: u32 = 10;
+/// How far italics lean for every pixel of height
+const ITALIC_SLANT: f32 = 0.2;
+const SPOILER_BLUR: f32 = 16.0;
+const CODE_BACKGROUND: Rgba<u8> = Rgba([128, 128, 128, 72]);
+const ATTACHMENT_MAX_HEIGHT: u32 = QUOTE_HEIGHT / 3;
+pub const ATTACHMENT_MAX_BYTES: u32 = 8 * 1024 * 1024;
+const OUTLINE_OFFSETS: [(i32, i32); 8] = [
+	(-2, -2),
+	(0, -2),
+	(2, -2),
+	(-2, 0),
+	(2, 0),
+	(-2, 2),
+	(0, 2),
+	(2, 2),
+];
 
 static EMOJI_FONT: LazyLock<FontArc> = LazyLock::new(|| {
 	FontArc::try_from_slice(include_bytes!("../../../fonts/NotoEmoji-Regular.ttf")).unwrap()
@@ -56,7 +88,8 @@ impl FontMetrics {
 
 #[derive(Clone)]
 pub struct TextLayout {
-	content_lines: Vec<(String, i32, i32, i32)>,
+	content_lines: Vec<(String, TextStyle, i32, i32, i32)>,
+	attachment: Option<(RgbaImage, i32, i32, i32)>,
 	author_position: (i32, i32, i32),
 	content_scale: PxScale,
 	author_scale: PxScale,
@@ -66,6 +99,7 @@ impl Default for TextLayout {
 	fn default() -> Self {
 		Self {
 			content_lines: Vec::new(),
+			attachment: None,
 			author_position: (0, 0, 0),
 			content_scale: PxScale::from(0.0),
 			author_scale: PxScale::from(0.0),
@@ -78,14 +112,19 @@ pub fn create_solid_theme(color: [u8; 4]) -> RgbaImage {
 	RgbaImage::from_pixel(QUOTE_WIDTH, QUOTE_HEIGHT, Rgba(color))
 }
 
-fn truncate_text(text: &str, metrics: &FontMetrics, font: &FontArc) -> String {
-	let ellipsis_width = text_size(metrics.scale, font, ELLIPSIS).0;
-	let target_width = MAX_CONTENT_WIDTH.saturating_sub(ellipsis_width);
+fn truncate_text(text: &str, style: TextStyle, metrics: &FontMetrics, font: &FontArc) -> String {
+	truncate_to_width(text, MAX_CONTENT_WIDTH, |text| {
+		line_width(text, style, metrics.scale, font)
+	})
+}
+
+fn truncate_to_width(text: &str, max_width: u32, measure: impl Fn(&str) -> u32) -> String {
+	let target_width = max_width.saturating_sub(measure(ELLIPSIS));
 
 	let mut end = text.len();
 	while end > ELLIPSIS.len() {
 		end = text.floor_char_boundary(end.saturating_sub(1));
-		if text_size(metrics.scale, font, &text[..end]).0 <= target_width {
+		if measure(&text[..end]) <= target_width {
 			break;
 		}
 	}
@@ -93,6 +132,96 @@ fn truncate_text(text: &str, metrics: &FontMetrics, font: &FontArc) -> String {
 	format!("{}{}", &text[..end], ELLIPSIS)
 }
 
+fn fit_text(text: &str, scale: PxScale, font: &FontArc, max_width: u32) -> String {
+	if text_size(scale, font, text).0 <= max_width {
+		text.to_owned()
+	} else {
+		truncate_to_width(text, max_width, |text| text_size(scale, font, text).0)
+	}
+}
+
+/// Emojis are drawn as squares as tall as a line of text
+fn emoji_size(scale: PxScale, font: &FontArc) -> u32 {
+	text_size(scale, font, "Tg").1
+}
+
+/// Thickness of bold strokes and of the lines under and through text
+const fn stroke_width(line_height: u32) -> u32 {
+	if line_height < 48 {
+		1
+	} else {
+		line_height / 24
+	}
+}
+
+fn char_width(c: char, style: TextStyle, scale: PxScale, font: &FontArc) -> u32 {
+	let text = c.to_string();
+	let width = text_size(scale, glyph_font(c, &text, font), &text).0;
+	if style.contains(TextStyle::BOLD) {
+		width.saturating_add(stroke_width(emoji_size(scale, font)))
+	} else {
+		width
+	}
+}
+
+/// Width of the line the way `draw_line` lays it out, starting in the given
+/// style
+fn line_width(line: &str, start: TextStyle, scale: PxScale, font: &FontArc) -> u32 {
+	let mut style = start;
+	line.chars()
+		.map(|c| {
+			if let Some(marker) = marker_style(c) {
+				style = marker;
+				0
+			} else if is_placeholder(c) {
+				emoji_size(scale, font)
+			} else {
+				char_width(c, style, scale, font)
+			}
+		})
+		.fold(0, u32::saturating_add)
+}
+
+/// Style the next line starts in
+fn end_style(line: &str, start: TextStyle) -> TextStyle {
+	line.chars()
+		.filter_map(marker_style)
+		.next_back()
+		.unwrap_or(start)
+}
+
+/// Scales the attachment down to fit below the text
+fn fit_attachment(image: &RgbaImage) -> RgbaImage {
+	let (width, height) = image.dimensions();
+	if width <= MAX_CONTENT_WIDTH && height <= ATTACHMENT_MAX_HEIGHT {
+		return image.clone();
+	}
+	let (new_width, new_height) =
+		if width.saturating_mul(ATTACHMENT_MAX_HEIGHT) > height.saturating_mul(MAX_CONTENT_WIDTH) {
+			(
+				MAX_CONTENT_WIDTH,
+				height
+					.saturating_mul(MAX_CONTENT_WIDTH)
+					.checked_div(width)
+					.unwrap_or_default(),
+			)
+		} else {
+			(
+				width
+					.saturating_mul(ATTACHMENT_MAX_HEIGHT)
+					.checked_div(height)
+					.unwrap_or_default(),
+				ATTACHMENT_MAX_HEIGHT,
+			)
+		};
+	resize(
+		image,
+		new_width.max(1),
+		new_height.max(1),
+		FilterType::Triangle,
+	)
+}
+
 fn apply_gradient_to_avatar(avatar: &mut RgbaImage, is_reverse: bool) {
 	let gradient_width = QUOTE_HEIGHT / 2;
 	let gradient_start = if is_reverse {
@@ -134,8 +263,14 @@ fn prepare_text_layout(
 	author_name: &str,
 	content_font: &FontArc,
 	author_font: &FontArc,
+	attachment: Option<&RgbaImage>,
 	text_layout: &mut TextLayout,
 ) {
+	let attachment = attachment.map(fit_attachment);
+	let attachment_height = attachment
+		.as_ref()
+		.map_or(0, |image| image.height().saturating_add(LINE_SPACING));
+	let content_height = MAX_CONTENT_HEIGHT.saturating_sub(attachment_height);
 	let mut content_metrics = FontMetrics::new(content_font, PxScale::from(MAX_CONTENT_FONT_SIZE));
 	let author_metrics = FontMetrics::new(author_font, PxScale::from(AUTHOR_FONT_SIZE));
 
@@ -146,7 +281,12 @@ fn prepare_text_layout(
 		let wrapped_lines = wrap(quoted_content, wrapped_length);
 
 		if let Some(first_line) = wrapped_lines.first()
-			&& text_size(content_metrics.scale, content_font, first_line).0 > MAX_CONTENT_WIDTH
+			&& line_width(
+				first_line,
+				TextStyle::default(),
+				content_metrics.scale,
+				content_font,
+			) > MAX_CONTENT_WIDTH
 		{
 			if content_metrics.scale.x == MIN_CONTENT_FONT_SIZE {
 				wrapped_length = wrapped_length.saturating_sub(WRAP_LENGTH_DECREMENT);
@@ -165,7 +305,7 @@ fn prepare_text_layout(
 		let max_possible_lines = {
 			let height_per_line = content_metrics.line_height.saturating_add(LINE_SPACING);
 			usize::try_from(
-				((MAX_CONTENT_HEIGHT.saturating_add(LINE_SPACING))
+				((content_height.saturating_add(LINE_SPACING))
 					.checked_div(height_per_line)
 					.unwrap())
 				.min(u32::try_from(MAX_LINES).unwrap()),
@@ -174,18 +314,20 @@ fn prepare_text_layout(
 		};
 
 		let bound = max_possible_lines.saturating_sub(1);
+		let mut style = TextStyle::default();
 		for (i, line) in wrapped_lines.iter().take(max_possible_lines).enumerate() {
 			let is_last_line = i == bound;
-			let needs_truncation = text_size(content_metrics.scale, content_font, line).0
+			let needs_truncation = line_width(line, style, content_metrics.scale, content_font)
 				> MAX_CONTENT_WIDTH
 				|| (is_last_line && wrapped_lines.len() > max_possible_lines);
 			let line_str = if needs_truncation && line.len() > ELLIPSIS.len() {
-				truncate_text(line, &content_metrics, content_font)
+				truncate_text(line, style, &content_metrics, content_font)
 			} else {
 				line.to_string()
 			};
 
-			final_lines.push(line_str);
+			final_lines.push((line_str, style));
+			style = end_style(line, style);
 		}
 
 		if !final_lines.is_empty() && content_metrics.scale.x >= MIN_CONTENT_FONT_SIZE {
@@ -208,14 +350,15 @@ fn prepare_text_layout(
 	let total_text_height = (lines_count.saturating_mul(content_metrics.line_height))
 		.saturating_add((lines_count.saturating_sub(1)).saturating_mul(LINE_SPACING));
 
-	let quoted_content_y = (QUOTE_HEIGHT.saturating_sub(total_text_height)) / 2;
+	let quoted_content_y =
+		(QUOTE_HEIGHT.saturating_sub(total_text_height.saturating_add(attachment_height))) / 2;
 
 	let mut current_y = quoted_content_y.cast_signed();
 
 	text_layout.content_lines.clear();
 
-	for line in final_lines {
-		let line_width = text_size(content_metrics.scale, content_font, &line).0;
+	for (line, style) in final_lines {
+		let line_width = line_width(&line, style, content_metrics.scale, content_font);
 		let centered_offset = (QUOTE_WIDTH
 			.saturating_sub(QUOTE_HEIGHT)
 			.saturating_sub(line_width))
@@ -226,13 +369,26 @@ fn prepare_text_layout(
 
 		text_layout
 			.content_lines
-			.push((line, line_x, line_x_reverse, current_y));
+			.push((line, style, line_x, line_x_reverse, current_y));
 
 		current_y = current_y.saturating_add(
 			(content_metrics.line_height.saturating_add(LINE_SPACING)).cast_signed(),
 		);
 	}
 
+	text_layout.attachment = attachment.map(|image| {
+		let centered_offset = (QUOTE_WIDTH
+			.saturating_sub(QUOTE_HEIGHT)
+			.saturating_sub(image.width()))
+			/ 3;
+		(
+			image,
+			(QUOTE_HEIGHT.saturating_add(centered_offset)).cast_signed(),
+			centered_offset.cast_signed(),
+			current_y,
+		)
+	});
+
 	let author_name_width = text_size(author_metrics.scale, author_font, author_name).0;
 	let author_x = (((QUOTE_WIDTH.saturating_sub(author_name_width)) / 2)
 		.saturating_add(QUOTE_HEIGHT / 2))
@@ -250,6 +406,7 @@ fn prepare_text_layout(
 	};
 	let author_y = (quoted_content_y
 		.saturating_add(total_text_height)
+		.saturating_add(attachment_height)
 		.saturating_add(author_y_offset))
 	.cast_signed();
 
@@ -258,28 +415,259 @@ fn prepare_text_layout(
 	text_layout.author_scale = author_metrics.scale;
 }
 
+fn draw_outlined_text(
+	img: &mut RgbaImage,
+	colours: TextColours,
+	x: i32,
+	y: i32,
+	scale: PxScale,
+	font: &FontArc,
+	text: &str,
+) {
+	if let Some(outline) = colours.outline {
+		for (dx, dy) in OUTLINE_OFFSETS {
+			draw_text_mut(
+				img,
+				outline,
+				x.saturating_add(dx),
+				y.saturating_add(dy),
+				scale,
+				font,
+				text,
+			);
+		}
+	}
+	draw_text_mut(img, colours.text, x, y, scale, font, text);
+}
+
+/// Picks the first font that can draw the character, going from the chosen
+/// one over NotoSansJP to the emoji font
+fn glyph_font<'a>(c: char, text: &str, content_font: &'a FontArc) -> &'a FontArc {
+	if emojis::get(text).is_some() {
+		return &EMOJI_FONT;
+	}
+	if content_font.glyph_id(c).0 != 0 {
+		return content_font;
+	}
+	match FONTS.get(CONTENT_FONT) {
+		Some(fallback) if fallback.glyph_id(c).0 != 0 => fallback,
+		_ => &EMOJI_FONT,
+	}
+}
+
+/// Draws the character leaning to the right, since the fonts come without
+/// italic variants
+#[expect(
+	clippy::as_conversions,
+	clippy::cast_possible_truncation,
+	clippy::cast_precision_loss,
+	clippy::cast_sign_loss
+)]
+fn draw_slanted_char(
+	img: &mut RgbaImage,
+	colour: Rgba<u8>,
+	x: i32,
+	y: i32,
+	scale: PxScale,
+	font: &FontArc,
+	c: char,
+) {
+	let ascent = font.as_scaled(scale).ascent();
+	let glyph = font
+		.glyph_id(c)
+		.with_scale_and_position(scale, point(0.0, ascent));
+	let Some(outlined) = font.outline_glyph(glyph) else {
+		return;
+	};
+	let bounds = outlined.px_bounds();
+	let Rgba([r, g, b, a]) = colour;
+	outlined.draw(|glyph_x, glyph_y, coverage| {
+		let row = glyph_y as f32;
+		let pixel_x =
+			(bounds.height() - row).mul_add(ITALIC_SLANT, x as f32 + bounds.min.x + glyph_x as f32);
+		let pixel_y = y as f32 + bounds.min.y + row;
+		if pixel_x < 0.0 || pixel_y < 0.0 {
+			return;
+		}
+		if let Some(pixel) = img.get_pixel_mut_checked(pixel_x as u32, pixel_y as u32) {
+			pixel.blend(&Rgba([
+				r,
+				g,
+				b,
+				(f32::from(a) * coverage.clamp(0.0, 1.0)).round() as u8,
+			]));
+		}
+	});
+}
+
+fn draw_styled_char(
+	img: &mut RgbaImage,
+	colours: TextColours,
+	x: i32,
+	y: i32,
+	scale: PxScale,
+	font: &FontArc,
+	c: char,
+	italic: bool,
+) {
+	if !italic {
+		draw_outlined_text(img, colours, x, y, scale, font, &c.to_string());
+		return;
+	}
+	if let Some(outline) = colours.outline {
+		for (dx, dy) in OUTLINE_OFFSETS {
+			draw_slanted_char(
+				img,
+				outline,
+				x.saturating_add(dx),
+				y.saturating_add(dy),
+				scale,
+				font,
+				c,
+			);
+		}
+	}
+	draw_slanted_char(img, colours.text, x, y, scale, font, c);
+}
+
+fn blend_rect(img: &mut RgbaImage, x: i32, y: i32, width: u32, height: u32, colour: Rgba<u8>) {
+	for dy in 0..height {
+		for dx in 0..width {
+			if let (Ok(pixel_x), Ok(pixel_y)) = (
+				u32::try_from(x.saturating_add_unsigned(dx)),
+				u32::try_from(y.saturating_add_unsigned(dy)),
+			) && let Some(pixel) = img.get_pixel_mut_checked(pixel_x, pixel_y)
+			{
+				pixel.blend(&colour);
+			}
+		}
+	}
+}
+
+fn blur_spoiler(img: &mut RgbaImage, x: i32, y: i32, width: u32, height: u32) {
+	let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+		return;
+	};
+	let width = width.min(img.width().saturating_sub(x));
+	let height = height.min(img.height().saturating_sub(y));
+	if width == 0 || height == 0 {
+		return;
+	}
+	let region = crop_imm(img, x, y, width, height).to_image();
+	replace(
+		img,
+		&fast_blur(&region, SPOILER_BLUR),
+		i64::from(x),
+		i64::from(y),
+	);
+}
+
+/// Draws the line character by character, so each can fall back to a font
+/// that has it, with placeholders replaced by their emoji images and style
+/// markers applied from the given starting style on
+fn draw_line(
+	img: &mut RgbaImage,
+	colours: TextColours,
+	x: i32,
+	y: i32,
+	scale: PxScale,
+	font: &FontArc,
+	line: &str,
+	emojis: &[Arc<RgbaImage>],
+	start: TextStyle,
+) -> i32 {
+	let line_height = emoji_size(scale, font);
+	let stroke = stroke_width(line_height);
+	let mut style = start;
+	let mut spoiler_start = None;
+	let mut step = x;
+	for c in line.chars() {
+		if let Some(marker) = marker_style(c) {
+			style = marker;
+			continue;
+		}
+		let width = if let Some(emoji) = placeholder_emoji(c, emojis) {
+			overlay(
+				img,
+				&resize(emoji, line_height, line_height, FilterType::Triangle),
+				i64::from(step),
+				i64::from(y),
+			);
+			line_height
+		} else {
+			let text = c.to_string();
+			let glyph_font = glyph_font(c, &text, font);
+			let width = char_width(c, style, scale, font);
+			if style.contains(TextStyle::CODE) {
+				blend_rect(img, step, y, width, line_height, CODE_BACKGROUND);
+			}
+			let italic = style.contains(TextStyle::ITALIC);
+			draw_styled_char(img, colours, step, y, scale, glyph_font, c, italic);
+			if style.contains(TextStyle::BOLD) {
+				draw_styled_char(
+					img,
+					colours,
+					step.saturating_add_unsigned(stroke),
+					y,
+					scale,
+					glyph_font,
+					c,
+					italic,
+				);
+			}
+			width
+		};
+		if style.contains(TextStyle::UNDERLINE) {
+			let underline_y = y.saturating_add_unsigned(line_height.saturating_sub(stroke));
+			blend_rect(img, step, underline_y, width, stroke, colours.text);
+		}
+		if style.contains(TextStyle::STRIKETHROUGH) {
+			let strike_y = y.saturating_add_unsigned(line_height / 2);
+			blend_rect(img, step, strike_y, width, stroke, colours.text);
+		}
+		if style.contains(TextStyle::SPOILER) {
+			spoiler_start.get_or_insert(step);
+		} else if let Some(spoiler_x) = spoiler_start.take() {
+			let spoiler_width = step.saturating_sub(spoiler_x).unsigned_abs();
+			blur_spoiler(img, spoiler_x, y, spoiler_width, line_height);
+		}
+		step = step.saturating_add_unsigned(width);
+	}
+	if let Some(spoiler_x) = spoiler_start {
+		let spoiler_width = step.saturating_sub(spoiler_x).unsigned_abs();
+		blur_spoiler(img, spoiler_x, y, spoiler_width, line_height);
+	}
+	step
+}
+
 fn apply_text_layout(
 	img: &mut RgbaImage,
 	layout: &TextLayout,
-	text_colour: Rgba<u8>,
+	colours: TextColours,
 	content_font: &FontArc,
 	author_font: &FontArc,
 	is_reverse: bool,
 	author_name: &str,
+	emojis: &[Arc<RgbaImage>],
 ) {
-	for (line, x, x_reverse, y) in &layout.content_lines {
-		let mut step = if is_reverse { *x_reverse } else { *x };
-		for c in line.chars().map(|c| c.to_string()) {
-			let font = if emojis::get(&c).is_some() {
-				&EMOJI_FONT
-			} else {
-				content_font
-			};
-
-			draw_text_mut(img, text_colour, step, *y, layout.content_scale, font, &c);
+	for (line, style, x, x_reverse, y) in &layout.content_lines {
+		let x = if is_reverse { *x_reverse } else { *x };
+		draw_line(
+			img,
+			colours,
+			x,
+			*y,
+			layout.content_scale,
+			content_font,
+			line,
+			emojis,
+			*style,
+		);
+	}
 
-			step = step.saturating_add(text_size(layout.content_scale, font, &c).0.cast_signed());
-		}
+	if let Some((attachment, x, x_reverse, y)) = &layout.attachment {
+		let x = if is_reverse { *x_reverse } else { *x };
+		overlay(img, attachment, i64::from(x), i64::from(*y));
 	}
 
 	let author_x = if is_reverse {
@@ -288,9 +676,9 @@ fn apply_text_layout(
 		layout.author_position.0
 	};
 
-	draw_text_mut(
+	draw_outlined_text(
 		img,
-		text_colour,
+		colours,
 		author_x,
 		layout.author_position.2,
 		layout.author_scale,
@@ -300,10 +688,14 @@ fn apply_text_layout(
 }
 
 pub fn resize_avatar(avatar_bytes: &[u8]) -> AResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+	resize_avatar_to(avatar_bytes, AVATAR_SQUARE_SIZE)
+}
+
+fn resize_avatar_to(avatar_bytes: &[u8], size: u32) -> AResult<ImageBuffer<Rgba<u8>, Vec<u8>>> {
 	Ok(resize(
 		&load_from_memory(avatar_bytes)?.to_rgba8(),
-		AVATAR_SQUARE_SIZE,
-		AVATAR_SQUARE_SIZE,
+		size,
+		size,
 		FilterType::Triangle,
 	))
 }
@@ -317,18 +709,16 @@ pub const fn avatar_position(is_reverse: bool) -> i64 {
 	}
 }
 
-pub fn get_theme(theme: &str) -> (ImageBuffer<Rgba<u8>, Vec<u8>>, Rgba<u8>) {
-	match theme {
-		"random" => {
-			let random_base =
-				create_solid_theme([fastrand::u8(..), fastrand::u8(..), fastrand::u8(..), 255]);
-			let random_color = Rgba([fastrand::u8(..), fastrand::u8(..), fastrand::u8(..), 255]);
-			(random_base, random_color)
-		}
+/// Renders a built-in theme, falling back to the default for unknown names
+pub fn get_theme(theme: &str) -> AResult<(RgbaImage, TextColours)> {
+	let theme = match theme {
+		"random" => QuoteTheme::random(),
 		_ => THEMES
 			.get(theme)
-			.map_or_else(|| THEMES.get(DEFAULT_THEME).unwrap().clone(), Clone::clone),
-	}
+			.unwrap_or_else(|| THEMES.get(DEFAULT_THEME).unwrap())
+			.clone(),
+	};
+	Ok((theme.render()?, theme.colours))
 }
 
 fn convert_to_bw(image: &mut RgbaImage) {
@@ -364,10 +754,11 @@ pub enum ImageType {
 pub fn quote_image(
 	image: &mut ImageType,
 	author_name: &str,
-	quoted_content: &str,
+	quoted_content: &InlineText,
+	attachment: Option<&RgbaImage>,
 	author_font: &FontArc,
 	content_font: &FontArc,
-	text_colour: Rgba<u8>,
+	colours: TextColours,
 	mut img: ImageBuffer<Rgba<u8>, Vec<u8>>,
 	text_layout: &mut TextLayout,
 	avatar_position: i64,
@@ -378,10 +769,11 @@ pub fn quote_image(
 ) -> AResult<()> {
 	if new_font {
 		prepare_text_layout(
-			quoted_content,
+			&quoted_content.text,
 			author_name,
 			content_font,
 			author_font,
+			attachment,
 			text_layout,
 		);
 	}
@@ -397,11 +789,12 @@ pub fn quote_image(
 			apply_text_layout(
 				&mut img,
 				text_layout,
-				text_colour,
+				colours,
 				content_font,
 				author_font,
 				config.reverse,
 				author_name,
+				&quoted_content.emojis,
 			);
 			Ok(img.write_to(cursor, STATIC_FORMAT)?)
 		}
@@ -409,11 +802,12 @@ pub fn quote_image(
 			apply_text_layout(
 				&mut img,
 				text_layout,
-				text_colour,
+				colours,
 				content_font,
 				author_font,
 				config.reverse,
 				author_name,
+				&quoted_content.emojis,
 			);
 			let frames: Vec<_> = GifDecoder::new(cursor)?
 				.into_frames()
@@ -448,3 +842,189 @@ pub fn quote_image(
 		}
 	}
 }
+
+const WRAPPED_MARGIN: u32 = 48;
+const WRAPPED_AVATAR_SIZE: u32 = 160;
+const WRAPPED_TITLE_FONT_SIZE: f32 = 56.0;
+const WRAPPED_SUBTITLE_FONT_SIZE: f32 = 30.0;
+const WRAPPED_NUMBER_FONT_SIZE: f32 = 72.0;
+const WRAPPED_LABEL_FONT_SIZE: f32 = 28.0;
+const WRAPPED_STATS_Y: i32 = 250;
+const WRAPPED_LISTS_Y: i32 = 420;
+const WRAPPED_LIST_WIDTH: u32 = QUOTE_WIDTH / 2 - WRAPPED_MARGIN * 2;
+const WRAPPED_RIGHT_LIST_X: u32 = QUOTE_WIDTH / 2 + WRAPPED_MARGIN;
+
+pub struct WrappedCard {
+	pub username: String,
+	pub guild_name: String,
+	pub year: i32,
+	pub messages: i64,
+	pub quotes: i64,
+	pub minutes: i64,
+	pub top_words: Vec<String>,
+	pub top_songs: Vec<String>,
+}
+
+fn draw_wrapped_list(
+	img: &mut RgbaImage,
+	colour: Rgba<u8>,
+	font: &FontArc,
+	x: i32,
+	heading: &str,
+	entries: &[String],
+) {
+	let heading_scale = PxScale::from(WRAPPED_SUBTITLE_FONT_SIZE);
+	let entry_scale = PxScale::from(WRAPPED_LABEL_FONT_SIZE);
+	draw_text_mut(
+		img,
+		colour,
+		x,
+		WRAPPED_LISTS_Y,
+		heading_scale,
+		font,
+		heading,
+	);
+	let line_height = text_size(entry_scale, font, "Tg")
+		.1
+		.saturating_add(LINE_SPACING)
+		.cast_signed();
+	let mut y = WRAPPED_LISTS_Y.saturating_add(line_height.saturating_mul(2));
+	if entries.is_empty() {
+		draw_text_mut(img, colour, x, y, entry_scale, font, "Nothing yet");
+		return;
+	}
+	for (rank, entry) in entries.iter().enumerate() {
+		let line = fit_text(
+			&format!("{}. {entry}", rank.saturating_add(1)),
+			entry_scale,
+			font,
+			WRAPPED_LIST_WIDTH,
+		);
+		draw_text_mut(img, colour, x, y, entry_scale, font, &line);
+		y = y.saturating_add(line_height);
+	}
+}
+
+/// Renders the yearly recap card of a member with the given theme
+pub fn wrapped_image(
+	card: &WrappedCard,
+	avatar_bytes: &[u8],
+	title_font: &FontArc,
+	content_font: &FontArc,
+	theme: &str,
+	format: ImageFormat,
+	buffer: Vec<u8>,
+) -> AResult<Vec<u8>> {
+	let (mut img, colours) = get_theme(theme)?;
+	let text_colour = colours.text;
+
+	let avatar = resize(
+		&load_from_memory(avatar_bytes)?.to_rgba8(),
+		WRAPPED_AVATAR_SIZE,
+		WRAPPED_AVATAR_SIZE,
+		FilterType::Triangle,
+	);
+	overlay(
+		&mut img,
+		&avatar,
+		i64::from(WRAPPED_MARGIN),
+		i64::from(WRAPPED_MARGIN),
+	);
+
+	let text_x = WRAPPED_MARGIN
+		.saturating_mul(2)
+		.saturating_add(WRAPPED_AVATAR_SIZE)
+		.cast_signed();
+	let text_width = QUOTE_WIDTH
+		.saturating_sub(WRAPPED_MARGIN.saturating_mul(3))
+		.saturating_sub(WRAPPED_AVATAR_SIZE);
+	let title_scale = PxScale::from(WRAPPED_TITLE_FONT_SIZE);
+	let title = fit_text(
+		&format!("{}'s {}", card.username, card.year),
+		title_scale,
+		title_font,
+		text_width,
+	);
+	draw_text_mut(
+		&mut img,
+		text_colour,
+		text_x,
+		WRAPPED_MARGIN.saturating_add(24).cast_signed(),
+		title_scale,
+		title_font,
+		&title,
+	);
+	let subtitle_scale = PxScale::from(WRAPPED_SUBTITLE_FONT_SIZE);
+	let subtitle = fit_text(
+		&format!("Wrapped in {}", card.guild_name),
+		subtitle_scale,
+		content_font,
+		text_width,
+	);
+	draw_text_mut(
+		&mut img,
+		text_colour,
+		text_x,
+		WRAPPED_MARGIN.saturating_add(104).cast_signed(),
+		subtitle_scale,
+		content_font,
+		&subtitle,
+	);
+
+	let number_scale = PxScale::from(WRAPPED_NUMBER_FONT_SIZE);
+	let label_scale = PxScale::from(WRAPPED_LABEL_FONT_SIZE);
+	let column_width = QUOTE_WIDTH
+		.saturating_sub(WRAPPED_MARGIN.saturating_mul(2))
+		.saturating_div(3);
+	let number_height = text_size(number_scale, content_font, "0").1;
+	let stats = [
+		(card.messages, "messages sent"),
+		(card.minutes, "minutes of music"),
+		(card.quotes, "quotes made"),
+	];
+	let mut x = WRAPPED_MARGIN.cast_signed();
+	for (value, label) in stats {
+		draw_text_mut(
+			&mut img,
+			text_colour,
+			x,
+			WRAPPED_STATS_Y,
+			number_scale,
+			content_font,
+			&value.to_string(),
+		);
+		draw_text_mut(
+			&mut img,
+			text_colour,
+			x,
+			WRAPPED_STATS_Y
+				.saturating_add(number_height.cast_signed())
+				.saturating_add(LINE_SPACING.cast_signed()),
+			label_scale,
+			content_font,
+			label,
+		);
+		x = x.saturating_add(column_width.cast_signed());
+	}
+
+	draw_wrapped_list(
+		&mut img,
+		text_colour,
+		content_font,
+		WRAPPED_MARGIN.cast_signed(),
+		"Top words",
+		&card.top_words,
+	);
+	draw_wrapped_list(
+		&mut img,
+		text_colour,
+		content_font,
+		WRAPPED_RIGHT_LIST_X.cast_signed(),
+		"Top songs",
+		&card.top_songs,
+	);
+
+	let mut cursor = Cursor::new(buffer);
+	img.write_to(&mut cursor, format)?;
+	Ok(cursor.into_inner())
+}
@@ -0,0 +1,393 @@
+use std::{collections::HashMap, io::Cursor};
+
+use ab_glyph::{FontArc, PxScale};
+use anyhow::Result as AResult;
+use bytes::Bytes;
+use image::{
+	AnimationDecoder as _, Delay, Frame,
+	ImageFormat::Avif as STATIC_FORMAT,
+	Rgba, RgbaImage,
+	codecs::gif::{GifDecoder, GifEncoder, Repeat},
+	imageops::{FilterType, overlay, resize},
+};
+use imageproc::drawing::text_size;
+use jiff::Timestamp;
+use serenity::all::UserId;
+
+use crate::utils::{
+	helpers::url_bytes,
+	image::{
+		ELLIPSIS, LINE_SPACING, draw_line, end_style, fit_text,
+		inline::{InlineText, TextStyle},
+		line_width, resize_avatar_to,
+		theme::TextColours,
+		truncate_to_width,
+	},
+};
+
+pub const MAX_CONVERSATION_MESSAGES: u8 = 10;
+const CONVERSATION_WIDTH: u32 = 1000;
+const CONVERSATION_PADDING: u32 = 32;
+const CONVERSATION_AVATAR_SIZE: u32 = 80;
+const CONVERSATION_TEXT_X: u32 = CONVERSATION_PADDING * 2 + CONVERSATION_AVATAR_SIZE;
+const CONVERSATION_TEXT_WIDTH: u32 =
+	CONVERSATION_WIDTH - CONVERSATION_TEXT_X - CONVERSATION_PADDING;
+const NAME_FONT_SIZE: f32 = 34.0;
+const TIMESTAMP_FONT_SIZE: f32 = 22.0;
+const MESSAGE_FONT_SIZE: f32 = 30.0;
+const TIMESTAMP_GAP: i32 = 16;
+const GROUP_SPACING: u32 = 28;
+const MESSAGE_SPACING: u32 = 6;
+const MAX_MESSAGE_LINES: usize = 8;
+const MAX_AVATAR_FRAMES: usize = 30;
+/// Messages of the same author this close together share one header
+const GROUP_WINDOW_SECS: i64 = 7 * 60;
+
+const BACKGROUND_COLOUR: Rgba<u8> = Rgba([49, 51, 56, 255]);
+const NAME_COLOURS: TextColours = TextColours {
+	text: Rgba([242, 243, 245, 255]),
+	outline: None,
+};
+const TIMESTAMP_COLOURS: TextColours = TextColours {
+	text: Rgba([148, 155, 164, 255]),
+	outline: None,
+};
+const MESSAGE_COLOURS: TextColours = TextColours {
+	text: Rgba([219, 222, 225, 255]),
+	outline: None,
+};
+
+pub struct ConversationMessage {
+	pub author_id: UserId,
+	pub author_name: String,
+	pub avatar_url: String,
+	/// Unix timestamp in seconds
+	pub sent_at: i64,
+	pub content: InlineText,
+}
+
+pub struct ConversationImage {
+	pub bytes: Vec<u8>,
+	pub animated: bool,
+}
+
+/// Consecutive messages of one author, drawn under a single avatar and name
+struct MessageGroup<'a> {
+	first: &'a ConversationMessage,
+	/// Wrapped lines of every message along with the style each starts in
+	lines: Vec<(&'a InlineText, Vec<(String, TextStyle)>)>,
+	y: u32,
+	height: u32,
+}
+
+enum Avatar {
+	Static(RgbaImage),
+	Animated(Vec<(RgbaImage, Delay)>),
+}
+
+fn is_animated(avatar_url: &str) -> bool {
+	avatar_url.contains(".gif") || avatar_url.contains("format=gif")
+}
+
+/// Downloads the avatar of everyone taking part once
+pub async fn fetch_avatars(messages: &[ConversationMessage]) -> AResult<HashMap<String, Bytes>> {
+	let mut avatars = HashMap::new();
+	for message in messages {
+		if !avatars.contains_key(&message.avatar_url) {
+			let bytes = url_bytes(&message.avatar_url).await?;
+			avatars.insert(message.avatar_url.clone(), bytes);
+		}
+	}
+	Ok(avatars)
+}
+
+fn mask_circle(avatar: &mut RgbaImage) {
+	let radius = f64::from(avatar.width()) / 2.0;
+	for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+		let distance = (f64::from(x) + 0.5 - radius).hypot(f64::from(y) + 0.5 - radius);
+		if distance > radius
+			&& let Some(alpha) = pixel.0.get_mut(3)
+		{
+			*alpha = 0;
+		}
+	}
+}
+
+fn decode_avatar(avatar_url: &str, bytes: &Bytes) -> AResult<Avatar> {
+	if is_animated(avatar_url) {
+		let frames: Vec<_> = GifDecoder::new(Cursor::new(bytes.clone()))?
+			.into_frames()
+			.take(MAX_AVATAR_FRAMES)
+			.filter_map(Result::ok)
+			.map(|frame| {
+				let mut resized = resize(
+					frame.buffer(),
+					CONVERSATION_AVATAR_SIZE,
+					CONVERSATION_AVATAR_SIZE,
+					FilterType::Nearest,
+				);
+				mask_circle(&mut resized);
+				(resized, frame.delay())
+			})
+			.collect();
+		if !frames.is_empty() {
+			return Ok(Avatar::Animated(frames));
+		}
+	}
+	let mut avatar = resize_avatar_to(bytes, CONVERSATION_AVATAR_SIZE)?;
+	mask_circle(&mut avatar);
+	Ok(Avatar::Static(avatar))
+}
+
+/// Cuts the line to the width of the text column
+fn fit_line(line: &str, style: TextStyle, scale: PxScale, font: &FontArc) -> String {
+	if line_width(line, style, scale, font) <= CONVERSATION_TEXT_WIDTH {
+		line.to_owned()
+	} else {
+		truncate_to_width(line, CONVERSATION_TEXT_WIDTH, |text| {
+			line_width(text, style, scale, font)
+		})
+	}
+}
+
+/// Greedily breaks text into lines that fit the width, truncating long
+/// messages
+fn wrap_to_width(text: &str, scale: PxScale, font: &FontArc) -> Vec<(String, TextStyle)> {
+	let mut lines = Vec::new();
+	let mut style = TextStyle::default();
+	for paragraph in text.lines() {
+		let mut line = String::new();
+		for word in paragraph.split_whitespace() {
+			let candidate = if line.is_empty() {
+				word.to_owned()
+			} else {
+				format!("{line} {word}")
+			};
+			if line.is_empty()
+				|| line_width(&candidate, style, scale, font) <= CONVERSATION_TEXT_WIDTH
+			{
+				line = candidate;
+			} else {
+				lines.push((fit_line(&line, style, scale, font), style));
+				style = end_style(&line, style);
+				word.clone_into(&mut line);
+			}
+		}
+		lines.push((fit_line(&line, style, scale, font), style));
+		style = end_style(&line, style);
+	}
+	if lines.len() > MAX_MESSAGE_LINES {
+		lines.truncate(MAX_MESSAGE_LINES);
+		if let Some((last, style)) = lines.last_mut() {
+			let marked = format!("{last}{ELLIPSIS}");
+			*last = if line_width(&marked, *style, scale, font) <= CONVERSATION_TEXT_WIDTH {
+				marked
+			} else {
+				truncate_to_width(last, CONVERSATION_TEXT_WIDTH, |text| {
+					line_width(text, *style, scale, font)
+				})
+			};
+		}
+	}
+	lines
+}
+
+fn group_messages<'a>(
+	messages: &'a [ConversationMessage],
+	font: &FontArc,
+) -> (Vec<MessageGroup<'a>>, u32) {
+	let name_height = text_size(PxScale::from(NAME_FONT_SIZE), font, "Tg").1;
+	let line_height = text_size(PxScale::from(MESSAGE_FONT_SIZE), font, "Tg")
+		.1
+		.saturating_add(LINE_SPACING);
+
+	let mut groups: Vec<MessageGroup<'a>> = Vec::new();
+	let mut previous: Option<&ConversationMessage> = None;
+	for message in messages {
+		let lines = wrap_to_width(
+			&message.content.text,
+			PxScale::from(MESSAGE_FONT_SIZE),
+			font,
+		);
+		let continues = previous.is_some_and(|previous| {
+			previous.author_id == message.author_id
+				&& message.sent_at.saturating_sub(previous.sent_at) <= GROUP_WINDOW_SECS
+		});
+		if continues && let Some(group) = groups.last_mut() {
+			group.lines.push((&message.content, lines));
+		} else {
+			groups.push(MessageGroup {
+				first: message,
+				lines: vec![(&message.content, lines)],
+				y: 0,
+				height: 0,
+			});
+		}
+		previous = Some(message);
+	}
+
+	let mut y = CONVERSATION_PADDING;
+	for group in &mut groups {
+		let text_height = group.lines.iter().fold(
+			name_height.saturating_add(LINE_SPACING),
+			|height, (_, lines)| {
+				height
+					.saturating_add(
+						line_height.saturating_mul(u32::try_from(lines.len()).unwrap_or(u32::MAX)),
+					)
+					.saturating_add(MESSAGE_SPACING)
+			},
+		);
+		group.y = y;
+		group.height = text_height.max(CONVERSATION_AVATAR_SIZE);
+		y = y.saturating_add(group.height).saturating_add(GROUP_SPACING);
+	}
+	let height = y
+		.saturating_sub(GROUP_SPACING)
+		.saturating_add(CONVERSATION_PADDING);
+	(groups, height)
+}
+
+fn timestamp_label(sent_at: i64) -> String {
+	Timestamp::from_second(sent_at).map_or_else(
+		|_| String::new(),
+		|timestamp| timestamp.strftime("%Y-%m-%d %H:%M UTC").to_string(),
+	)
+}
+
+fn draw_group(img: &mut RgbaImage, group: &MessageGroup<'_>, font: &FontArc) {
+	let name_scale = PxScale::from(NAME_FONT_SIZE);
+	let timestamp_scale = PxScale::from(TIMESTAMP_FONT_SIZE);
+	let message_scale = PxScale::from(MESSAGE_FONT_SIZE);
+	let name_height = text_size(name_scale, font, "Tg").1;
+	let timestamp_height = text_size(timestamp_scale, font, "Tg").1;
+	let line_height = text_size(message_scale, font, "Tg")
+		.1
+		.saturating_add(LINE_SPACING);
+
+	let text_x = CONVERSATION_TEXT_X.cast_signed();
+	let name = fit_text(
+		&group.first.author_name,
+		name_scale,
+		font,
+		CONVERSATION_TEXT_WIDTH / 2,
+	);
+	let name_end = draw_line(
+		img,
+		NAME_COLOURS,
+		text_x,
+		group.y.cast_signed(),
+		name_scale,
+		font,
+		&name,
+		&[],
+		TextStyle::default(),
+	);
+	draw_line(
+		img,
+		TIMESTAMP_COLOURS,
+		name_end.saturating_add(TIMESTAMP_GAP),
+		group
+			.y
+			.saturating_add(name_height.saturating_sub(timestamp_height))
+			.cast_signed(),
+		timestamp_scale,
+		font,
+		&timestamp_label(group.first.sent_at),
+		&[],
+		TextStyle::default(),
+	);
+
+	let mut y = group
+		.y
+		.saturating_add(name_height)
+		.saturating_add(LINE_SPACING);
+	for (content, lines) in &group.lines {
+		for (line, style) in lines {
+			draw_line(
+				img,
+				MESSAGE_COLOURS,
+				text_x,
+				y.cast_signed(),
+				message_scale,
+				font,
+				line,
+				&content.emojis,
+				*style,
+			);
+			y = y.saturating_add(line_height);
+		}
+		y = y.saturating_add(MESSAGE_SPACING);
+	}
+}
+
+/// Renders the messages as a chat screenshot, animated if anyone taking part
+/// has an animated avatar
+pub fn conversation_image(
+	messages: &[ConversationMessage],
+	avatars: &HashMap<String, Bytes>,
+	font: &FontArc,
+	buffer: Vec<u8>,
+) -> AResult<ConversationImage> {
+	let (groups, height) = group_messages(messages, font);
+	let mut img = RgbaImage::from_pixel(CONVERSATION_WIDTH, height, BACKGROUND_COLOUR);
+
+	let mut decoded: HashMap<&str, Avatar> = HashMap::new();
+	for (avatar_url, bytes) in avatars {
+		decoded.insert(avatar_url.as_str(), decode_avatar(avatar_url, bytes)?);
+	}
+
+	let mut animated = Vec::new();
+	for group in &groups {
+		draw_group(&mut img, group, font);
+		let position = (i64::from(CONVERSATION_PADDING), i64::from(group.y));
+		match decoded.get(group.first.avatar_url.as_str()) {
+			Some(Avatar::Static(avatar)) => overlay(&mut img, avatar, position.0, position.1),
+			Some(Avatar::Animated(frames)) => animated.push((frames, position)),
+			None => {}
+		}
+	}
+
+	let mut cursor = Cursor::new(buffer);
+	if animated.is_empty() {
+		img.write_to(&mut cursor, STATIC_FORMAT)?;
+		return Ok(ConversationImage {
+			bytes: cursor.into_inner(),
+			animated: false,
+		});
+	}
+
+	// Every avatar loops on its own, the longest one sets the length
+	let frame_count = animated
+		.iter()
+		.map(|(frames, _)| frames.len())
+		.max()
+		.unwrap_or_default();
+	let frames = (0..frame_count).map(|index| {
+		let mut frame = img.clone();
+		let mut delay = None;
+		for (avatar_frames, (x, y)) in &animated {
+			if let Some((avatar, avatar_delay)) =
+				avatar_frames.get(index.checked_rem(avatar_frames.len()).unwrap_or_default())
+			{
+				overlay(&mut frame, avatar, *x, *y);
+				delay.get_or_insert(*avatar_delay);
+			}
+		}
+		Frame::from_parts(
+			frame,
+			0,
+			0,
+			delay.unwrap_or_else(|| Delay::from_numer_denom_ms(100, 1)),
+		)
+	});
+	{
+		let mut encoder = GifEncoder::new_with_speed(&mut cursor, 10);
+		encoder.set_repeat(Repeat::Infinite)?;
+		encoder.encode_frames(frames)?;
+	}
+	Ok(ConversationImage {
+		bytes: cursor.into_inner(),
+		animated: true,
+	})
+}
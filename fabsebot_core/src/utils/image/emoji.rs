@@ -0,0 +1,107 @@
+use std::{
+	iter::once,
+	sync::{Arc, LazyLock},
+	time::Duration,
+};
+
+use anyhow::Result as AResult;
+use image::{RgbaImage, load_from_memory};
+use mini_moka::sync::Cache;
+use serenity::all::EmojiId;
+use tracing::warn;
+use twemoji_assets::png::PngTwemojiAsset;
+use winnow::{
+	ModalResult, Parser as _,
+	ascii::digit1,
+	combinator::{delimited, opt, separated_pair},
+	token::take_while,
+};
+
+use crate::utils::helpers::url_bytes;
+
+/// Long enough for family and flag sequences
+const MAX_EMOJI_CHARS: usize = 10;
+const EMOJI_CACHE_CAPACITY: u64 = 500;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum EmojiKey {
+	Unicode(&'static str),
+	Custom(EmojiId),
+}
+
+/// Emoji images decoded once and reused for every quote afterwards
+static EMOJI_CACHE: LazyLock<Cache<EmojiKey, Arc<RgbaImage>>> = LazyLock::new(|| {
+	Cache::builder()
+		.max_capacity(EMOJI_CACHE_CAPACITY)
+		.time_to_idle(Duration::from_hours(1))
+		.build()
+});
+
+/// Parses `<:name:id>` and `<a:name:id>` into the name and id
+pub(super) fn custom_emoji<'a>(input: &mut &'a str) -> ModalResult<(&'a str, u64)> {
+	delimited(
+		('<', opt('a'), ':'),
+		separated_pair(
+			take_while(1.., |c: char| c.is_alphanumeric() || c == '_'),
+			':',
+			digit1.parse_to::<u64>().verify(|id| *id != u64::MAX),
+		),
+		'>',
+	)
+	.parse_next(input)
+}
+
+/// Skips the ASCII characters that can't begin an emoji, keycaps aside
+pub(super) const fn may_start_emoji(c: char) -> bool {
+	!c.is_ascii() || matches!(c, '#' | '*' | '0'..='9')
+}
+
+pub(super) async fn custom_emoji_image(id: EmojiId) -> Option<Arc<RgbaImage>> {
+	let key = EmojiKey::Custom(id);
+	if let Some(image) = EMOJI_CACHE.get(&key) {
+		return Some(image);
+	}
+	let fetched: AResult<RgbaImage> = async {
+		let bytes = url_bytes(&format!(
+			"https://cdn.discordapp.com/emojis/{id}.png?size=128"
+		))
+		.await?;
+		Ok(load_from_memory(&bytes)?.to_rgba8())
+	}
+	.await;
+	match fetched {
+		Ok(image) => {
+			let image = Arc::new(image);
+			EMOJI_CACHE.insert(key, image.clone());
+			Some(image)
+		}
+		Err(err) => {
+			warn!("Failed to fetch emoji {id}: {err}");
+			None
+		}
+	}
+}
+
+/// Longest emoji at the start of the text with a bundled image, along with
+/// its length in bytes
+pub(super) fn unicode_emoji_image(text: &str) -> Option<(usize, Arc<RgbaImage>)> {
+	let ends: Vec<usize> = text
+		.char_indices()
+		.skip(1)
+		.map(|(index, _)| index)
+		.chain(once(text.len()))
+		.take(MAX_EMOJI_CHARS)
+		.collect();
+	ends.into_iter().rev().find_map(|end| {
+		let emoji = emojis::get(text.get(..end)?)?;
+		let key = EmojiKey::Unicode(emoji.as_str());
+		if let Some(image) = EMOJI_CACHE.get(&key) {
+			return Some((end, image));
+		}
+		let asset = PngTwemojiAsset::from_emoji(emoji.as_str())
+			.or_else(|| PngTwemojiAsset::from_emoji(&emoji.as_str().replace('\u{fe0f}', "")))?;
+		let image = Arc::new(load_from_memory(asset).ok()?.to_rgba8());
+		EMOJI_CACHE.insert(key, image.clone());
+		Some((end, image))
+	})
+}
@@ -0,0 +1,59 @@
+use std::{sync::LazyLock, time::Duration};
+
+use ab_glyph::{Font as _, FontArc};
+use anyhow::{Result as AResult, bail};
+use fabsebot_db::guild::fetch_font;
+use mini_moka::sync::Cache;
+use serenity::all::GuildId;
+use sqlx::{Pool, Postgres};
+use tokio::task::spawn_blocking;
+
+pub const FONT_MAX_MIB: u32 = 5;
+pub const FONT_MAX_BYTES: u32 = FONT_MAX_MIB * 1024 * 1024;
+pub const FONT_EXTENSIONS: [&str; 2] = [".ttf", ".otf"];
+const FONT_CACHE_CAPACITY: u64 = 100;
+
+/// Fonts parsed once and reused for every quote afterwards
+static FONT_CACHE: LazyLock<Cache<(GuildId, String), FontArc>> = LazyLock::new(|| {
+	Cache::builder()
+		.max_capacity(FONT_CACHE_CAPACITY)
+		.time_to_idle(Duration::from_hours(1))
+		.build()
+});
+
+fn parse_font(bytes: Vec<u8>) -> AResult<FontArc> {
+	let Ok(font) = FontArc::try_from_vec(bytes) else {
+		bail!("it isn't a valid TrueType or OpenType font");
+	};
+	if font.glyph_count() == 0 {
+		bail!("it doesn't contain any glyphs");
+	}
+	Ok(font)
+}
+
+/// Parses an uploaded font to make sure quotes can be drawn with it
+pub async fn validate_font(bytes: Vec<u8>) -> AResult<FontArc> {
+	spawn_blocking(move || parse_font(bytes)).await?
+}
+
+/// Drops the cached font that got replaced or deleted
+pub fn forget_font(guild_id: GuildId, name: &str) {
+	FONT_CACHE.invalidate(&(guild_id, name.to_owned()));
+}
+
+pub async fn guild_font(
+	guild_id: GuildId,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> AResult<Option<FontArc>> {
+	let key = (guild_id, name.to_owned());
+	if let Some(font) = FONT_CACHE.get(&key) {
+		return Ok(Some(font));
+	}
+	let Some(bytes) = fetch_font(i64::from(guild_id), name, conn).await? else {
+		return Ok(None);
+	};
+	let font = validate_font(bytes).await?;
+	FONT_CACHE.insert(key, font.clone());
+	Ok(Some(font))
+}
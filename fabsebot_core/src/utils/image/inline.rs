@@ -0,0 +1,290 @@
+use std::{collections::HashMap, sync::Arc};
+
+use image::RgbaImage;
+use serenity::all::EmojiId;
+use tokio::task::JoinSet;
+
+use crate::utils::image::emoji::{
+	custom_emoji, custom_emoji_image, may_start_emoji, unicode_emoji_image,
+};
+
+/// Private use characters standing in for emojis in the laid out text
+const PLACEHOLDER_START: u32 = 0xE000;
+const PLACEHOLDER_END: u32 = 0xF8BF;
+/// Private use characters switching the style of the text after them, one for
+/// every combination of styles
+const STYLE_MARKER_START: u32 = 0xF8C0;
+const STYLE_MARKER_END: u32 = 0xF8FF;
+/// Markdown delimiters, the longer ones first so `**` wins over `*`
+const DELIMITERS: [(&str, TextStyle); 6] = [
+	("**", TextStyle::BOLD),
+	("__", TextStyle::UNDERLINE),
+	("~~", TextStyle::STRIKETHROUGH),
+	("||", TextStyle::SPOILER),
+	("*", TextStyle::ITALIC),
+	("_", TextStyle::ITALIC),
+];
+const DELIMITER_CHARS: [char; 4] = ['*', '_', '~', '|'];
+/// Most distinct custom emojis fetched for one text, the rest stay as their
+/// names
+const MAX_CUSTOM_EMOJIS: usize = 16;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStyle(u8);
+
+impl TextStyle {
+	pub const BOLD: Self = Self(1);
+	pub const CODE: Self = Self(1 << 4);
+	pub const ITALIC: Self = Self(1 << 1);
+	pub const SPOILER: Self = Self(1 << 5);
+	pub const STRIKETHROUGH: Self = Self(1 << 3);
+	pub const UNDERLINE: Self = Self(1 << 2);
+
+	#[must_use]
+	pub const fn contains(self, style: Self) -> bool {
+		self.0 & style.0 == style.0
+	}
+
+	const fn toggled(self, style: Self) -> Self {
+		Self(self.0 ^ style.0)
+	}
+
+	const fn with(self, style: Self) -> Self {
+		Self(self.0 | style.0)
+	}
+}
+
+/// Quote text with markdown turned into style markers and every emoji that
+/// has an image swapped for a placeholder, so wrapping and measuring see it
+/// as a single glyph
+#[derive(Clone, Default)]
+pub struct InlineText {
+	pub text: String,
+	pub emojis: Vec<Arc<RgbaImage>>,
+}
+
+impl InlineText {
+	/// Tokenizes markdown, custom emoji markup and Unicode emojis, fetching
+	/// the images of custom ones from Discord
+	pub async fn new(content: &str) -> Self {
+		let images = fetch_custom_emojis(content).await;
+		Self::tokenize(content, &images)
+	}
+
+	fn tokenize(content: &str, images: &HashMap<u64, Arc<RgbaImage>>) -> Self {
+		let mut inline = Self {
+			text: String::with_capacity(content.len()),
+			emojis: Vec::new(),
+		};
+		let mut style = TextStyle::default();
+		let mut written = TextStyle::default();
+		let mut rest = content;
+		while let Some(c) = rest.chars().next() {
+			let before = content
+				.get(..content.len().saturating_sub(rest.len()))
+				.and_then(|before| before.chars().next_back());
+			if c == '\\'
+				&& let Some(escaped) = rest.chars().nth(1).filter(char::is_ascii_punctuation)
+			{
+				inline.push(escaped, style, &mut written);
+				rest = rest.get(2..).unwrap_or_default();
+				continue;
+			}
+			if let Some((code, len)) = code_span(rest) {
+				for c in code.chars() {
+					inline.push(c, style.with(TextStyle::CODE), &mut written);
+				}
+				rest = rest.get(len..).unwrap_or_default();
+				continue;
+			}
+			if let Some((delimiter, toggle)) = delimiter(rest, before, style) {
+				style = style.toggled(toggle);
+				rest = rest.get(delimiter.len()..).unwrap_or_default();
+				continue;
+			}
+			let mut input = rest;
+			if let Some(placeholder) = placeholder(inline.emojis.len())
+				&& c == '<' && let Ok((name, id)) = custom_emoji(&mut input)
+			{
+				if let Some(image) = images.get(&id) {
+					inline.push_emoji(placeholder, image.clone(), style, &mut written);
+				} else {
+					for c in format!(":{name}:").chars() {
+						inline.push(c, style, &mut written);
+					}
+				}
+				rest = input;
+				continue;
+			}
+			if let Some(placeholder) = placeholder(inline.emojis.len())
+				&& may_start_emoji(c)
+				&& let Some((len, image)) = unicode_emoji_image(rest)
+			{
+				inline.push_emoji(placeholder, image, style, &mut written);
+				rest = rest.get(len..).unwrap_or_default();
+				continue;
+			}
+			inline.push(c, style, &mut written);
+			rest = rest.get(c.len_utf8()..).unwrap_or_default();
+		}
+		inline
+	}
+
+	/// Writes a style marker first if the style changed since the last
+	/// character
+	fn mark(&mut self, style: TextStyle, written: &mut TextStyle) {
+		if style != *written
+			&& let Some(marker) =
+				char::from_u32(STYLE_MARKER_START.saturating_add(u32::from(style.0)))
+		{
+			self.text.push(marker);
+			*written = style;
+		}
+	}
+
+	fn push(&mut self, c: char, style: TextStyle, written: &mut TextStyle) {
+		self.mark(style, written);
+		self.text.push(if is_reserved(c) {
+			char::REPLACEMENT_CHARACTER
+		} else {
+			c
+		});
+	}
+
+	fn push_emoji(
+		&mut self,
+		placeholder: char,
+		image: Arc<RgbaImage>,
+		style: TextStyle,
+		written: &mut TextStyle,
+	) {
+		self.mark(style, written);
+		self.text.push(placeholder);
+		self.emojis.push(image);
+	}
+}
+
+/// Distinct custom emojis outside of code, in order of appearance
+fn custom_emoji_ids(content: &str) -> Vec<u64> {
+	let mut ids = Vec::new();
+	let mut rest = content;
+	while let Some(c) = rest.chars().next()
+		&& ids.len() < MAX_CUSTOM_EMOJIS
+	{
+		if c == '\\'
+			&& rest
+				.chars()
+				.nth(1)
+				.is_some_and(|c| c.is_ascii_punctuation())
+		{
+			rest = rest.get(2..).unwrap_or_default();
+			continue;
+		}
+		if let Some((_, len)) = code_span(rest) {
+			rest = rest.get(len..).unwrap_or_default();
+			continue;
+		}
+		let mut input = rest;
+		if c == '<'
+			&& let Ok((_, id)) = custom_emoji(&mut input)
+		{
+			if !ids.contains(&id) {
+				ids.push(id);
+			}
+			rest = input;
+			continue;
+		}
+		rest = rest.get(c.len_utf8()..).unwrap_or_default();
+	}
+	ids
+}
+
+/// Fetches the images of the custom emojis in the text all at once
+async fn fetch_custom_emojis(content: &str) -> HashMap<u64, Arc<RgbaImage>> {
+	let mut fetches = JoinSet::new();
+	for id in custom_emoji_ids(content) {
+		fetches.spawn(async move { (id, custom_emoji_image(EmojiId::new(id)).await) });
+	}
+	let mut images = HashMap::new();
+	while let Some(fetched) = fetches.join_next().await {
+		if let Ok((id, Some(image))) = fetched {
+			images.insert(id, image);
+		}
+	}
+	images
+}
+
+/// Inline code or a code block at the start of the text, along with the
+/// length of it in bytes including the fences
+fn code_span(text: &str) -> Option<(&str, usize)> {
+	for fence in ["```", "`"] {
+		let Some(after) = text.strip_prefix(fence) else {
+			continue;
+		};
+		let Some(end) = after.find(fence) else {
+			continue;
+		};
+		let mut code = after.get(..end)?;
+		if fence == "```"
+			&& let Some((language, body)) = code.split_once('\n')
+			&& !language.contains(char::is_whitespace)
+		{
+			code = body.trim_end_matches('\n');
+		}
+		if code.is_empty() {
+			continue;
+		}
+		return Some((code, end.saturating_add(fence.len().saturating_mul(2))));
+	}
+	None
+}
+
+/// Markdown delimiter at the start of the text that opens or closes a style,
+/// requiring text right inside it like Discord does
+fn delimiter(
+	text: &str,
+	before: Option<char>,
+	style: TextStyle,
+) -> Option<(&'static str, TextStyle)> {
+	DELIMITERS.into_iter().find(|(delimiter, toggle)| {
+		let Some(after) = text.strip_prefix(delimiter) else {
+			return false;
+		};
+		if style.contains(*toggle) {
+			return before.is_some_and(|c| !c.is_whitespace());
+		}
+		let inner = after.trim_start_matches(DELIMITER_CHARS);
+		inner.chars().next().is_some_and(|c| !c.is_whitespace())
+			&& inner.contains(delimiter)
+			&& (*delimiter != "_" || !before.is_some_and(char::is_alphanumeric))
+	})
+}
+
+/// Characters of the private use area the layout claims for itself
+fn is_reserved(c: char) -> bool {
+	(PLACEHOLDER_START..=STYLE_MARKER_END).contains(&u32::from(c))
+}
+
+pub(super) fn is_placeholder(c: char) -> bool {
+	(PLACEHOLDER_START..=PLACEHOLDER_END).contains(&u32::from(c))
+}
+
+fn placeholder(index: usize) -> Option<char> {
+	let index = u32::try_from(index).ok()?;
+	char::from_u32(PLACEHOLDER_START.checked_add(index)?).filter(|c| is_placeholder(*c))
+}
+
+/// The emoji image a placeholder character stands for
+pub(super) fn placeholder_emoji(c: char, emojis: &[Arc<RgbaImage>]) -> Option<&RgbaImage> {
+	let index = u32::from(c).checked_sub(PLACEHOLDER_START)?;
+	emojis.get(usize::try_from(index).ok()?).map(Arc::as_ref)
+}
+
+/// The style a marker character switches to
+pub(super) fn marker_style(c: char) -> Option<TextStyle> {
+	let style = u32::from(c).checked_sub(STYLE_MARKER_START)?;
+	u8::try_from(style)
+		.ok()
+		.filter(|_| u32::from(c) <= STYLE_MARKER_END)
+		.map(TextStyle)
+}
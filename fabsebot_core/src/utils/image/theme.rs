@@ -0,0 +1,196 @@
+use std::io::Cursor;
+
+use anyhow::{Error as AError, Result as AResult, bail};
+use fabsebot_db::guild::GuildQuoteTheme;
+use image::{
+	ImageFormat, Rgba, RgbaImage,
+	imageops::{FilterType, fast_blur},
+	load_from_memory,
+};
+
+use crate::utils::image::{QUOTE_HEIGHT, QUOTE_WIDTH, create_solid_theme};
+
+pub const MAX_THEME_BLUR: f32 = 50.0;
+pub const THEME_IMAGE_MAX_MIB: u32 = 8;
+pub const THEME_IMAGE_MAX_BYTES: u32 = THEME_IMAGE_MAX_MIB * 1024 * 1024;
+
+#[derive(Clone)]
+pub enum ThemeBackground {
+	Solid(Rgba<u8>),
+	/// Blends between the colours along the angle in degrees, 0 running left
+	/// to right
+	LinearGradient {
+		from: Rgba<u8>,
+		to: Rgba<u8>,
+		angle: u16,
+	},
+	/// Blends from the centre out to the corners
+	RadialGradient {
+		inner: Rgba<u8>,
+		outer: Rgba<u8>,
+	},
+	/// Picture cropped to the quote, blurred by the sigma and darkened by the
+	/// percentage
+	Image {
+		bytes: Vec<u8>,
+		blur: f32,
+		dim: u8,
+	},
+}
+
+#[derive(Clone, Copy)]
+pub struct TextColours {
+	pub text: Rgba<u8>,
+	pub outline: Option<Rgba<u8>>,
+}
+
+#[derive(Clone)]
+pub struct QuoteTheme {
+	pub background: ThemeBackground,
+	pub colours: TextColours,
+}
+
+impl QuoteTheme {
+	#[must_use]
+	pub const fn solid(background: [u8; 4], text: [u8; 4]) -> Self {
+		Self {
+			background: ThemeBackground::Solid(Rgba(background)),
+			colours: TextColours {
+				text: Rgba(text),
+				outline: None,
+			},
+		}
+	}
+
+	#[must_use]
+	pub fn random() -> Self {
+		Self::solid(
+			[fastrand::u8(..), fastrand::u8(..), fastrand::u8(..), 255],
+			[fastrand::u8(..), fastrand::u8(..), fastrand::u8(..), 255],
+		)
+	}
+
+	/// Paints the background the quote gets drawn on
+	pub fn render(&self) -> AResult<RgbaImage> {
+		Ok(match &self.background {
+			ThemeBackground::Solid(colour) => create_solid_theme(colour.0),
+			ThemeBackground::LinearGradient { from, to, angle } => {
+				let (sin, cos) = f64::from(*angle).to_radians().sin_cos();
+				let half_width = f64::from(QUOTE_WIDTH) / 2.0;
+				let half_height = f64::from(QUOTE_HEIGHT) / 2.0;
+				let reach = half_width.mul_add(cos.abs(), half_height * sin.abs());
+				RgbaImage::from_fn(QUOTE_WIDTH, QUOTE_HEIGHT, |x, y| {
+					let projected = (f64::from(x) - half_width)
+						.mul_add(cos, (f64::from(y) - half_height) * sin);
+					mix(*from, *to, (projected + reach) / (reach * 2.0))
+				})
+			}
+			ThemeBackground::RadialGradient { inner, outer } => {
+				let half_width = f64::from(QUOTE_WIDTH) / 2.0;
+				let half_height = f64::from(QUOTE_HEIGHT) / 2.0;
+				let reach = half_width.hypot(half_height);
+				RgbaImage::from_fn(QUOTE_WIDTH, QUOTE_HEIGHT, |x, y| {
+					let distance = (f64::from(x) - half_width).hypot(f64::from(y) - half_height);
+					mix(*inner, *outer, distance / reach)
+				})
+			}
+			ThemeBackground::Image { bytes, blur, dim } => {
+				let mut img = load_from_memory(bytes)?
+					.resize_to_fill(QUOTE_WIDTH, QUOTE_HEIGHT, FilterType::Triangle)
+					.to_rgba8();
+				if *blur > 0.0 {
+					img = fast_blur(&img, *blur);
+				}
+				let keep = u16::from(100_u8.saturating_sub(*dim));
+				if keep < 100 {
+					for pixel in img.pixels_mut() {
+						for channel in pixel.0.iter_mut().take(3) {
+							*channel = u8::try_from(u16::from(*channel).saturating_mul(keep) / 100)
+								.unwrap_or(u8::MAX);
+						}
+					}
+				}
+				img
+			}
+		})
+	}
+}
+
+#[expect(
+	clippy::as_conversions,
+	clippy::cast_possible_truncation,
+	clippy::cast_sign_loss
+)]
+fn mix(from: Rgba<u8>, to: Rgba<u8>, progress: f64) -> Rgba<u8> {
+	let progress = progress.clamp(0.0, 1.0);
+	let mut mixed = from;
+	for (channel, target) in mixed.0.iter_mut().zip(to.0) {
+		let start = f64::from(*channel);
+		*channel = (f64::from(target) - start).mul_add(progress, start).round() as u8;
+	}
+	mixed
+}
+
+/// Crops an uploaded picture to the size of a quote so only that gets stored
+pub fn prepare_theme_background(bytes: &[u8]) -> AResult<Vec<u8>> {
+	let img = load_from_memory(bytes)?
+		.resize_to_fill(QUOTE_WIDTH, QUOTE_HEIGHT, FilterType::Triangle)
+		.to_rgb8();
+	let mut cursor = Cursor::new(Vec::new());
+	img.write_to(&mut cursor, ImageFormat::Jpeg)?;
+	Ok(cursor.into_inner())
+}
+
+/// Reads `#RRGGBB` or `RRGGBB` into `0xRRGGBB`
+#[must_use]
+pub fn parse_hex_colour(input: &str) -> Option<i32> {
+	let hex = input.trim().trim_start_matches('#');
+	if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+		return None;
+	}
+	i32::from_str_radix(hex, 16).ok()
+}
+
+fn colour(rgb: i32) -> Rgba<u8> {
+	let [_, r, g, b] = rgb.to_be_bytes();
+	Rgba([r, g, b, 255])
+}
+
+impl TryFrom<GuildQuoteTheme> for QuoteTheme {
+	type Error = AError;
+
+	fn try_from(theme: GuildQuoteTheme) -> AResult<Self> {
+		let primary = colour(theme.primary_colour);
+		let secondary = theme.secondary_colour.map_or(primary, colour);
+		let background = match theme.style.as_str() {
+			"solid" => ThemeBackground::Solid(primary),
+			"linear" => ThemeBackground::LinearGradient {
+				from: primary,
+				to: secondary,
+				angle: theme.angle.unsigned_abs(),
+			},
+			"radial" => ThemeBackground::RadialGradient {
+				inner: primary,
+				outer: secondary,
+			},
+			"image" => {
+				let Some(bytes) = theme.background else {
+					bail!("Image theme {} has no background", theme.name);
+				};
+				ThemeBackground::Image {
+					bytes,
+					blur: theme.blur.clamp(0.0, MAX_THEME_BLUR),
+					dim: u8::try_from(theme.dim.clamp(0, 100)).unwrap_or_default(),
+				}
+			}
+			style => bail!("Unknown theme style {style}"),
+		};
+		Ok(Self {
+			background,
+			colours: TextColours {
+				text: colour(theme.text_colour),
+				outline: theme.outline_colour.map(colour),
+			},
+		})
+	}
+}
@@ -1,6 +1,17 @@
+pub mod backend;
+pub mod library;
+mod loudness;
+pub mod nodes;
+pub mod party;
+pub mod recording;
+pub mod requests;
+pub mod sessions;
+pub mod soundboard;
+pub mod sources;
+
 use std::{
 	borrow::Cow,
-	collections::VecDeque,
+	collections::{HashSet, VecDeque},
 	fmt::Write as _,
 	sync::{
 		Arc,
@@ -11,29 +22,35 @@ use std::{
 
 use anyhow::{Result as AResult, bail};
 use bytes::Bytes;
+use fabsebot_db::guild::{MusicSettings, fetch_music_settings};
 use lavalink_rs::{
 	client::LavalinkClient,
 	hook,
 	model::{
-		UserId as LavaUserId, client::NodeDistributionStrategy, events, search::SearchEngines,
-		track::TrackLoadData,
+		UserId as LavaUserId,
+		client::NodeDistributionStrategy,
+		events,
+		player::{Equalizer, Filters, Karaoke, LowPass, Rotation, Timescale},
+		search::SearchEngines,
+		track::{TrackData, TrackLoadData},
 	},
 	node::NodeBuilder,
 	player_context::{PlayerContext, TrackInQueue},
 };
 use metrics::counter;
-use poise::ReplyHandle;
+use poise::{ChoiceParameter, ReplyHandle};
 use serde::{Deserialize, Serialize};
 use serde_json::{from_value, to_value};
 use serenity::{
 	all::{
-		ButtonStyle, ChannelId, Colour, ComponentInteraction, ComponentInteractionCollector,
-		Context as SerenityContext, CreateActionRow, CreateButton, CreateContainer, CreateMessage,
-		EditMessage, Error as SerenityError, GenericChannelId, GuildId, MessageId, UserId,
+		Attachment, ButtonStyle, ChannelId, Colour, ComponentInteraction,
+		ComponentInteractionCollector, Context as SerenityContext, CreateActionRow, CreateButton,
+		CreateContainer, CreateInteractionResponse, CreateInteractionResponseMessage,
+		Error as SerenityError, GenericChannelId, GuildId, Member, Message, MessageId, UserId,
 	},
 	async_trait,
 	builder::{CreateComponent, CreateContainerComponent, CreateSection},
-	futures::StreamExt as _,
+	futures::{StreamExt as _, stream},
 	http::Typing,
 };
 use songbird::{
@@ -41,24 +58,24 @@ use songbird::{
 	Songbird, TrackEvent,
 	driver::Bitrate,
 	input::{Compose as _, Input, LiveInput, YoutubeDl, cached::Compressed},
-	tracks::{LoopState, PlayMode, Track},
+	tracks::{PlayMode, Track},
 };
 use sqlx::{
 	Error, Pool, Postgres, postgres::PgQueryResult, query, query_as, query_scalar,
 	types::time::OffsetDateTime,
 };
 use tokio::{
-	select,
+	select, spawn,
 	sync::{Mutex, mpsc, watch::Receiver},
-	time::sleep,
+	time::{MissedTickBehavior, interval, sleep},
 };
 use tracing::{error, warn};
-use url::Url;
 use uuid::Uuid;
 
 use crate::{
 	config::{
-		constants::{FAILED_SONG_FETCH, MESSAGE_LIMIT, QUEUEING_MSG},
+		constants::MESSAGE_LIMIT,
+		settings::LavalinkNodeConfig,
 		types::{
 			ContextType, Data, GuildCache, HTTP_CLIENT, MusicQueue, MusicQueueData, SContext,
 			bot_context, utils_config,
@@ -67,12 +84,43 @@ use crate::{
 	events::interaction::FEEDBACK_BUTTON_CUSTOM_ID,
 	log_error,
 	stats::counters::METRICS,
-	utils::helpers::{
-		edit_message_container, get_lyrics, guild_cache, reply_container, separator,
-		silent_message, text_display, thumbnail_section, visit_page_button,
+	utils::{
+		helpers::{
+			Lyrics, SyncedLine, edit_message_container, get_lyrics, guild_cache, message_container,
+			reply_container, separator, silent_message, text_display, thumbnail_section, url_bytes,
+			visit_page_button,
+		},
+		voice::{
+			backend::{AudioBackend, PlaybackState, PlayerAction, fetch_backend},
+			loudness::{AudioSource, normalization_gain},
+			nodes::{pick_node, register_nodes},
+			party::{
+				MirroredTrack, PARTY_GUEST_DENIAL, apply_to_peers, is_party_guest, leave_party,
+				mirror_to_party,
+			},
+			recording::end_recording,
+			requests::{RequestStatus, mark_request},
+			sessions::{delete_session, is_shutting_down, save_session},
+			sources::{
+				StreamingMetadata, TrackSource, canonical_url, is_attachment_url,
+				streaming_metadata,
+			},
+		},
 	},
 };
 
+pub const DEFAULT_VOLUME: u16 = 100;
+const LAVALINK_MAX_VOLUME: f32 = 1000.0;
+/// Bigger uploads play without getting their loudness measured first
+const MAX_MEASURED_UPLOAD: u32 = 50 * 1024 * 1024;
+/// Links loaded from Lavalink at the same time when queueing many at once
+const PARALLEL_TRACK_LOADS: usize = 8;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const LYRICS_REFRESH_INTERVAL: Duration = Duration::from_secs(3);
+const KARAOKE_CONTEXT_LINES: usize = 3;
+const PROGRESS_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const PROGRESS_BAR_WIDTH: usize = 16;
+const UNKNOWN_THUMBNAIL: &str = "https://c.tenor.com/gRnPiR82No4AAAAd/tenor.gif";
 const EMPTY_VOICE_CHAN_MSG: &str = "No voice channel with at least 1 user found :/";
 pub const ALREADY_IN_VOICE_CHAN_MSG: &str =
 	"Bruh I'm already in a voice channel!\nUse leave_voice-command if I should leave the channel";
@@ -99,9 +147,19 @@ impl VoiceEventHandler for DriverDisconnectHandler {
 			&& self.guild_cache.music_data.is_songbird_connected()
 		{
 			self.guild_cache.music_data.disconnected();
+			leave_party(GuildId::new(disconnect_data.guild_id.0.get()));
 			if let Err(err) = self.music_manager.remove(disconnect_data.guild_id).await {
 				error!("Failed to remove call (songbird): {err}");
 			}
+			if !is_shutting_down()
+				&& let Err(err) = delete_session(
+					&bot_context().data,
+					GuildId::new(disconnect_data.guild_id.0.get()),
+				)
+				.await
+			{
+				warn!("Failed to forget music session: {err}");
+			}
 		}
 		None
 	}
@@ -136,11 +194,72 @@ impl VoiceEventHandler for ClientDisconnectHandler {
 	}
 }
 
+#[must_use]
+pub fn format_timestamp(duration: Duration) -> String {
+	let secs = duration.as_secs();
+	format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+fn progress_bar(position: Duration, total: Duration) -> String {
+	let filled = position
+		.as_millis()
+		.saturating_mul(u128::try_from(PROGRESS_BAR_WIDTH).unwrap_or_default())
+		.checked_div(total.as_millis())
+		.map_or(0, |filled| {
+			usize::try_from(filled).unwrap_or(PROGRESS_BAR_WIDTH)
+		})
+		.min(PROGRESS_BAR_WIDTH);
+	format!(
+		"{}🔘{}",
+		"▬".repeat(filled),
+		"▬".repeat(PROGRESS_BAR_WIDTH.saturating_sub(filled))
+	)
+}
+
+fn now_playing_text(
+	author_id: UserId,
+	optional_data: &OptionalTrackData,
+	queue_size: usize,
+	playback: Option<&PlaybackState>,
+) -> String {
+	let duration = Duration::from_secs(optional_data.duration_sec.cast_unsigned());
+	let progress = playback.map_or_else(
+		|| format!("**Duration:** {}", format_timestamp(duration)),
+		|playback| {
+			let position = playback.position.min(duration);
+			format!(
+				"`{}` {} `{}` (-{})\n**Status:** {}{}",
+				format_timestamp(position),
+				progress_bar(position, duration),
+				format_timestamp(duration),
+				format_timestamp(duration.saturating_sub(position)),
+				if playback.paused { "Paused" } else { "Playing" },
+				if playback.looping { ", looping" } else { "" }
+			)
+		},
+	);
+	let up_next = playback
+		.and_then(|playback| playback.next_title.as_deref())
+		.map_or_else(String::new, |title| format!("\n**Up next:** {title}"));
+	let effects = playback
+		.and_then(|playback| playback.effects.as_deref())
+		.map_or_else(String::new, |effects| format!("\n{effects}"));
+	format!(
+		"# {}\n**Added by:** <@{author_id}>\n**Artist:** {}\n**Source:** \
+		 {}\n{progress}{effects}{up_next}\n**Queue size:** {}",
+		optional_data.title,
+		optional_data.artist,
+		optional_data.source.name(),
+		queue_size.saturating_sub(1)
+	)
+}
+
 fn create_components<'a>(
 	author_id: UserId,
 	metadata: &'a TrackPlayData,
 	queue_size: usize,
 	payload_type: &PayloadType,
+	seek_step: i16,
 ) -> (
 	CreateContainerComponent<'a>,
 	CreateContainerComponent<'a>,
@@ -150,22 +269,10 @@ fn create_components<'a>(
 
 	let thumbnail_section = {
 		let (text, thumbnail) = optional_data.map_or_else(
-			|| {
-				thumbnail_section(
-					"# Unknown song data :/",
-					"https://c.tenor.com/gRnPiR82No4AAAAd/tenor.gif",
-				)
-			},
+			|| thumbnail_section("# Unknown song data :/", UNKNOWN_THUMBNAIL),
 			|optional_data| {
 				thumbnail_section(
-					format!(
-						"# {}\n**Added by:** <@{author_id}>\n**Artist:** {}\n**Duration:** \
-						 {}s\n**Queue size:** {}",
-						optional_data.title.as_str(),
-						optional_data.artist.as_str(),
-						optional_data.duration_sec,
-						queue_size.saturating_sub(1)
-					),
+					now_playing_text(author_id, optional_data, queue_size, None),
 					optional_data.thumbnail_url.as_str(),
 				)
 			},
@@ -174,10 +281,8 @@ fn create_components<'a>(
 	};
 
 	let (primary_len, additional_len) = match (payload_type, optional_data.is_some()) {
-		(PayloadType::Song, true) => (5, 4),
-		(PayloadType::Song, false) => (3, 2),
-		(PayloadType::Lavalink, true) => (5, 3),
-		(PayloadType::Lavalink, false) => (3, 1),
+		(PayloadType::Song | PayloadType::Lavalink, true) => (5, 4),
+		(PayloadType::Song | PayloadType::Lavalink, false) => (3, 2),
 		(PayloadType::Custom, _) => (1, 2),
 		_ => (1, 1),
 	};
@@ -206,12 +311,12 @@ fn create_components<'a>(
 			primary_buttons.push(
 				CreateButton::new("forward")
 					.style(ButtonStyle::Primary)
-					.label("Seek forward 10s"),
+					.label(format!("Seek forward {seek_step}s")),
 			);
 			primary_buttons.push(
 				CreateButton::new("backwards")
 					.style(ButtonStyle::Primary)
-					.label("Seek backwards 10s"),
+					.label(format!("Seek backwards {seek_step}s")),
 			);
 		}
 	}
@@ -219,7 +324,10 @@ fn create_components<'a>(
 	let primary_row =
 		CreateContainerComponent::ActionRow(CreateActionRow::buttons(primary_buttons));
 
-	if *payload_type == PayloadType::Song || *payload_type == PayloadType::Custom {
+	if matches!(
+		payload_type,
+		PayloadType::Song | PayloadType::Lavalink | PayloadType::Custom
+	) {
 		additional_buttons.push(
 			CreateButton::new("retry")
 				.style(ButtonStyle::Secondary)
@@ -245,287 +353,464 @@ fn create_components<'a>(
 	(thumbnail_section, primary_row, additional_buttons)
 }
 
-enum PlayerAction {
-	Pause,
-	Skip,
-	Clear,
-	SeekForward(i64),
-	SeekBackward(i64),
-	Loop,
+enum ControlDecision {
+	Allowed,
+	Voted { votes: usize, needed: usize },
+	Denied(&'static str),
 }
 
-enum AudioBackend {
-	Songbird(Arc<Mutex<Call>>),
-	Lavalink(PlayerContext),
+/// Non-bot users sharing the bot's voice channel
+fn voice_listeners(guild_id: GuildId) -> Vec<UserId> {
+	let ctx = bot_context();
+	let bot_id = ctx.cache.current_user().id;
+	let Some(guild) = ctx.cache.guild(guild_id) else {
+		return Vec::new();
+	};
+	let Some(bot_channel) = guild
+		.voice_states
+		.get(&bot_id)
+		.and_then(|voice_state| voice_state.channel_id)
+	else {
+		return Vec::new();
+	};
+	guild
+		.voice_states
+		.iter()
+		.filter(|voice_state| {
+			voice_state.channel_id == Some(bot_channel)
+				&& voice_state.user_id != bot_id
+				&& !guild
+					.members
+					.get(&voice_state.user_id)
+					.is_some_and(|member| member.user.bot())
+		})
+		.map(|voice_state| voice_state.user_id)
+		.collect()
 }
 
-impl AudioBackend {
-	async fn apply(&self, action: &PlayerAction) -> AResult<()> {
-		match action {
-			PlayerAction::Pause => self.pause_song().await,
-			PlayerAction::Skip => self.skip_song().await,
-			PlayerAction::Clear => self.clear_queue().await,
-			PlayerAction::SeekForward(duration) => {
-				self.seek_song(SeekType::Forward, *duration).await
-			}
-			PlayerAction::SeekBackward(duration) => {
-				self.seek_song(SeekType::Backwards, *duration).await
-			}
-			PlayerAction::Loop => self.loop_song().await,
-		}
+fn is_privileged(member: Option<&Member>, settings: &MusicSettings) -> bool {
+	member.is_some_and(|member| {
+		member
+			.permissions
+			.is_some_and(|permissions| permissions.manage_guild())
+			|| settings
+				.music_dj_role
+				.is_some_and(|role_id| member.roles.iter().any(|role| i64::from(*role) == role_id))
+	})
+}
+
+/// Why a member may not use a player control other than skipping, if they
+/// may not
+#[must_use]
+pub fn control_denial(
+	member: Option<&Member>,
+	user_id: UserId,
+	settings: &MusicSettings,
+	guild_id: GuildId,
+) -> Option<&'static str> {
+	if is_party_guest(guild_id) {
+		return Some(PARTY_GUEST_DENIAL);
 	}
+	party_denial(member, user_id, settings, guild_id)
+}
 
-	async fn pause_song(&self) -> AResult<()> {
-		match self {
-			Self::Songbird(lock) => {
-				let Some(current_track) = lock.lock().await.queue().current() else {
-					return Ok(());
-				};
-				match current_track.get_info().await.map(|t| t.playing) {
-					Ok(PlayMode::Pause) => current_track.play()?,
-					Ok(PlayMode::Play) => current_track.pause()?,
-					Err(err) => {
-						warn!("Failed to get track info. {err}");
-					}
-					_ => {}
-				}
-			}
-			Self::Lavalink(ctx) => {
-				let player_info = ctx.get_player().await?;
-				ctx.set_pause(!player_info.paused).await?;
-			}
-		}
-		Ok(())
+/// Why a member may not start, join or leave a listening party, if they may
+/// not; unlike other controls this is allowed in guest servers
+#[must_use]
+pub fn party_denial(
+	member: Option<&Member>,
+	user_id: UserId,
+	settings: &MusicSettings,
+	guild_id: GuildId,
+) -> Option<&'static str> {
+	member_denial(member, user_id, settings, &voice_listeners(guild_id))
+}
+
+fn member_denial(
+	member: Option<&Member>,
+	user_id: UserId,
+	settings: &MusicSettings,
+	listeners: &[UserId],
+) -> Option<&'static str> {
+	if is_privileged(member, settings) {
+		return None;
+	}
+	if !listeners.contains(&user_id) {
+		return Some("Join my voice channel to control the music");
+	}
+	settings
+		.music_dj_role
+		.is_some()
+		.then_some("Only DJs can use this control")
+}
+
+/// Member pressing a button on the now playing message
+struct ControlRequest<'a> {
+	user_id: UserId,
+	member: Option<&'a Member>,
+	/// Non-bot users sharing the bot's voice channel
+	listeners: &'a [UserId],
+	party_guest: bool,
+}
+
+fn control_decision(
+	request: &ControlRequest<'_>,
+	action: &PlayerAction,
+	settings: &MusicSettings,
+	track: &TrackPlayData,
+	skip_votes: &mut HashSet<UserId>,
+) -> ControlDecision {
+	let ControlRequest {
+		user_id,
+		member,
+		listeners,
+		party_guest,
+	} = *request;
+	if party_guest {
+		return ControlDecision::Denied(PARTY_GUEST_DENIAL);
+	}
+	if !matches!(action, PlayerAction::Skip) {
+		return member_denial(member, user_id, settings, listeners)
+			.map_or(ControlDecision::Allowed, ControlDecision::Denied);
+	}
+	if is_privileged(member, settings) {
+		return ControlDecision::Allowed;
 	}
 
-	async fn clear_queue(&self) -> AResult<()> {
-		match self {
-			Self::Songbird(lock) => {
-				lock.lock().await.queue().stop();
-			}
-			Self::Lavalink(ctx) => {
-				ctx.get_queue().clear()?;
-				ctx.stop_now().await?;
-			}
-		}
-		Ok(())
+	if !listeners.contains(&user_id) {
+		return ControlDecision::Denied("Join my voice channel to control the music");
 	}
 
-	async fn skip_song(&self) -> AResult<()> {
-		match self {
-			Self::Songbird(lock) => {
-				lock.lock().await.queue().skip()?;
-			}
-			Self::Lavalink(ctx) => {
-				let queue_size = ctx.get_queue().get_count().await?;
-				if queue_size > 0 {
-					ctx.skip()?;
-				}
-			}
-		}
-		Ok(())
+	if settings.music_requester_skip && track.requested_by == user_id {
+		return ControlDecision::Allowed;
 	}
 
-	async fn loop_song(&self) -> AResult<()> {
-		match self {
-			Self::Songbird(lock) => {
-				let Some(current_track) = lock.lock().await.queue().current() else {
-					return Ok(());
-				};
-				match current_track.get_info().await.map(|t| t.loops) {
-					Ok(loops) => {
-						if loops == LoopState::Infinite {
-							current_track.disable_loop()?;
-						} else {
-							current_track.enable_loop()?;
-						}
-					}
-					Err(err) => {
-						warn!("Failed to get track info. {err}");
-					}
-				}
-			}
-			Self::Lavalink(_ctx) => return Ok(()),
+	skip_votes.retain(|voter| listeners.contains(voter));
+	skip_votes.insert(user_id);
+	let percent = usize::try_from(settings.music_vote_skip_percent).unwrap_or(100);
+	let needed = listeners.len().saturating_mul(percent).div_ceil(100).max(1);
+	if skip_votes.len() >= needed {
+		skip_votes.clear();
+		ControlDecision::Allowed
+	} else {
+		ControlDecision::Voted {
+			votes: skip_votes.len(),
+			needed,
 		}
-		Ok(())
 	}
+}
 
-	async fn seek_song(&self, seek_type: SeekType, song_duration: i64) -> AResult<()> {
-		let seek_amount = Duration::from_secs(10);
-		match self {
-			Self::Songbird(lock) => {
-				let Some(current_track) = lock.lock().await.queue().current() else {
-					return Ok(());
-				};
-				let song_info = current_track.get_info().await?;
-				let target = match seek_type {
-					SeekType::Forward => song_info
-						.position
-						.saturating_add(seek_amount)
-						.min(Duration::from_secs(song_duration.cast_unsigned())),
-					SeekType::Backwards => song_info.position.saturating_sub(seek_amount),
-				};
-				current_track.seek_async(target).await?;
-			}
-			Self::Lavalink(ctx) => {
-				let player_info = ctx.get_player().await?;
-				if let Some(track) = player_info.track {
-					let current_position = Duration::from_millis(player_info.state.position);
-					let track_duration = Duration::from_millis(track.info.length);
-					let new_duration = match seek_type {
-						SeekType::Forward => current_position
-							.saturating_add(seek_amount)
-							.min(track_duration),
-						SeekType::Backwards => current_position.saturating_sub(seek_amount),
-					};
-					ctx.set_position(new_duration).await?;
-				}
-			}
+/// Player action behind a button of the now playing message, `None` for the
+/// buttons that only change what the message shows
+fn button_action(
+	custom_id: &str,
+	track: &TrackPlayData,
+	settings: &MusicSettings,
+) -> Option<PlayerAction> {
+	let song_duration = track
+		.optional_data
+		.as_ref()
+		.map_or(0, |optional_data| optional_data.duration_sec);
+	let seek_step = Duration::from_secs(settings.music_seek_step.unsigned_abs().into());
+	match custom_id {
+		"skip" => Some(PlayerAction::Skip),
+		"pause" => Some(PlayerAction::Pause),
+		"clear" => Some(PlayerAction::Clear),
+		"backwards" => Some(PlayerAction::Seek(
+			SeekType::Backwards(seek_step),
+			song_duration,
+		)),
+		"forward" => Some(PlayerAction::Seek(
+			SeekType::Forward(seek_step),
+			song_duration,
+		)),
+		"retry" => Some(PlayerAction::Loop),
+		_ => None,
+	}
+}
+
+enum ButtonOutcome {
+	/// Apply to the player and its listening party peers
+	Control(PlayerAction),
+	/// Only tell the member why nothing happened
+	Refused(String),
+	/// Switch between the now playing, lyrics and history views
+	View,
+}
+
+fn button_outcome(
+	custom_id: &str,
+	request: &ControlRequest<'_>,
+	track: &TrackPlayData,
+	settings: &MusicSettings,
+	skip_votes: &mut HashSet<UserId>,
+) -> ButtonOutcome {
+	let Some(action) = button_action(custom_id, track, settings) else {
+		return ButtonOutcome::View;
+	};
+	match control_decision(request, &action, settings, track, skip_votes) {
+		ControlDecision::Allowed => ButtonOutcome::Control(action),
+		ControlDecision::Voted { votes, needed } => {
+			ButtonOutcome::Refused(format!("Voted to skip, {votes}/{needed} votes so far"))
 		}
-		Ok(())
+		ControlDecision::Denied(reason) => ButtonOutcome::Refused(reason.to_owned()),
 	}
 }
 
-fn fetch_context(bot_data: &Data, guild_id: GuildId) -> AudioBackend {
-	bot_data
-		.lavalink_client
-		.get_player_context(guild_id)
-		.map(AudioBackend::Lavalink)
-		.or_else(|| {
-			bot_data
-				.music_manager
-				.get(guild_id)
-				.map(AudioBackend::Songbird)
+/// Seeks the track currently playing in the guild, returning the new position
+/// or `None` if nothing is playing
+pub async fn seek_current(
+	bot_data: &Data,
+	guild_id: GuildId,
+	seek_type: &SeekType,
+) -> AResult<Option<Duration>> {
+	let Some(backend) = fetch_backend(bot_data, guild_id) else {
+		return Ok(None);
+	};
+	let song_duration = backend
+		.queue()
+		.await?
+		.first()
+		.and_then(|queue_data| queue_data.track_data.optional_data.as_ref())
+		.map_or(0, |optional_data| optional_data.duration_sec);
+	let target = backend.seek(seek_type, song_duration).await?;
+	if let Some(target) = target {
+		apply_to_peers(
+			bot_data,
+			guild_id,
+			&PlayerAction::Seek(SeekType::To(target), song_duration),
+		)
+		.await;
+	}
+	Ok(target)
+}
+
+/// Position of the track currently playing in the guild
+async fn playback_position(bot_data: &Data, guild_id: GuildId) -> Option<Duration> {
+	let backend = fetch_backend(bot_data, guild_id)?;
+	let playback = backend.playback_state().await.ok()??;
+	Some(playback.position)
+}
+
+fn karaoke_line(lines: &[SyncedLine], position: Duration) -> Option<usize> {
+	lines
+		.partition_point(|line| line.time <= position)
+		.checked_sub(1)
+}
+
+fn karaoke_text(lines: &[SyncedLine], current: Option<usize>) -> String {
+	let start = current.map_or(0, |current| current.saturating_sub(KARAOKE_CONTEXT_LINES));
+	let end = current
+		.map_or(KARAOKE_CONTEXT_LINES, |current| {
+			current
+				.saturating_add(KARAOKE_CONTEXT_LINES)
+				.saturating_add(1)
 		})
-		.unwrap()
+		.min(lines.len());
+	let mut text = String::from("# Lyrics\n");
+	for (index, line) in lines.iter().enumerate().take(end).skip(start) {
+		let content = if line.text.is_empty() {
+			"♪"
+		} else {
+			line.text.as_str()
+		};
+		text.push_str(if Some(index) == current { "## " } else { "-# " });
+		text.push_str(content);
+		text.push('\n');
+	}
+	text
 }
 
-async fn apply_to_all_guilds(
-	bot_data: &Data,
-	track_guilds: &[i64],
-	action: PlayerAction,
-) -> AResult<()> {
-	for &gid in track_guilds {
-		let backend = fetch_context(bot_data, GuildId::from(gid.cast_unsigned()));
-		backend.apply(&action).await?;
+#[derive(Default)]
+struct LyricsView {
+	shown: bool,
+	lyrics: Option<Lyrics>,
+	line: Option<usize>,
+}
+
+impl LyricsView {
+	/// Whether the view follows the playback position
+	fn is_live(&self) -> bool {
+		self.shown
+			&& self
+				.lyrics
+				.as_ref()
+				.is_some_and(|lyrics| !lyrics.synced.is_empty())
+	}
+
+	fn line_changed(&self, position: Duration) -> bool {
+		self.lyrics
+			.as_ref()
+			.is_some_and(|lyrics| karaoke_line(&lyrics.synced, position) != self.line)
+	}
+
+	fn render(&mut self, position: Option<Duration>) -> String {
+		let Some(lyrics) = self.lyrics.as_ref() else {
+			return String::new();
+		};
+		if lyrics.synced.is_empty() {
+			let mut text = format!("# Lyrics\n{}", lyrics.plain);
+			text.truncate(MESSAGE_LIMIT);
+			text
+		} else {
+			self.line = karaoke_line(&lyrics.synced, position.unwrap_or_default());
+			let mut text = karaoke_text(&lyrics.synced, self.line);
+			text.truncate(MESSAGE_LIMIT);
+			text
+		}
 	}
-	Ok(())
+}
+
+fn lyrics_container<'a>(
+	text: String,
+	primary_row: &CreateContainerComponent<'a>,
+	secondary_row: &CreateContainerComponent<'a>,
+) -> CreateContainer<'a> {
+	CreateContainer::new(vec![text_display(text)])
+		.add_component(separator())
+		.add_component(primary_row.clone())
+		.add_component(separator())
+		.add_component(secondary_row.clone())
+		.accent_colour(Colour::BLUE)
 }
 
 async fn handle_interaction<'a>(
 	interaction: ComponentInteraction,
-	lyrics_shown: &mut bool,
-	lyrics_container: &mut Option<CreateContainer<'a>>,
+	lyrics_view: &mut LyricsView,
 	history_shown: &mut bool,
 	history_container: &mut Option<CreateContainer<'a>>,
 	track: &TrackPlayData,
-	track_guilds: &[i64],
+	settings: &MusicSettings,
+	skip_votes: &mut HashSet<UserId>,
 	container: &CreateContainer<'a>,
 	primary_row: &CreateContainerComponent<'a>,
 	secondary_row: &CreateContainerComponent<'a>,
 	guild_id: GuildId,
 ) -> AResult<()> {
 	let ctx = bot_context();
+
+	let listeners = voice_listeners(guild_id);
+	let request = ControlRequest {
+		user_id: interaction.user.id,
+		member: interaction.member.as_deref(),
+		listeners: &listeners,
+		party_guest: is_party_guest(guild_id),
+	};
+	match button_outcome(
+		&interaction.data.custom_id,
+		&request,
+		track,
+		settings,
+		skip_votes,
+	) {
+		ButtonOutcome::Control(action) => {
+			interaction.defer(&ctx.http).await?;
+			if let Some(backend) = fetch_backend(&ctx.data, guild_id) {
+				backend.apply(&action).await?;
+			}
+			apply_to_peers(&ctx.data, guild_id, &action).await;
+			return Ok(());
+		}
+		ButtonOutcome::Refused(reason) => {
+			interaction
+				.create_response(
+					&ctx.http,
+					CreateInteractionResponse::Message(
+						CreateInteractionResponseMessage::new()
+							.content(reason)
+							.ephemeral(true),
+					),
+				)
+				.await?;
+			return Ok(());
+		}
+		ButtonOutcome::View => {}
+	}
+
 	interaction.defer(&ctx.http).await?;
 
 	let mut msg = interaction.message;
 
-	if interaction.data.custom_id == "skip" {
-		apply_to_all_guilds(&ctx.data, track_guilds, PlayerAction::Skip).await?;
-	} else if interaction.data.custom_id == "pause" {
-		apply_to_all_guilds(&ctx.data, track_guilds, PlayerAction::Pause).await?;
-	} else if interaction.data.custom_id == "clear" {
-		apply_to_all_guilds(&ctx.data, track_guilds, PlayerAction::Clear).await?;
-	} else if interaction.data.custom_id == "backwards" {
-		apply_to_all_guilds(
-			&ctx.data,
-			track_guilds,
-			PlayerAction::SeekBackward(track.optional_data.as_ref().unwrap().duration_sec),
-		)
-		.await?;
-	} else if interaction.data.custom_id == "forward" {
-		apply_to_all_guilds(
-			&ctx.data,
-			track_guilds,
-			PlayerAction::SeekForward(track.optional_data.as_ref().unwrap().duration_sec),
-		)
-		.await?;
-	} else if interaction.data.custom_id == "retry" {
-		apply_to_all_guilds(&ctx.data, track_guilds, PlayerAction::Loop).await?;
-	} else {
-		if interaction.data.custom_id == "lyrics" {
-			if *lyrics_shown {
-				*lyrics_shown = false;
-			} else if let Some(optional_data) = &track.optional_data {
-				*lyrics_shown = true;
-				*history_shown = false;
-				if lyrics_container.is_none() {
-					let lyrics = get_lyrics(&optional_data.title, &optional_data.artist).await;
-					let mut text = String::with_capacity(lyrics.len().saturating_add(16));
-					write!(text, "# Lyrics\n{lyrics}")?;
-					text.truncate(MESSAGE_LIMIT);
-					let text_display = vec![text_display(text)];
-					let container = CreateContainer::new(text_display)
-						.add_component(separator())
-						.add_component(primary_row.clone())
-						.add_component(separator())
-						.add_component(secondary_row.clone())
-						.accent_colour(Colour::BLUE);
-					*lyrics_container = Some(container);
-				}
+	if interaction.data.custom_id == "lyrics" {
+		if lyrics_view.shown {
+			lyrics_view.shown = false;
+		} else if let Some(optional_data) = &track.optional_data {
+			lyrics_view.shown = true;
+			*history_shown = false;
+			if lyrics_view.lyrics.is_none() {
+				lyrics_view.lyrics =
+					Some(get_lyrics(&optional_data.title, &optional_data.artist).await);
 			}
+		}
+	} else {
+		if *history_shown {
+			*history_shown = false;
 		} else {
-			if *history_shown {
-				*history_shown = false;
-			} else {
-				*history_shown = true;
-				*lyrics_shown = false;
-				if history_container.is_none() {
-					let queue_history =
-						get_queue_history(i64::from(guild_id), &ctx.data.db).await?;
-					let mut history_string = String::with_capacity(MESSAGE_LIMIT);
+			*history_shown = true;
+			lyrics_view.shown = false;
+			if history_container.is_none() {
+				let queue_history = get_queue_history(i64::from(guild_id), &ctx.data.db).await?;
+				let mut history_string = String::with_capacity(MESSAGE_LIMIT);
+				writeln!(
+					history_string,
+					"# History of {} last played songs",
+					queue_history.len()
+				)?;
+				for track in queue_history {
 					writeln!(
 						history_string,
-						"# History of {} last played songs",
-						queue_history.len()
+						"**{}:** *<@{}> - <t:{}:F>*",
+						track.title,
+						track.requested_by,
+						track.played_at.unix_timestamp()
 					)?;
-					for track in queue_history {
-						writeln!(
-							history_string,
-							"**{}:** *<@{}> - <t:{}:F>*",
-							track.title,
-							track.requested_by,
-							track.played_at.unix_timestamp()
-						)?;
-					}
-					history_string.truncate(MESSAGE_LIMIT);
-					let text_display = vec![text_display(history_string)];
-					let container = CreateContainer::new(text_display)
-						.add_component(separator())
-						.add_component(primary_row.clone())
-						.add_component(separator())
-						.add_component(secondary_row.clone())
-						.accent_colour(Colour::BLUE);
-					*history_container = Some(container);
 				}
+				history_string.truncate(MESSAGE_LIMIT);
+				let text_display = vec![text_display(history_string)];
+				let container = CreateContainer::new(text_display)
+					.add_component(separator())
+					.add_component(primary_row.clone())
+					.add_component(separator())
+					.add_component(secondary_row.clone())
+					.accent_colour(Colour::BLUE);
+				*history_container = Some(container);
 			}
 		}
-		let new_container = if *history_shown {
-			history_container.as_ref().unwrap().clone()
-		} else if *lyrics_shown {
-			lyrics_container.as_ref().unwrap().clone()
-		} else {
-			container.clone()
-		};
-		msg.edit(
-			ctx.http.clone(),
-			edit_message_container(vec![CreateComponent::Container(new_container)]),
-		)
-		.await?;
 	}
+	let new_container = if *history_shown {
+		history_container.as_ref().unwrap().clone()
+	} else if lyrics_view.shown {
+		let position = playback_position(&ctx.data, guild_id).await;
+		lyrics_container(lyrics_view.render(position), primary_row, secondary_row)
+	} else {
+		container.clone()
+	};
+	msg.edit(
+		ctx.http.clone(),
+		edit_message_container(vec![CreateComponent::Container(new_container)]),
+	)
+	.await?;
 	Ok(())
 }
 
+/// Now playing text with the progress the backend reports, `None` if nothing
+/// is playing or nothing is known about the track
+async fn live_now_playing_text(
+	backend: &dyn AudioBackend,
+	track: &TrackPlayData,
+	effects: Option<String>,
+) -> Option<String> {
+	let optional_data = track.optional_data.as_ref()?;
+	let mut playback = backend.playback_state().await.ok()??;
+	playback.effects = effects;
+	Some(now_playing_text(
+		track.requested_by,
+		optional_data,
+		playback.queue_size,
+		Some(&playback),
+	))
+}
+
 async fn update_info(
 	queue_data: &QueueData,
 	mut track_receiver: Receiver<TrackSignal>,
@@ -535,30 +820,35 @@ async fn update_info(
 ) -> AResult<()> {
 	let bot_data: Arc<Data> = serenity_context.data();
 
-	let queue_size = match &fetch_context(&bot_data, guild_id) {
-		AudioBackend::Lavalink(ctx) => ctx.get_queue().get_count().await?,
-		AudioBackend::Songbird(lock) => lock.lock().await.queue().len(),
+	let queue_size = match fetch_backend(&bot_data, guild_id) {
+		Some(backend) => backend.queue().await?.len(),
+		None => 0,
 	};
 
+	save_session(&bot_data, guild_id).await;
+
 	let track_data = &queue_data.track_data;
 
+	let settings = fetch_music_settings(i64::from(guild_id), &bot_data.db)
+		.await?
+		.unwrap_or_default();
+
 	let (thumbnail_section, primary_row, additional_buttons) = create_components(
 		track_data.requested_by,
 		track_data,
 		queue_size,
 		&queue_data.payload_type,
+		settings.music_seek_step,
 	);
 
-	let thumbnail_slice = [thumbnail_section];
-
-	let mut base_container = CreateContainer::new(&thumbnail_slice)
+	let mut base_container = CreateContainer::new(vec![thumbnail_section])
 		.add_component(separator())
 		.accent_colour(Colour::RED);
 
 	let secondary_row =
 		CreateContainerComponent::ActionRow(CreateActionRow::buttons(additional_buttons.clone()));
 
-	let full_container = base_container
+	let mut full_container = base_container
 		.clone()
 		.add_component(primary_row.clone())
 		.add_component(separator())
@@ -566,19 +856,33 @@ async fn update_info(
 
 	let full_component = [CreateComponent::Container(full_container.clone())];
 
-	track_data
-		.requested_channel
-		.edit_message(
-			&serenity_context.http,
+	let now_playing_id = if track_data.channel_request {
+		mark_request(
+			track_data.requested_channel,
 			track_data.request_message_id,
-			edit_message_container(&full_component),
+			RequestStatus::Playing,
 		)
-		.await?;
+		.await;
+		track_data
+			.requested_channel
+			.send_message(&serenity_context.http, message_container(&full_component))
+			.await?
+			.id
+	} else {
+		track_data
+			.requested_channel
+			.edit_message(
+				&serenity_context.http,
+				track_data.request_message_id,
+				edit_message_container(&full_component),
+			)
+			.await?;
+		track_data.request_message_id
+	};
 
-	let mut lyrics_shown = false;
+	let mut lyrics_view = LyricsView::default();
 	let mut history_shown = false;
 
-	let mut lyrics_container: Option<CreateContainer> = None;
 	let mut history_embed: Option<CreateContainer> = None;
 
 	let interaction_timeout = track_data.optional_data.as_ref().map_or(3600 * 2, |o| {
@@ -587,21 +891,17 @@ async fn update_info(
 
 	let mut collector_stream = ComponentInteractionCollector::new(serenity_context)
 		.timeout(Duration::from_secs(interaction_timeout))
-		.message_id(track_data.request_message_id)
+		.message_id(now_playing_id)
 		.stream();
 
-	let track_guilds = {
-		let guild_id_i64 = i64::from(guild_id);
-		let guild_cache = bot_data.guilds.get(&guild_id).unwrap();
-		if guild_cache.music_data.global.load(Ordering::Relaxed) {
-			Cow::Owned(
-				get_matching_guild_plays(queue_data.track_data.uuid, guild_id_i64, &bot_data.db)
-					.await?,
-			)
-		} else {
-			Cow::Borrowed(&[guild_id_i64][..])
-		}
-	};
+	let mut skip_votes = HashSet::new();
+
+	let mut lyrics_refresh = interval(LYRICS_REFRESH_INTERVAL);
+	lyrics_refresh.set_missed_tick_behavior(MissedTickBehavior::Skip);
+	let mut progress_refresh = interval(PROGRESS_REFRESH_INTERVAL);
+	progress_refresh.set_missed_tick_behavior(MissedTickBehavior::Skip);
+	progress_refresh.reset();
+	let mut progress_text = String::new();
 
 	let mut track_exception = false;
 
@@ -612,12 +912,12 @@ async fn update_info(
 					Some(interaction) => {
 						handle_interaction(
 							interaction,
-							&mut lyrics_shown,
-							&mut lyrics_container,
+							&mut lyrics_view,
 							&mut history_shown,
 							&mut history_embed,
 							track_data,
-							&track_guilds,
+							&settings,
+							&mut skip_votes,
 							&full_container,
 							&primary_row,
 							&secondary_row,
@@ -630,6 +930,71 @@ async fn update_info(
 					}
 				}
 			},
+			_ = lyrics_refresh.tick(), if lyrics_view.is_live() => {
+				if let Some(position) = playback_position(&bot_data, guild_id).await
+					&& lyrics_view.line_changed(position)
+				{
+					let container = lyrics_container(
+						lyrics_view.render(Some(position)),
+						&primary_row,
+						&secondary_row
+					);
+					let component = [CreateComponent::Container(container)];
+					if let Err(err) = track_data
+						.requested_channel
+						.edit_message(
+							&serenity_context.http,
+							now_playing_id,
+							edit_message_container(&component),
+						)
+						.await
+					{
+						warn!("Failed to refresh lyrics: {err}");
+					}
+				}
+			},
+			_ = progress_refresh.tick(), if !lyrics_view.shown && !history_shown => {
+				if let Some(optional_data) = track_data.optional_data.as_ref()
+					&& let Some(backend) = fetch_backend(&bot_data, guild_id)
+					&& let Some(text) = live_now_playing_text(
+						backend.as_ref(),
+						track_data,
+						bot_data
+							.guilds
+							.get(&guild_id)
+							.and_then(|guild_cache| effects_summary(&guild_cache.music_data))
+					)
+					.await
+					&& text != progress_text
+				{
+					progress_text.clone_from(&text);
+					let (text, thumbnail) =
+						thumbnail_section(text, optional_data.thumbnail_url.as_str());
+					full_container = CreateContainer::new(vec![
+						CreateContainerComponent::Section(CreateSection::new(
+							vec![text],
+							thumbnail
+						))
+					])
+					.add_component(separator())
+					.add_component(primary_row.clone())
+					.add_component(separator())
+					.add_component(secondary_row.clone())
+					.accent_colour(Colour::RED);
+					let component = [CreateComponent::Container(full_container.clone())];
+					if let Err(err) = track_data
+						.requested_channel
+						.edit_message(
+							&serenity_context.http,
+							now_playing_id,
+							edit_message_container(&component),
+						)
+						.await
+					{
+						warn!("Failed to refresh now playing progress: {err}");
+					}
+				}
+			},
 			result = track_receiver.changed() => {
 				if result.is_ok() {
 					match &*track_receiver.borrow() {
@@ -661,6 +1026,14 @@ async fn update_info(
 	}
 
 	if track_exception {
+		if track_data.channel_request {
+			mark_request(
+				track_data.requested_channel,
+				track_data.request_message_id,
+				RequestStatus::Failed,
+			)
+			.await;
+		}
 		let text_display = [text_display("# Track errored on playback :/")];
 		let container = CreateContainer::new(&text_display).accent_colour(Colour::ORANGE);
 		let component = [CreateComponent::Container(container)];
@@ -668,7 +1041,7 @@ async fn update_info(
 			.requested_channel
 			.edit_message(
 				&serenity_context.http,
-				track_data.request_message_id,
+				now_playing_id,
 				edit_message_container(&component),
 			)
 			.await?;
@@ -682,7 +1055,7 @@ async fn update_info(
 			.requested_channel
 			.edit_message(
 				&serenity_context.http,
-				track_data.request_message_id,
+				now_playing_id,
 				edit_message_container(&component),
 			)
 			.await?;
@@ -710,56 +1083,328 @@ struct PlaybackHandler {
 	guild_id: GuildId,
 }
 
-#[derive(PartialEq)]
-enum SeekType {
-	Forward,
-	Backwards,
+#[derive(Clone, Copy, PartialEq, Eq, ChoiceParameter)]
+pub enum AudioFilter {
+	#[name = "Flat (clears every filter)"]
+	Flat,
+	#[name = "Bass boost"]
+	BassBoost,
+	#[name = "Vocal"]
+	Vocal,
+	#[name = "Nightcore"]
+	Nightcore,
+	#[name = "Vaporwave"]
+	Vaporwave,
+	#[name = "8D"]
+	EightD,
+	#[name = "Karaoke"]
+	Karaoke,
+	#[name = "Low-pass"]
+	LowPass,
 }
 
-impl PlaybackHandler {
-	const fn new(guild_id: GuildId, music_queue: MusicQueue) -> Self {
-		Self {
-			music_queue,
-			guild_id,
+fn equalizer(bands: &[(u8, f64)]) -> Vec<Equalizer> {
+	bands
+		.iter()
+		.map(|&(band, gain)| Equalizer { band, gain })
+		.collect()
+}
+
+impl AudioFilter {
+	#[must_use]
+	pub const fn name(self) -> &'static str {
+		match self {
+			Self::Flat => "Flat",
+			Self::BassBoost => "Bass boost",
+			Self::Vocal => "Vocal",
+			Self::Nightcore => "Nightcore",
+			Self::Vaporwave => "Vaporwave",
+			Self::EightD => "8D",
+			Self::Karaoke => "Karaoke",
+			Self::LowPass => "Low-pass",
 		}
 	}
-}
 
-#[async_trait]
-impl VoiceEventHandler for PlaybackHandler {
-	async fn act(&self, event: &EventContext<'_>) -> Option<SongBirdEvent> {
-		if let EventContext::Track(tracks) = event {
-			for (state, handle) in *tracks {
-				let queue_data: Arc<QueueData> = handle.data();
-				if let PlayMode::Errored(error) = &state.playing
-					&& queue_data.first_error.swap(false, Ordering::Relaxed)
-				{
-					track_error(&error.to_string(), self.guild_id).await;
-				} else if queue_data.payload_type != PayloadType::TextToVoice {
-					if state.playing == PlayMode::Play {
-						if queue_data.first_play.swap(false, Ordering::Relaxed)
-							&& let Err(err) = self.music_queue.send(queue_data).await
-						{
-							error!("Failed to queue track: {err}");
-						}
-					} else if state.playing == PlayMode::End {
-						notify_end(self.guild_id);
-					}
-				}
+	/// Filters of the same group override each other
+	const fn group(self) -> u8 {
+		match self {
+			Self::Flat | Self::BassBoost | Self::Vocal => 0,
+			Self::Nightcore | Self::Vaporwave => 1,
+			Self::EightD => 2,
+			Self::Karaoke => 3,
+			Self::LowPass => 4,
+		}
+	}
+
+	fn apply(self, filters: &mut Filters) {
+		match self {
+			Self::Flat => {}
+			Self::BassBoost => {
+				filters.equalizer = Some(equalizer(&[(0, 0.2), (1, 0.15), (2, 0.1), (3, 0.05)]));
+			}
+			Self::Vocal => {
+				filters.equalizer = Some(equalizer(&[
+					(0, -0.1),
+					(1, -0.1),
+					(5, 0.1),
+					(6, 0.15),
+					(7, 0.15),
+					(8, 0.1),
+				]));
+			}
+			Self::Nightcore => {
+				filters.timescale = Some(Timescale {
+					speed: Some(1.2),
+					pitch: Some(1.2),
+					rate: Some(1.0),
+				});
+			}
+			Self::Vaporwave => {
+				filters.timescale = Some(Timescale {
+					speed: Some(0.8),
+					pitch: Some(0.8),
+					rate: Some(1.0),
+				});
+			}
+			Self::EightD => {
+				filters.rotation = Some(Rotation {
+					rotation_hz: Some(0.2),
+				});
+			}
+			Self::Karaoke => {
+				filters.karaoke = Some(Karaoke {
+					level: Some(1.0),
+					mono_level: Some(1.0),
+					filter_band: Some(220.0),
+					filter_width: Some(100.0),
+				});
+			}
+			Self::LowPass => {
+				filters.low_pass = Some(LowPass {
+					smoothing: Some(20.0),
+				});
 			}
 		}
-		return None;
 	}
 }
 
-#[derive(PartialEq, Eq)]
-pub enum TrackSignal {
+fn build_filters(active: &[AudioFilter]) -> Filters {
+	let mut filters = Filters::default();
+	for filter in active {
+		filter.apply(&mut filters);
+	}
+	filters
+}
+
+fn volume_ratio(volume: u16) -> f32 {
+	f32::from(volume) / 100.0
+}
+
+/// Lavalink volume in percent for the guild volume, evening out the loudness
+/// of uploaded audio that got measured
+#[expect(
+	clippy::as_conversions,
+	clippy::cast_possible_truncation,
+	clippy::cast_sign_loss
+)]
+fn lavalink_volume(volume: u16, gain: Option<f32>) -> u16 {
+	(f32::from(volume) * gain.unwrap_or(1.0))
+		.round()
+		.clamp(0.0, LAVALINK_MAX_VOLUME) as u16
+}
+
+/// Normalization gain a Lavalink track was queued with
+fn lavalink_gain(track: &TrackData) -> Option<f32> {
+	from_value::<QueueData>(track.user_data.clone()?)
+		.ok()?
+		.track_data
+		.gain
+}
+
+/// Volume and filters line for the now-playing message, if any differ from
+/// the defaults
+fn effects_summary(music_data: &MusicData) -> Option<String> {
+	let volume = music_data.volume.load(Ordering::Relaxed);
+	let filters = music_data
+		.filters
+		.read()
+		.map(|filters| {
+			filters
+				.iter()
+				.map(|filter| filter.name())
+				.collect::<Vec<_>>()
+				.join(", ")
+		})
+		.unwrap_or_default();
+	match (volume == DEFAULT_VOLUME, filters.is_empty()) {
+		(true, true) => None,
+		(false, true) => Some(format!("**Volume:** {volume}%")),
+		(true, false) => Some(format!("**Filters:** {filters}")),
+		(false, false) => Some(format!("**Volume:** {volume}% | **Filters:** {filters}")),
+	}
+}
+
+/// Sets the volume in percent for the rest of the voice session, returning
+/// `false` if the bot isn't playing in the guild
+pub async fn set_volume(bot_data: &Data, guild_id: GuildId, volume: u16) -> AResult<bool> {
+	let Some(backend) = fetch_backend(bot_data, guild_id) else {
+		return Ok(false);
+	};
+	backend.set_volume(volume).await?;
+	if let Some(guild_cache) = bot_data.guilds.get(&guild_id) {
+		guild_cache
+			.music_data
+			.volume
+			.store(volume, Ordering::Relaxed);
+	}
+	Ok(true)
+}
+
+/// Applies a filter on top of the active ones for the rest of the voice
+/// session, returning the active filters or `None` without a Lavalink player
+pub async fn set_filter(
+	bot_data: &Data,
+	guild_id: GuildId,
+	filter: AudioFilter,
+) -> AResult<Option<Vec<AudioFilter>>> {
+	let (Some(player), Some(guild_cache)) = (
+		bot_data.lavalink_client.get_player_context(guild_id),
+		bot_data.guilds.get(&guild_id),
+	) else {
+		return Ok(None);
+	};
+	let active = {
+		let Ok(mut active) = guild_cache.music_data.filters.write() else {
+			bail!("Filter state is poisoned");
+		};
+		if filter == AudioFilter::Flat {
+			active.clear();
+		} else {
+			active.retain(|active| active.group() != filter.group());
+			active.push(filter);
+		}
+		active.clone()
+	};
+	player.set_filters(build_filters(&active)).await?;
+	Ok(Some(active))
+}
+
+pub enum SeekType {
+	Forward(Duration),
+	Backwards(Duration),
+	To(Duration),
+}
+
+impl SeekType {
+	/// Parses a timestamp like `1:23` or `1:02:03`, or a jump like `+30s`,
+	/// `-1m` or `+1m30s`, plain numbers are seconds
+	#[must_use]
+	pub fn parse(input: &str) -> Option<Self> {
+		let input = input.trim();
+		let (seek_type, rest): (fn(Duration) -> Self, &str) =
+			if let Some(rest) = input.strip_prefix('+') {
+				(Self::Forward, rest)
+			} else if let Some(rest) = input.strip_prefix('-') {
+				(Self::Backwards, rest)
+			} else {
+				(Self::To, input)
+			};
+		if rest.is_empty() {
+			return None;
+		}
+		let secs = if rest.contains(':') {
+			rest.split(':').try_fold(0_u64, |total, part| {
+				total.checked_mul(60)?.checked_add(part.parse().ok()?)
+			})?
+		} else {
+			let mut total = 0_u64;
+			let mut value: Option<u64> = None;
+			for c in rest.chars() {
+				if let Some(digit) = c.to_digit(10) {
+					value = Some(
+						value
+							.unwrap_or(0)
+							.checked_mul(10)?
+							.checked_add(u64::from(digit))?,
+					);
+				} else {
+					let unit = match c {
+						'h' => 3600,
+						'm' => 60,
+						's' => 1,
+						_ => return None,
+					};
+					total = total.checked_add(value.take()?.checked_mul(unit)?)?;
+				}
+			}
+			total.checked_add(value.unwrap_or(0))?
+		};
+		Some(seek_type(Duration::from_secs(secs)))
+	}
+
+	/// Where the seek lands, kept within the track unless its length is
+	/// unknown
+	fn target(&self, position: Duration, length: Duration) -> Duration {
+		let target = match self {
+			Self::Forward(amount) => position.saturating_add(*amount),
+			Self::Backwards(amount) => position.saturating_sub(*amount),
+			Self::To(target) => *target,
+		};
+		if length.is_zero() {
+			target
+		} else {
+			target.min(length)
+		}
+	}
+}
+
+impl PlaybackHandler {
+	const fn new(guild_id: GuildId, music_queue: MusicQueue) -> Self {
+		Self {
+			music_queue,
+			guild_id,
+		}
+	}
+}
+
+#[async_trait]
+impl VoiceEventHandler for PlaybackHandler {
+	async fn act(&self, event: &EventContext<'_>) -> Option<SongBirdEvent> {
+		if let EventContext::Track(tracks) = event {
+			for (state, handle) in *tracks {
+				let queue_data: Arc<QueueData> = handle.data();
+				if queue_data.payload_type == PayloadType::Sound {
+					continue;
+				}
+				if let PlayMode::Errored(error) = &state.playing
+					&& queue_data.first_error.swap(false, Ordering::Relaxed)
+				{
+					track_error(&error.to_string(), self.guild_id).await;
+				} else if queue_data.payload_type != PayloadType::TextToVoice {
+					if state.playing == PlayMode::Play {
+						if queue_data.first_play.swap(false, Ordering::Relaxed)
+							&& let Err(err) = self.music_queue.send(queue_data).await
+						{
+							error!("Failed to queue track: {err}");
+						}
+					} else if state.playing == PlayMode::End {
+						notify_end(self.guild_id);
+					}
+				}
+			}
+		}
+		return None;
+	}
+}
+
+#[derive(PartialEq, Eq)]
+pub enum TrackSignal {
 	Ended,
 	Exception,
 	Idle,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub enum ConnectionStatus {
 	Disconnected,
 	SongbirdConnected,
@@ -771,16 +1416,10 @@ async fn add_voice_events(
 	guild_id: GuildId,
 	channel_id: GenericChannelId,
 	handler_lock: &Mutex<Call>,
-	global: bool,
 	guild_cache: Arc<GuildCache>,
 ) {
 	let bot_data: Arc<Data> = ctx.data();
 
-	guild_cache
-		.music_data
-		.global
-		.store(global, Ordering::Relaxed);
-
 	let mut handler = handler_lock.lock().await;
 
 	handler.add_global_event(
@@ -803,23 +1442,113 @@ async fn add_voice_events(
 		SongBirdEvent::Core(CoreEvent::ClientDisconnect),
 		ClientDisconnectHandler::new(channel_id),
 	);
+
+	spawn_idle_watcher(
+		guild_id,
+		channel_id,
+		guild_cache,
+		ConnectionStatus::SongbirdConnected,
+	);
 }
 
-#[must_use]
-fn youtube_source(url: &str) -> bool {
-	Url::parse(url).is_ok_and(|parsed_url| {
-		parsed_url.domain().is_some_and(|d| {
-			d == "youtube.com" || d == "www.youtube.com" || d == "youtu.be" || d == "m.youtube.com"
-		})
-	})
+async fn queue_is_empty(bot_data: &Data, guild_id: GuildId) -> bool {
+	match fetch_backend(bot_data, guild_id) {
+		Some(backend) => backend.queue().await.is_ok_and(|queue| queue.is_empty()),
+		None => true,
+	}
+}
+
+/// Leaves voice with whichever backend is active in the guild
+pub async fn leave_voice_channel(guild_id: GuildId) -> AResult<()> {
+	let bot_data = bot_context().data.clone();
+	delete_session(&bot_data, guild_id).await?;
+	match fetch_backend(&bot_data, guild_id) {
+		Some(backend) => backend.leave().await,
+		None => remove_handler(guild_id).await,
+	}
+}
+
+async fn idle_watcher(
+	guild_id: GuildId,
+	channel_id: GenericChannelId,
+	guild_cache: Arc<GuildCache>,
+	status: ConnectionStatus,
+) -> AResult<()> {
+	let bot_data = bot_context().data.clone();
+	let idle_minutes = fetch_music_settings(i64::from(guild_id), &bot_data.db)
+		.await?
+		.unwrap_or_default()
+		.music_idle_timeout;
+	let idle_minutes = u64::try_from(idle_minutes).unwrap_or_default();
+	let idle_timeout = (idle_minutes > 0).then_some(Duration::from_mins(idle_minutes));
+	let mut status_receiver = guild_cache.music_data.connection_signals.subscribe();
+	let mut idle_for = Duration::ZERO;
+
+	loop {
+		select! {
+			() = sleep(IDLE_CHECK_INTERVAL) => {},
+			result = status_receiver.changed() => {
+				if result.is_err() {
+					return Ok(());
+				}
+			},
+		}
+		if *status_receiver.borrow_and_update() != status {
+			return Ok(());
+		}
+		save_session(&bot_data, guild_id).await;
+		let Some(idle_timeout) = idle_timeout else {
+			continue;
+		};
+
+		let reason = if voice_listeners(guild_id).is_empty() {
+			"everyone left"
+		} else if queue_is_empty(&bot_data, guild_id).await {
+			"the queue is empty"
+		} else {
+			idle_for = Duration::ZERO;
+			continue;
+		};
+
+		idle_for = idle_for.saturating_add(IDLE_CHECK_INTERVAL);
+		if idle_for < idle_timeout {
+			continue;
+		}
+
+		leave_voice_channel(guild_id).await?;
+		channel_id
+			.send_message(
+				&bot_context().http,
+				silent_message(&format!(
+					"Left the voice channel since {reason} for {idle_minutes} minute(s)"
+				)),
+			)
+			.await?;
+		return Ok(());
+	}
 }
 
-#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
+fn spawn_idle_watcher(
+	guild_id: GuildId,
+	channel_id: GenericChannelId,
+	guild_cache: Arc<GuildCache>,
+	status: ConnectionStatus,
+) {
+	spawn(async move {
+		if let Err(err) = idle_watcher(guild_id, channel_id, guild_cache, status).await {
+			warn!("Idle watcher stopped: {err}");
+		}
+	});
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum PayloadType {
 	Song,
 	Lavalink,
 	Custom,
 	TextToVoice,
+	/// Soundboard clip mixed over the queue
+	Sound,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -836,7 +1565,47 @@ impl Clone for QueueData {
 			track_data: self.track_data.clone(),
 			first_play: AtomicBool::new(self.first_play.load(Ordering::Relaxed)),
 			first_error: AtomicBool::new(self.first_error.load(Ordering::Relaxed)),
-			payload_type: self.payload_type.clone(),
+			payload_type: self.payload_type,
+		}
+	}
+}
+
+impl QueueData {
+	/// Points the now playing message of a copied track at a new message
+	fn for_message(mut self, msg: &Message) -> Self {
+		self.track_data.requested_channel = msg.channel_id;
+		self.track_data.request_message_id = msg.id;
+		self.track_data.channel_request = false;
+		self.first_play = AtomicBool::new(true);
+		self.first_error = AtomicBool::new(true);
+		self
+	}
+
+	/// Songbird volume of the track at the given guild volume in percent
+	fn track_volume(&self, volume: u16) -> f32 {
+		volume_ratio(volume) * self.track_data.gain.unwrap_or(1.0)
+	}
+}
+
+#[cfg(test)]
+impl QueueData {
+	/// Song as a search would have queued it for the given member
+	fn test_song(title: &str, duration_sec: i64, requested_by: UserId) -> Self {
+		Self {
+			track_data: TrackPlayData {
+				optional_data: Some(OptionalTrackData {
+					title: title.to_owned(),
+					artist: "Artist".to_owned(),
+					source_url: format!("https://example.com/{title}"),
+					duration_sec,
+					..Default::default()
+				}),
+				requested_by,
+				..Default::default()
+			},
+			first_play: AtomicBool::new(true),
+			first_error: AtomicBool::new(true),
+			payload_type: PayloadType::Song,
 		}
 	}
 }
@@ -851,11 +1620,21 @@ pub async fn add_payload(
 	let reply = ctx.reply("Payload queued").await?;
 	let msg = reply.message().await?;
 
+	// Identical files share an id, so their loudness only gets measured once
+	let uuid = Uuid::new_v5(&Uuid::NAMESPACE_OID, &payload);
+	let target_lufs = fetch_music_settings(i64::from(guild_id), &ctx.data().db)
+		.await?
+		.unwrap_or_default()
+		.music_target_lufs;
+	let gain = normalization_gain(uuid, AudioSource::Bytes(payload.clone()), target_lufs).await;
+
 	let queue_data = QueueData {
 		track_data: TrackPlayData {
 			requested_channel: msg.channel_id,
 			request_message_id: msg.id,
 			requested_by: ctx.author().id,
+			uuid,
+			gain,
 			..Default::default()
 		},
 		first_error: AtomicBool::new(true),
@@ -876,11 +1655,12 @@ pub async fn add_payload(
 	)
 	.await?;
 
-	global_queue(guild_id, compressed, queue_data).await?;
+	mirror_to_party(guild_id, MirroredTrack::Audio(&compressed, &queue_data)).await;
 
 	Ok(())
 }
 
+/// Expects the url in its canonical form, so every link to a song shares one id
 fn track_uuid(url: Option<&String>) -> Uuid {
 	url.as_ref().map_or_else(Uuid::new_v4, |url| {
 		Uuid::new_v5(&Uuid::NAMESPACE_URL, url.as_bytes())
@@ -898,14 +1678,22 @@ async fn enqueue(
 		insert_guild_play(&queue_data, guild_id, pool, author_id).await?;
 	}
 
+	let volume = bot_context()
+		.data
+		.guilds
+		.get(&GuildId::new(guild_id.cast_unsigned()))
+		.map_or(DEFAULT_VOLUME, |guild_cache| {
+			guild_cache.music_data.volume.load(Ordering::Relaxed)
+		});
+	let track_volume = queue_data.track_volume(volume);
+
 	handler_lock
 		.lock()
 		.await
-		.enqueue(Track::new_with_uuid_and_data(
-			input,
-			queue_data.track_data.uuid,
-			Arc::new(queue_data),
-		))
+		.enqueue(
+			Track::new_with_uuid_and_data(input, queue_data.track_data.uuid, Arc::new(queue_data))
+				.volume(track_volume),
+		)
 		.await;
 
 	Ok(())
@@ -916,10 +1704,13 @@ async fn join_container<'a>(ctx: &SContext<'a>) -> Result<ReplyHandle<'a>, Seren
 	- **/play_song**: *Queue a song or playlist from YouTube with an url OR search for a song*
 	- **/play_song_old**: *Old implementation, prone to blocking from YouTube*
 	- **/play_file**: *Queue a custom audio file*
+	- **/library play**: *Queue a song from the local music library*
 	- **/text_to_voice**: *Make the bot say smth either by providing an input or replying to a \
 	                     message*
+	- **/party**: *Listen along with other servers using a join code*
 	- **/leave_voice**: *Make the bot leave the party*\n### NEW: *Set a music channel with \
-	                     /configure_server_settings and I'll listen to your song requests there*";
+	                     /configure_server_settings and I'll queue every line and audio file \
+	                     posted there*";
 
 	let text = [text_display(playback_info)];
 
@@ -991,7 +1782,6 @@ pub fn check_in_channel(ctx: SContext<'_>, lavalink: bool) -> Option<GuildId> {
 
 pub async fn try_voice(
 	ctx: SContext<'_>,
-	global: bool,
 ) -> AResult<Option<(Option<Typing>, GuildId, Arc<Mutex<Call>>)>> {
 	let typing = ctx.defer_or_broadcast().await?;
 	let guild_id = ctx.guild_id().unwrap();
@@ -1024,22 +1814,9 @@ pub async fn try_voice(
 			guild_id,
 			ctx.channel_id(),
 			&handler_lock,
-			global,
 			guild_cache,
 		)
 		.await;
-		if global {
-			query!(
-				r#"
-				UPDATE guild_settings
-				SET GLOBAL_CALL = TRUE
-				WHERE guild_id = $1
-				"#,
-				i64::from(guild_id),
-			)
-			.execute(&ctx.data().db)
-			.await?;
-		}
 		handler_lock
 	};
 
@@ -1052,22 +1829,11 @@ pub async fn remove_handler(guild_id: GuildId) -> AResult<()> {
 	let guild_cache = bot_data.guilds.get(&guild_id).unwrap();
 
 	guild_cache.music_data.disconnected();
+	leave_party(guild_id);
+	end_recording(guild_id);
 
 	bot_data.music_manager.remove(guild_id).await?;
 
-	if guild_cache.music_data.global.load(Ordering::Relaxed) {
-		query!(
-			r#"
-				UPDATE guild_settings
-				SET GLOBAL_CALL = FALSE
-				WHERE guild_id = $1
-				"#,
-			i64::from(guild_id),
-		)
-		.execute(&bot_data.db)
-		.await?;
-	}
-
 	Ok(())
 }
 
@@ -1078,6 +1844,8 @@ struct OptionalTrackData {
 	source_url: String,
 	duration_sec: i64,
 	thumbnail_url: String,
+	#[serde(default)]
+	source: TrackSource,
 }
 
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -1087,6 +1855,13 @@ struct TrackPlayData {
 	requested_channel: GenericChannelId,
 	request_message_id: MessageId,
 	uuid: Uuid,
+	/// Volume multiplier evening out the loudness of uploaded and local audio
+	#[serde(default)]
+	gain: Option<f32>,
+	/// Requested in the music channel, so the request gets status reactions and
+	/// the now playing message is posted on its own
+	#[serde(default)]
+	channel_request: bool,
 }
 
 async fn insert_guild_play(
@@ -1099,8 +1874,8 @@ async fn insert_guild_play(
 	query!(
 		r#"
     	WITH ensured_track AS (
-        	INSERT INTO tracks (track_uuid, title, artist, source_url, duration_sec, thumbnail_url)
-        	VALUES ($1, $4, $5, $6, $7, $8)
+        	INSERT INTO tracks (track_uuid, title, artist, source_url, duration_sec, thumbnail_url, source)
+        	VALUES ($1, $4, $5, $6, $7, $8, $9)
 			ON CONFLICT (track_uuid)
 			DO UPDATE SET last_seen = NOW()
     	)
@@ -1114,34 +1889,13 @@ async fn insert_guild_play(
 		optional_data.artist,
 		optional_data.source_url,
 		optional_data.duration_sec,
-		optional_data.thumbnail_url
+		optional_data.thumbnail_url,
+		optional_data.source.db_name()
 	)
 	.execute(conn)
 	.await
 }
 
-async fn get_matching_guild_plays(
-	uuid: Uuid,
-	guild_id: i64,
-	conn: &Pool<Postgres>,
-) -> Result<Vec<i64>, Error> {
-	query_scalar!(
-		r#"
-    	SELECT DISTINCT sp.guild_id
-    	FROM song_plays sp
-    	JOIN guild_settings gs ON gs.guild_id = sp.guild_id
-    	WHERE sp.track_uuid = $1
-    		AND sp.guild_id != $2
-    		AND GLOBAL_CALL = TRUE
-        LIMIT 10
-    	"#,
-		uuid,
-		guild_id
-	)
-	.fetch_all(conn)
-	.await
-}
-
 struct ChannelPlayHistory {
 	played_at: OffsetDateTime,
 	requested_by: i64,
@@ -1171,7 +1925,19 @@ async fn get_queue_history(
 	.await
 }
 
-pub async fn setup_lavalink(host: String, password: String, bot_id: LavaUserId) -> LavalinkClient {
+/// Connects to every configured node, failing without any since picking a
+/// node for a player needs at least one to fall back to
+pub async fn setup_lavalink(
+	nodes: Vec<LavalinkNodeConfig>,
+	bot_id: LavaUserId,
+) -> AResult<LavalinkClient> {
+	if nodes.is_empty() {
+		bail!(
+			"At least one Lavalink node has to be configured with [[Bot.lavalink_nodes]] or \
+			 lavalink_host"
+		);
+	}
+
 	let events = events::Events {
 		track_start: Some(track_start),
 		track_end: Some(track_end),
@@ -1180,26 +1946,31 @@ pub async fn setup_lavalink(host: String, password: String, bot_id: LavaUserId)
 		..Default::default()
 	};
 
-	let node_local = NodeBuilder {
-		hostname: host,
-		is_ssl: false,
-		events: events::Events::default(),
-		password,
-		user_id: bot_id,
-		session_id: None,
-	};
+	register_nodes(&nodes);
+	let node_builders = nodes
+		.into_iter()
+		.map(|node| NodeBuilder {
+			hostname: node.host,
+			is_ssl: node.ssl,
+			events: events::Events::default(),
+			password: node.password,
+			user_id: bot_id,
+			session_id: None,
+		})
+		.collect();
 
-	LavalinkClient::new(
+	Ok(LavalinkClient::new(
 		events,
-		vec![node_local],
-		NodeDistributionStrategy::round_robin(),
+		node_builders,
+		NodeDistributionStrategy::Custom(pick_node),
 	)
-	.await
+	.await)
 }
 
 pub async fn lavalink_try_join(
 	ctx: ContextType<'_>,
 	guild_id: GuildId,
+	channel_id: GenericChannelId,
 	author_id: UserId,
 ) -> AResult<Option<(Option<Typing>, PlayerContext)>> {
 	let (typing, bot_data, guild_cache) = {
@@ -1236,7 +2007,7 @@ pub async fn lavalink_try_join(
 	{
 		context
 	} else {
-		let channel_id = match ctx {
+		let voice_channel_id = match ctx {
 			ContextType::Poise(ctx) => {
 				let Some(channel_id) = voice_channel_id(ctx) else {
 					ctx.reply(EMPTY_VOICE_CHAN_MSG).await?;
@@ -1255,51 +2026,87 @@ pub async fn lavalink_try_join(
 		} else if let ContextType::Poise(poise_ctx) = ctx {
 			join_container(&poise_ctx).await?;
 		}
-		let (connection_info, handler_lock) = bot_data
-			.music_manager
-			.join_gateway(guild_id, channel_id)
-			.await?;
-		configure_handler(&handler_lock).await;
-		guild_cache
-			.music_data
-			.connected(ConnectionStatus::LavalinkConnected);
-		bot_data
-			.lavalink_client
-			.create_player_context(guild_id, connection_info)
-			.await?
+		lavalink_connect(
+			&bot_data,
+			guild_id,
+			voice_channel_id,
+			channel_id,
+			guild_cache,
+		)
+		.await?
 	};
 
 	Ok(Some((typing, player_context)))
 }
 
-pub async fn lavalink_play(
-	ctx: &SerenityContext,
+async fn lavalink_connect(
+	bot_data: &Data,
 	guild_id: GuildId,
-	msg_id: MessageId,
+	voice_channel_id: ChannelId,
 	channel_id: GenericChannelId,
-	author_id: UserId,
+	guild_cache: Arc<GuildCache>,
+) -> AResult<PlayerContext> {
+	let (connection_info, handler_lock) = bot_data
+		.music_manager
+		.join_gateway(guild_id, voice_channel_id)
+		.await?;
+	configure_handler(&handler_lock).await;
+	guild_cache
+		.music_data
+		.connected(ConnectionStatus::LavalinkConnected);
+	let player_context = bot_data
+		.lavalink_client
+		.create_player_context(guild_id, connection_info)
+		.await?;
+	spawn_idle_watcher(
+		guild_id,
+		channel_id,
+		guild_cache,
+		ConnectionStatus::LavalinkConnected,
+	);
+	Ok(player_context)
+}
+
+struct LoadedTrack {
+	track: TrackInQueue,
+	uuid: Uuid,
+	optional_data: Option<OptionalTrackData>,
+}
+
+async fn lavalink_load(
+	lava_client: &LavalinkClient,
+	guild_id: GuildId,
 	input: &str,
-	player: PlayerContext,
-	pool: &Pool<Postgres>,
-) -> AResult<()> {
-	let bot_data: Arc<Data> = ctx.data();
-	let lava_client = bot_data.lavalink_client.clone();
-	let query = if youtube_source(input) {
-		if input.contains("playlist?list=") {
-			input
-		} else {
-			let clean_url = input.split_once("&pp=").map_or(input, |(b, _)| b);
-			&SearchEngines::YouTube.to_query(clean_url)?
+) -> AResult<Vec<LoadedTrack>> {
+	let source = TrackSource::detect(input);
+	let mut streaming = None;
+	let query = match source {
+		Some(TrackSource::YouTube) => {
+			if input.contains("playlist?list=") {
+				input.to_owned()
+			} else {
+				let clean_url = input.split_once("&pp=").map_or(input, |(b, _)| b);
+				SearchEngines::YouTube.to_query(clean_url)?
+			}
 		}
-	} else {
-		&SearchEngines::YouTube.to_query(input)?
+		Some(source) if source.is_metadata_only() => {
+			let metadata = streaming_metadata(source, input).await?;
+			let query = SearchEngines::YouTube.to_query(&metadata.search_query())?;
+			streaming = Some(metadata);
+			query
+		}
+		Some(_) => input.trim().to_owned(),
+		None => SearchEngines::YouTube.to_query(input)?,
 	};
-	let loaded_tracks = lava_client.load_tracks(guild_id, query).await?;
+	let loaded_tracks = lava_client.load_tracks(guild_id, &query).await?;
 
-	let mut tracks: Vec<TrackInQueue> = match loaded_tracks.data {
+	let tracks = match loaded_tracks.data {
 		Some(TrackLoadData::Track(track)) => vec![TrackInQueue::from(track)],
 		Some(TrackLoadData::Search(search)) => {
-			vec![TrackInQueue::from(search.into_iter().next().unwrap())]
+			let Some(track) = search.into_iter().next() else {
+				bail!("No search results for: {input}");
+			};
+			vec![TrackInQueue::from(track)]
 		}
 		Some(TrackLoadData::Playlist(playlist)) => playlist
 			.tracks
@@ -1314,48 +2121,293 @@ pub async fn lavalink_play(
 		}
 	};
 
-	for track in &mut tracks {
-		let track_info = track.track.info.clone();
-		let duration = Duration::from_millis(track_info.length);
-		let uuid = track_uuid(track_info.uri.as_ref());
-		let optional_data = if let Some(source_url) = track_info.uri
-			&& let Some(thumbnail_url) = track_info.artwork_url
-		{
-			Some(OptionalTrackData {
-				title: track_info.title,
-				artist: track_info.author,
-				source_url,
-				duration_sec: duration.as_secs().cast_signed(),
-				thumbnail_url,
-			})
-		} else {
-			None
+	Ok(tracks
+		.into_iter()
+		.map(|track| {
+			let (uuid, optional_data) = lavalink_track_data(
+				&track,
+				source,
+				streaming.as_ref().map(|streaming| (streaming, input)),
+			);
+			LoadedTrack {
+				track,
+				uuid,
+				optional_data,
+			}
+		})
+		.collect())
+}
+
+/// Track details as Lavalink loaded them, with tracks found through the
+/// metadata of a streaming link keeping that link and metadata instead
+fn lavalink_track_data(
+	track: &TrackInQueue,
+	source: Option<TrackSource>,
+	streaming: Option<(&StreamingMetadata, &str)>,
+) -> (Uuid, Option<OptionalTrackData>) {
+	let track_info = track.track.info.clone();
+	let duration = Duration::from_millis(track_info.length);
+	let source_url = match streaming {
+		Some((_, url)) => Some(canonical_url(url)),
+		None => track_info.uri.as_deref().map(canonical_url),
+	};
+	let uuid = track_uuid(source_url.as_ref());
+	let source = source
+		.filter(|source| source.is_metadata_only())
+		.unwrap_or_else(|| TrackSource::from_lavalink(&track_info.source_name));
+	let optional_data = source_url.map(|source_url| {
+		let (title, artist, thumbnail_url) = match streaming {
+			Some((streaming, _)) => (
+				streaming.title.clone(),
+				streaming.artist.clone(),
+				streaming.thumbnail_url.clone().or(track_info.artwork_url),
+			),
+			None => (track_info.title, track_info.author, track_info.artwork_url),
+		};
+		OptionalTrackData {
+			title,
+			artist,
+			source_url,
+			duration_sec: duration.as_secs().cast_signed(),
+			thumbnail_url: thumbnail_url.unwrap_or_else(|| UNKNOWN_THUMBNAIL.to_owned()),
+			source,
+		}
+	});
+	(uuid, optional_data)
+}
+
+async fn insert_track(
+	uuid: Uuid,
+	optional_data: &OptionalTrackData,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		INSERT INTO tracks (track_uuid, title, artist, source_url, duration_sec, thumbnail_url, source)
+		VALUES ($1, $2, $3, $4, $5, $6, $7)
+		ON CONFLICT (track_uuid)
+		DO UPDATE SET last_seen = NOW()
+		"#,
+		uuid,
+		optional_data.title,
+		optional_data.artist,
+		optional_data.source_url,
+		optional_data.duration_sec,
+		optional_data.thumbnail_url,
+		optional_data.source.db_name()
+	)
+	.execute(conn)
+	.await
+}
+
+/// Loads the given link or query without playing it and makes sure every
+/// resulting track is known, returning their ids in order
+pub async fn resolve_tracks(bot_data: &Data, guild_id: GuildId, input: &str) -> AResult<Vec<Uuid>> {
+	let tracks = lavalink_load(&bot_data.lavalink_client, guild_id, input).await?;
+	let mut uuids = Vec::with_capacity(tracks.len());
+	for track in &tracks {
+		if let Some(optional_data) = track.optional_data.as_ref() {
+			insert_track(track.uuid, optional_data, &bot_data.db).await?;
+			uuids.push(track.uuid);
+		}
+	}
+	Ok(uuids)
+}
+
+/// Id of the track currently playing in the guild, if it has a known source
+/// that stays playable
+pub async fn current_track_uuid(bot_data: &Data, guild_id: GuildId) -> Option<Uuid> {
+	let queue_data = fetch_backend(bot_data, guild_id)?
+		.queue()
+		.await
+		.ok()?
+		.into_iter()
+		.next()?;
+	queue_data
+		.track_data
+		.optional_data
+		.is_some_and(|optional_data| !is_attachment_url(&optional_data.source_url))
+		.then_some(queue_data.track_data.uuid)
+}
+
+pub async fn lavalink_play(
+	ctx: &SerenityContext,
+	guild_id: GuildId,
+	msg_id: MessageId,
+	channel_id: GenericChannelId,
+	author_id: UserId,
+	input: &str,
+	payload_type: PayloadType,
+	channel_request: bool,
+	player: PlayerContext,
+	pool: &Pool<Postgres>,
+) -> AResult<()> {
+	let bot_data: Arc<Data> = ctx.data();
+	let loaded_tracks = lavalink_load(&bot_data.lavalink_client, guild_id, input).await?;
+	let request = TrackRequest {
+		msg_id,
+		channel_id,
+		author_id,
+		payload_type,
+		channel_request,
+		gain: None,
+	};
+	lavalink_queue_loaded(&bot_data, guild_id, loaded_tracks, &request, &player, pool).await
+}
+
+/// Loads an uploaded audio file through Lavalink, with its loudness measured
+/// so it plays as loud as everything else
+pub async fn lavalink_play_upload(
+	ctx: &SerenityContext,
+	guild_id: GuildId,
+	msg_id: MessageId,
+	channel_id: GenericChannelId,
+	author_id: UserId,
+	attachment: &Attachment,
+	player: PlayerContext,
+	pool: &Pool<Postgres>,
+) -> AResult<()> {
+	let bot_data: Arc<Data> = ctx.data();
+	let gain = if attachment.size <= MAX_MEASURED_UPLOAD {
+		let payload = url_bytes(&attachment.url).await?;
+		// Identical files share an id, so their loudness only gets measured once
+		let uuid = Uuid::new_v5(&Uuid::NAMESPACE_OID, &payload);
+		let target_lufs = fetch_music_settings(i64::from(guild_id), pool)
+			.await?
+			.unwrap_or_default()
+			.music_target_lufs;
+		normalization_gain(uuid, AudioSource::Bytes(payload), target_lufs).await
+	} else {
+		None
+	};
+	let loaded_tracks = lavalink_load(&bot_data.lavalink_client, guild_id, &attachment.url).await?;
+	let request = TrackRequest {
+		msg_id,
+		channel_id,
+		author_id,
+		payload_type: PayloadType::Lavalink,
+		channel_request: true,
+		gain,
+	};
+	lavalink_queue_loaded(&bot_data, guild_id, loaded_tracks, &request, &player, pool).await
+}
+
+/// Loads several links at once and queues all of their tracks in one go,
+/// returning how many of the links couldn't be loaded
+pub async fn lavalink_play_many(
+	ctx: &SerenityContext,
+	guild_id: GuildId,
+	msg_id: MessageId,
+	channel_id: GenericChannelId,
+	author_id: UserId,
+	inputs: &[&str],
+	player: PlayerContext,
+	pool: &Pool<Postgres>,
+) -> AResult<usize> {
+	let bot_data: Arc<Data> = ctx.data();
+	let results: Vec<_> = stream::iter(inputs)
+		.map(|input| lavalink_load(&bot_data.lavalink_client, guild_id, input))
+		.buffered(PARALLEL_TRACK_LOADS)
+		.collect()
+		.await;
+	let mut failed = 0_usize;
+	let mut loaded_tracks = Vec::with_capacity(results.len());
+	for (input, result) in inputs.iter().zip(results) {
+		match result {
+			Ok(tracks) => loaded_tracks.extend(tracks),
+			Err(err) => {
+				warn!("Failed to load {input}: {err}");
+				failed = failed.saturating_add(1);
+			}
+		}
+	}
+	if !loaded_tracks.is_empty() {
+		let request = TrackRequest {
+			msg_id,
+			channel_id,
+			author_id,
+			payload_type: PayloadType::Lavalink,
+			channel_request: false,
+			gain: None,
 		};
+		lavalink_queue_loaded(&bot_data, guild_id, loaded_tracks, &request, &player, pool).await?;
+	}
+	Ok(failed)
+}
+
+/// Who asked for a batch of Lavalink tracks and where
+struct TrackRequest {
+	msg_id: MessageId,
+	channel_id: GenericChannelId,
+	author_id: UserId,
+	payload_type: PayloadType,
+	channel_request: bool,
+	/// Normalization gain of uploaded audio
+	gain: Option<f32>,
+}
+
+async fn lavalink_queue_loaded(
+	bot_data: &Data,
+	guild_id: GuildId,
+	loaded_tracks: Vec<LoadedTrack>,
+	request: &TrackRequest,
+	player: &PlayerContext,
+	pool: &Pool<Postgres>,
+) -> AResult<()> {
+	let mut tracks = Vec::with_capacity(loaded_tracks.len());
+
+	for LoadedTrack {
+		mut track,
+		uuid,
+		optional_data,
+	} in loaded_tracks
+	{
 		let queue_data = QueueData {
 			track_data: TrackPlayData {
 				optional_data,
-				requested_by: author_id,
-				requested_channel: channel_id,
-				request_message_id: msg_id,
+				requested_by: request.author_id,
+				requested_channel: request.channel_id,
+				request_message_id: request.msg_id,
 				uuid,
+				gain: request.gain,
+				channel_request: request.channel_request,
 			},
 			first_play: AtomicBool::new(true),
 			first_error: AtomicBool::new(true),
-			payload_type: PayloadType::Lavalink,
+			payload_type: request.payload_type,
 		};
-		insert_guild_play(
-			&queue_data,
-			guild_id.get().cast_signed(),
-			pool,
-			i64::from(author_id),
-		)
-		.await?;
+		// Attachment links expire, so they aren't worth keeping in the history
+		if !queue_data
+			.track_data
+			.optional_data
+			.as_ref()
+			.is_some_and(|optional_data| is_attachment_url(&optional_data.source_url))
+		{
+			insert_guild_play(
+				&queue_data,
+				guild_id.get().cast_signed(),
+				pool,
+				i64::from(request.author_id),
+			)
+			.await?;
+		}
 		let json = to_value(queue_data)?;
 		track.track.user_data = Some(json);
+		tracks.push(track);
 	}
 
+	let mut tracks = VecDeque::from(tracks);
+	lavalink_enqueue(player, tracks.clone()).await?;
+	save_session(bot_data, guild_id).await;
+	mirror_to_party(guild_id, MirroredTrack::Lavalink(tracks.make_contiguous())).await;
+
+	Ok(())
+}
+
+/// Appends to the Lavalink queue, starting playback if the player is idle
+async fn lavalink_enqueue(player: &PlayerContext, tracks: VecDeque<TrackInQueue>) -> AResult<()> {
 	let queue = player.get_queue();
-	queue.append(VecDeque::from(tracks))?;
+	queue.append(tracks)?;
 
 	if let Ok(player_data) = player.get_player().await
 		&& player_data.track.is_none()
@@ -1368,13 +2420,46 @@ pub async fn lavalink_play(
 }
 
 #[hook]
-async fn track_start(_client: LavalinkClient, _session_id: String, event: &events::TrackStart) {
+async fn track_start(client: LavalinkClient, _session_id: String, event: &events::TrackStart) {
 	let guild_cache = bot_context()
 		.data
 		.guilds
 		.get(&GuildId::from(event.guild_id.0))
 		.unwrap();
-	if let Some(track_data) = event.track.user_data.as_ref()
+	let previous = guild_cache
+		.music_data
+		.lavalink_track
+		.write()
+		.ok()
+		.and_then(|mut lavalink_track| lavalink_track.replace(event.track.clone()));
+	// Uploads get their loudness evened out through the player volume
+	let gain = lavalink_gain(&event.track);
+	if gain != previous.as_ref().and_then(lavalink_gain)
+		&& let Some(player) = client.get_player_context(event.guild_id)
+	{
+		let volume = guild_cache.music_data.volume.load(Ordering::Relaxed);
+		if let Err(err) = player.set_volume(lavalink_volume(volume, gain)).await {
+			error!("Failed to normalize track volume: {err}");
+		}
+	}
+	// The replay of a looping track queues the next replay
+	let looping = guild_cache
+		.music_data
+		.lavalink_looping
+		.load(Ordering::Relaxed);
+	if looping
+		&& let Some(player) = client.get_player_context(event.guild_id)
+		&& let Err(err) = player.get_queue().push_to_front(event.track.clone())
+	{
+		error!("Failed to loop track: {err}");
+	}
+	// Replays were already announced when the track first started
+	let replay = looping
+		&& previous
+			.as_ref()
+			.is_some_and(|previous| previous.encoded == event.track.encoded);
+	if !replay
+		&& let Some(track_data) = event.track.user_data.as_ref()
 		&& let Ok(queue_data) = from_value(track_data.clone())
 		&& let Err(err) = guild_cache
 			.music_data
@@ -1435,72 +2520,19 @@ async fn websocket_closed(
 	}
 	if guild_cache.music_data.is_lavalink_connected() {
 		guild_cache.music_data.disconnected();
+		leave_party(guild_id);
 		if let Err(err) = bot_data.music_manager.remove(guild_id).await {
 			error!("Failed to remove call (lavalink): {err}");
 		}
-	}
-}
-
-async fn global_queue(
-	guild_id: GuildId,
-	compressed: Compressed,
-	mut queue_data: QueueData,
-) -> AResult<()> {
-	let ctx = bot_context();
-	if ctx
-		.data
-		.guilds
-		.get(&guild_id)
-		.unwrap()
-		.music_data
-		.global
-		.load(Ordering::Relaxed)
-	{
-		for global_guild in ctx
-			.data
-			.guilds
-			.iter()
-			.filter(|t| t.music_data.global.load(Ordering::Relaxed) && *t.key() != guild_id)
-			.map(|t| *t.key())
+		if !is_shutting_down()
+			&& let Err(err) = delete_session(bot_data, guild_id).await
 		{
-			let Some(global_handler_lock) = ctx.data.music_manager.get(global_guild) else {
-				continue;
-			};
-			let Some(channel_id) = global_handler_lock.lock().await.current_channel() else {
-				continue;
-			};
-			if let Ok(channel) = ctx
-				.http
-				.get_channel(GenericChannelId::new(channel_id.get()))
-				.await && let Some(guild_channel) = channel.guild()
-			{
-				let mut msg = guild_channel
-					.send_message(&ctx.http, CreateMessage::new().content(QUEUEING_MSG))
-					.await?;
-				let input = Input::from(compressed.new_handle());
-				queue_data.track_data.requested_channel = msg.channel_id;
-				queue_data.track_data.request_message_id = msg.id;
-				if let Err(err) = enqueue(
-					queue_data.clone(),
-					input,
-					&global_handler_lock,
-					global_guild.get().cast_signed(),
-					None,
-				)
-				.await
-				{
-					warn!("Failed to queue global song: {err}");
-					msg.edit(&ctx.http, EditMessage::new().content(FAILED_SONG_FETCH))
-						.await?;
-				}
-			}
+			warn!("Failed to forget music session: {err}");
 		}
 	}
-
-	Ok(())
 }
 
-pub async fn add_youtube_song(
+pub async fn add_song(
 	url: String,
 	handler_lock: &Mutex<Call>,
 	guild_id: GuildId,
@@ -1509,31 +2541,50 @@ pub async fn add_youtube_song(
 	author_id: UserId,
 	conn: &Pool<Postgres>,
 ) -> AResult<()> {
-	let mut src = if youtube_source(&url) {
-		YoutubeDl::new(HTTP_CLIENT.clone(), url)
-	} else {
-		YoutubeDl::new_search(HTTP_CLIENT.clone(), url)
+	let source = TrackSource::detect(&url);
+	// The YouTube match of a streaming link is stored under the link itself
+	let streaming_url = source
+		.filter(|source| source.is_metadata_only())
+		.map(|_| canonical_url(&url));
+	let (mut src, streaming) = match source {
+		Some(source) if source.is_metadata_only() => {
+			let streaming = streaming_metadata(source, &url).await?;
+			(
+				YoutubeDl::new_search(HTTP_CLIENT.clone(), streaming.search_query()),
+				Some(streaming),
+			)
+		}
+		Some(_) => (YoutubeDl::new(HTTP_CLIENT.clone(), url), None),
+		None => (YoutubeDl::new_search(HTTP_CLIENT.clone(), url), None),
 	};
 	let audio = src.create_async().await?;
-	let metadata = src.aux_metadata().await?;
+	let mut metadata = src.aux_metadata().await?;
+	if let Some(streaming) = streaming {
+		metadata.title = Some(streaming.title);
+		metadata.artist = Some(streaming.artist);
+		metadata.thumbnail = streaming.thumbnail_url.or(metadata.thumbnail);
+	}
 	let input = Input::Live(LiveInput::Raw(audio), Some(Box::new(src)));
 	let compressed = Compressed::new(input, Bitrate::Max).await?;
 	let new_input = Input::from(compressed.new_handle());
 
-	let uuid = track_uuid(metadata.source_url.as_ref());
+	let source_url = streaming_url.or_else(|| metadata.source_url.as_deref().map(canonical_url));
+	let uuid = track_uuid(source_url.as_ref());
 
 	let optional_data = if let Some(title) = metadata.title
 		&& let Some(artist) = metadata.artist
-		&& let Some(source_url) = metadata.source_url
+		&& let Some(source_url) = source_url
 		&& let Some(duration) = metadata.duration
-		&& let Some(thumbnail_url) = metadata.thumbnail
 	{
 		Some(OptionalTrackData {
 			title,
 			artist,
 			source_url,
 			duration_sec: duration.as_secs().cast_signed(),
-			thumbnail_url,
+			thumbnail_url: metadata
+				.thumbnail
+				.unwrap_or_else(|| UNKNOWN_THUMBNAIL.to_owned()),
+			source: source.unwrap_or_default(),
 		})
 	} else {
 		None
@@ -1546,6 +2597,8 @@ pub async fn add_youtube_song(
 			requested_channel: channel_id,
 			request_message_id: msg_id,
 			uuid,
+			gain: None,
+			channel_request: false,
 		},
 		first_play: AtomicBool::new(true),
 		first_error: AtomicBool::new(true),
@@ -1565,7 +2618,8 @@ pub async fn add_youtube_song(
 	)
 	.await?;
 
-	global_queue(guild_id, compressed, queue_data).await?;
+	mirror_to_party(guild_id, MirroredTrack::Audio(&compressed, &queue_data)).await;
+	save_session(&bot_context().data, guild_id).await;
 
 	Ok(())
 }
@@ -1595,3 +2649,198 @@ pub async fn music_task(
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::{collections::HashSet, time::Duration};
+
+	use fabsebot_db::guild::MusicSettings;
+	use serenity::all::{ChannelId, GuildId, UserId};
+	use tokio::runtime::Builder;
+
+	use super::{
+		ButtonOutcome, ControlRequest, PARTY_GUEST_DENIAL, PlayerAction, QueueData, SeekType,
+		backend::{AudioBackend as _, BackendTrack, mock},
+		button_outcome, live_now_playing_text,
+	};
+
+	const REQUESTER: UserId = UserId::new(1);
+	const LISTENER: UserId = UserId::new(2);
+	const STRANGER: UserId = UserId::new(3);
+	const LISTENERS: &[UserId] = &[REQUESTER, LISTENER];
+
+	fn request(user_id: UserId) -> ControlRequest<'static> {
+		ControlRequest {
+			user_id,
+			member: None,
+			listeners: LISTENERS,
+			party_guest: false,
+		}
+	}
+
+	fn press(
+		custom_id: &str,
+		request: &ControlRequest<'_>,
+		settings: &MusicSettings,
+	) -> ButtonOutcome {
+		let queue_data = QueueData::test_song("Song", 180, REQUESTER);
+		button_outcome(
+			custom_id,
+			request,
+			&queue_data.track_data,
+			settings,
+			&mut HashSet::new(),
+		)
+	}
+
+	#[test]
+	fn view_buttons_skip_the_player() {
+		let settings = MusicSettings::default();
+		for custom_id in ["lyrics", "history"] {
+			assert!(matches!(
+				press(custom_id, &request(STRANGER), &settings),
+				ButtonOutcome::View
+			));
+		}
+	}
+
+	#[test]
+	fn buttons_map_to_their_actions() {
+		let settings = MusicSettings {
+			music_seek_step: 15,
+			..Default::default()
+		};
+		let listener = request(LISTENER);
+		assert!(matches!(
+			press("pause", &listener, &settings),
+			ButtonOutcome::Control(PlayerAction::Pause)
+		));
+		assert!(matches!(
+			press("clear", &listener, &settings),
+			ButtonOutcome::Control(PlayerAction::Clear)
+		));
+		assert!(matches!(
+			press("retry", &listener, &settings),
+			ButtonOutcome::Control(PlayerAction::Loop)
+		));
+		assert!(matches!(
+			press("forward", &listener, &settings),
+			ButtonOutcome::Control(PlayerAction::Seek(SeekType::Forward(step), 180))
+				if step == Duration::from_secs(15)
+		));
+		assert!(matches!(
+			press("backwards", &listener, &settings),
+			ButtonOutcome::Control(PlayerAction::Seek(SeekType::Backwards(step), 180))
+				if step == Duration::from_secs(15)
+		));
+	}
+
+	#[test]
+	fn controls_need_a_listener() {
+		let outcome = press("pause", &request(STRANGER), &MusicSettings::default());
+		assert!(matches!(
+			outcome,
+			ButtonOutcome::Refused(reason) if reason == "Join my voice channel to control the music"
+		));
+	}
+
+	#[test]
+	fn party_guests_cant_control() {
+		let guest = ControlRequest {
+			party_guest: true,
+			..request(REQUESTER)
+		};
+		assert!(matches!(
+			press("skip", &guest, &MusicSettings::default()),
+			ButtonOutcome::Refused(reason) if reason == PARTY_GUEST_DENIAL
+		));
+	}
+
+	#[test]
+	fn dj_role_limits_controls() {
+		let settings = MusicSettings {
+			music_dj_role: Some(4),
+			..Default::default()
+		};
+		assert!(matches!(
+			press("pause", &request(LISTENER), &settings),
+			ButtonOutcome::Refused(reason) if reason == "Only DJs can use this control"
+		));
+	}
+
+	#[test]
+	fn requester_skips_without_votes() {
+		assert!(matches!(
+			press("skip", &request(REQUESTER), &MusicSettings::default()),
+			ButtonOutcome::Control(PlayerAction::Skip)
+		));
+	}
+
+	#[test]
+	fn skipping_takes_enough_votes() {
+		let listeners = [REQUESTER, LISTENER, STRANGER];
+		let settings = MusicSettings {
+			music_vote_skip_percent: 60,
+			..Default::default()
+		};
+		let queue_data = QueueData::test_song("Song", 180, REQUESTER);
+		let mut skip_votes = HashSet::new();
+		let mut vote = |user_id| {
+			button_outcome(
+				"skip",
+				&ControlRequest {
+					user_id,
+					member: None,
+					listeners: &listeners,
+					party_guest: false,
+				},
+				&queue_data.track_data,
+				&settings,
+				&mut skip_votes,
+			)
+		};
+		assert!(matches!(
+			vote(LISTENER),
+			ButtonOutcome::Refused(reason) if reason == "Voted to skip, 1/2 votes so far"
+		));
+		assert!(matches!(
+			vote(STRANGER),
+			ButtonOutcome::Control(PlayerAction::Skip)
+		));
+		assert!(skip_votes.is_empty());
+	}
+
+	#[test]
+	fn now_playing_text_follows_the_backend() {
+		let guild_id = GuildId::new(10);
+		let backend = mock::install(guild_id, ChannelId::new(11));
+		let song = QueueData::test_song("Song", 180, REQUESTER);
+		let runtime = Builder::new_current_thread().build().unwrap();
+		runtime.block_on(async {
+			assert!(
+				live_now_playing_text(&backend, &song.track_data, None)
+					.await
+					.is_none()
+			);
+			backend
+				.enqueue(vec![
+					BackendTrack::Source(song.clone(), None),
+					BackendTrack::Source(QueueData::test_song("Next", 60, LISTENER), None),
+				])
+				.await
+				.unwrap();
+			backend.set_position(Duration::from_secs(75));
+			backend.apply(&PlayerAction::Loop).await.unwrap();
+			let text =
+				live_now_playing_text(&backend, &song.track_data, Some("Bass boost".to_owned()))
+					.await
+					.unwrap();
+			assert!(text.contains("`01:15`"));
+			assert!(text.contains("**Status:** Playing, looping"));
+			assert!(text.contains("Bass boost"));
+			assert!(text.contains("**Up next:** Next"));
+			assert!(text.contains("**Queue size:** 1"));
+		});
+		mock::uninstall(guild_id);
+	}
+}
@@ -0,0 +1,655 @@
+#[cfg(any(test, feature = "mock-backend"))]
+pub mod mock;
+
+use std::{
+	collections::VecDeque,
+	sync::{Arc, atomic::Ordering},
+	time::Duration,
+};
+
+use anyhow::{Result as AResult, bail};
+use lavalink_rs::{
+	model::http::UpdatePlayer,
+	player_context::{PlayerContext, TrackInQueue},
+};
+use serde_json::{from_value, to_value};
+use serenity::{
+	all::{ChannelId, GuildId},
+	async_trait,
+};
+use songbird::{
+	Call,
+	input::{Input, YoutubeDl},
+	tracks::{LoopState, PlayMode},
+};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::{
+	config::types::{Data, HTTP_CLIENT, bot_context},
+	utils::voice::{
+		QueueData, SeekType, enqueue, join_handler, lavalink_enqueue, lavalink_gain, lavalink_load,
+		lavalink_volume, library::local_input, remove_handler, sources::TrackSource,
+	},
+};
+
+pub enum PlayerAction {
+	Pause,
+	Skip,
+	Clear,
+	Seek(SeekType, i64),
+	Loop,
+}
+
+pub struct PlaybackState {
+	pub position: Duration,
+	pub paused: bool,
+	pub looping: bool,
+	/// Includes the track playing right now
+	pub queue_size: usize,
+	pub next_title: Option<String>,
+	pub effects: Option<String>,
+}
+
+/// Track handed to a backend, converted if it got loaded for the other one
+pub enum BackendTrack {
+	/// Audio songbird plays directly, Lavalink reloads it from its source
+	Audio(Input, QueueData),
+	/// Resolved by Lavalink, songbird streams it from its source instead
+	Lavalink(TrackInQueue),
+	/// Only known by its source, both backends load it again, starting at the
+	/// given position
+	Source(QueueData, Option<Duration>),
+}
+
+/// Songbird input streaming the track from where it came from
+pub(super) fn source_input(queue_data: &QueueData) -> Option<Input> {
+	let optional_data = queue_data.track_data.optional_data.as_ref()?;
+	match local_input(&optional_data.source_url) {
+		Some(input) => Some(input),
+		// The file got removed from the library in the meantime
+		None if optional_data.source == TrackSource::Local => None,
+		// Streaming services only lend their metadata to a YouTube search
+		None if optional_data.source.is_metadata_only() => {
+			Some(Input::from(YoutubeDl::new_search(
+				HTTP_CLIENT.clone(),
+				format!("{} - {}", optional_data.artist, optional_data.title),
+			)))
+		}
+		None => Some(Input::from(YoutubeDl::new(
+			HTTP_CLIENT.clone(),
+			optional_data.source_url.clone(),
+		))),
+	}
+}
+
+/// Playback in a guild, whether songbird or a Lavalink node does the work
+#[async_trait]
+pub trait AudioBackend: Send + Sync {
+	/// Moves the connection to another voice channel
+	async fn join(&self, channel_id: ChannelId) -> AResult<()>;
+
+	/// Appends to the queue, starting playback if nothing is playing
+	async fn enqueue(&self, tracks: Vec<BackendTrack>) -> AResult<()>;
+
+	/// Pauses the current track or resumes it if it's paused
+	async fn pause(&self) -> AResult<()>;
+
+	async fn skip(&self) -> AResult<()>;
+
+	/// Stops playback and empties the queue
+	async fn clear(&self) -> AResult<()>;
+
+	/// Returns the new position, or `None` if nothing is playing
+	async fn seek(&self, seek_type: &SeekType, song_duration: i64) -> AResult<Option<Duration>>;
+
+	/// Loops the current track or stops looping it
+	async fn toggle_loop(&self) -> AResult<()>;
+
+	/// Sets the volume in percent for every queued track
+	async fn set_volume(&self, volume: u16) -> AResult<()>;
+
+	/// The current track followed by everything queued after it
+	async fn queue(&self) -> AResult<Vec<QueueData>>;
+
+	async fn playback_state(&self) -> AResult<Option<PlaybackState>>;
+
+	/// Stops playing and leaves the voice channel
+	async fn leave(&self) -> AResult<()>;
+
+	async fn apply(&self, action: &PlayerAction) -> AResult<()> {
+		match action {
+			PlayerAction::Pause => self.pause().await,
+			PlayerAction::Skip => self.skip().await,
+			PlayerAction::Clear => self.clear().await,
+			PlayerAction::Seek(seek_type, duration) => {
+				self.seek(seek_type, *duration).await.map(|_| ())
+			}
+			PlayerAction::Loop => self.toggle_loop().await,
+		}
+	}
+}
+
+struct SongbirdBackend {
+	guild_id: GuildId,
+	call: Arc<Mutex<Call>>,
+}
+
+#[async_trait]
+impl AudioBackend for SongbirdBackend {
+	async fn join(&self, channel_id: ChannelId) -> AResult<()> {
+		if join_handler(&bot_context().data.music_manager, self.guild_id, channel_id)
+			.await
+			.is_none()
+		{
+			bail!("Failed to move to voice channel {channel_id}");
+		}
+		Ok(())
+	}
+
+	async fn enqueue(&self, tracks: Vec<BackendTrack>) -> AResult<()> {
+		let guild_id = i64::from(self.guild_id);
+		for track in tracks {
+			let (input, queue_data, start) = match track {
+				BackendTrack::Audio(input, queue_data) => (input, queue_data, None),
+				BackendTrack::Lavalink(track) => {
+					let Some(queue_data) = track
+						.track
+						.user_data
+						.and_then(|user_data| from_value::<QueueData>(user_data).ok())
+					else {
+						continue;
+					};
+					let Some(input) = source_input(&queue_data) else {
+						continue;
+					};
+					(input, queue_data, track.start_time)
+				}
+				BackendTrack::Source(queue_data, start) => {
+					let Some(input) = source_input(&queue_data) else {
+						continue;
+					};
+					(input, queue_data, start)
+				}
+			};
+			enqueue(queue_data, input, &self.call, guild_id, None).await?;
+			if let Some(start) = start.filter(|start| !start.is_zero())
+				&& let Some(track) = self.call.lock().await.queue().current_queue().pop()
+			{
+				drop(track.seek(start));
+			}
+		}
+		Ok(())
+	}
+
+	async fn pause(&self) -> AResult<()> {
+		let Some(current_track) = self.call.lock().await.queue().current() else {
+			return Ok(());
+		};
+		match current_track.get_info().await.map(|t| t.playing) {
+			Ok(PlayMode::Pause) => current_track.play()?,
+			Ok(PlayMode::Play) => current_track.pause()?,
+			Err(err) => {
+				warn!("Failed to get track info. {err}");
+			}
+			_ => {}
+		}
+		Ok(())
+	}
+
+	async fn skip(&self) -> AResult<()> {
+		self.call.lock().await.queue().skip()?;
+		Ok(())
+	}
+
+	async fn clear(&self) -> AResult<()> {
+		self.call.lock().await.queue().stop();
+		Ok(())
+	}
+
+	async fn seek(&self, seek_type: &SeekType, song_duration: i64) -> AResult<Option<Duration>> {
+		let Some(current_track) = self.call.lock().await.queue().current() else {
+			return Ok(None);
+		};
+		let song_info = current_track.get_info().await?;
+		let target = seek_type.target(
+			song_info.position,
+			Duration::from_secs(song_duration.cast_unsigned()),
+		);
+		current_track.seek_async(target).await?;
+		Ok(Some(target))
+	}
+
+	async fn toggle_loop(&self) -> AResult<()> {
+		let Some(current_track) = self.call.lock().await.queue().current() else {
+			return Ok(());
+		};
+		match current_track.get_info().await.map(|t| t.loops) {
+			Ok(loops) => {
+				if loops == LoopState::Infinite {
+					current_track.disable_loop()?;
+				} else {
+					current_track.enable_loop()?;
+				}
+			}
+			Err(err) => {
+				warn!("Failed to get track info. {err}");
+			}
+		}
+		Ok(())
+	}
+
+	async fn set_volume(&self, volume: u16) -> AResult<()> {
+		let tracks = self.call.lock().await.queue().current_queue();
+		for track in tracks {
+			let queue_data: Arc<QueueData> = track.data();
+			track.set_volume(queue_data.track_volume(volume))?;
+		}
+		Ok(())
+	}
+
+	async fn queue(&self) -> AResult<Vec<QueueData>> {
+		Ok(self
+			.call
+			.lock()
+			.await
+			.queue()
+			.current_queue()
+			.iter()
+			.map(|track| track.data::<QueueData>().as_ref().clone())
+			.collect())
+	}
+
+	async fn playback_state(&self) -> AResult<Option<PlaybackState>> {
+		let (current, next, queue_size) = {
+			let handler = self.call.lock().await;
+			let queue = handler.queue();
+			(
+				queue.current(),
+				queue.current_queue().into_iter().nth(1),
+				queue.len(),
+			)
+		};
+		let Some(current) = current else {
+			return Ok(None);
+		};
+		let info = current.get_info().await?;
+		let next_title = next.and_then(|next| {
+			let queue_data: Arc<QueueData> = next.data();
+			queue_data
+				.track_data
+				.optional_data
+				.as_ref()
+				.map(|optional_data| optional_data.title.clone())
+		});
+		Ok(Some(PlaybackState {
+			position: info.position,
+			paused: info.playing == PlayMode::Pause,
+			looping: info.loops != LoopState::Finite(0),
+			queue_size,
+			next_title,
+			effects: None,
+		}))
+	}
+
+	async fn leave(&self) -> AResult<()> {
+		remove_handler(self.guild_id).await
+	}
+}
+
+struct LavalinkBackend {
+	guild_id: GuildId,
+	player: PlayerContext,
+}
+
+impl LavalinkBackend {
+	fn is_looping(&self) -> bool {
+		bot_context()
+			.data
+			.guilds
+			.get(&self.guild_id)
+			.is_some_and(|guild_cache| {
+				guild_cache
+					.music_data
+					.lavalink_looping
+					.load(Ordering::Relaxed)
+			})
+	}
+
+	/// Returns whether the current track was looping
+	fn stop_looping(&self) -> bool {
+		bot_context()
+			.data
+			.guilds
+			.get(&self.guild_id)
+			.is_some_and(|guild_cache| {
+				guild_cache
+					.music_data
+					.lavalink_looping
+					.swap(false, Ordering::Relaxed)
+			})
+	}
+}
+
+#[async_trait]
+impl AudioBackend for LavalinkBackend {
+	async fn join(&self, channel_id: ChannelId) -> AResult<()> {
+		// The gateway connection stays with songbird, Lavalink only needs the new
+		// voice server
+		let (connection_info, _) = bot_context()
+			.data
+			.music_manager
+			.join_gateway(self.guild_id, channel_id)
+			.await?;
+		self.player
+			.update_player(
+				&UpdatePlayer {
+					voice: Some(connection_info.into()),
+					..Default::default()
+				},
+				true,
+			)
+			.await?;
+		Ok(())
+	}
+
+	async fn enqueue(&self, tracks: Vec<BackendTrack>) -> AResult<()> {
+		let mut queued = VecDeque::with_capacity(tracks.len());
+		for track in tracks {
+			let (queue_data, start) = match track {
+				BackendTrack::Lavalink(track) => {
+					queued.push_back(track);
+					continue;
+				}
+				BackendTrack::Audio(_, queue_data) => (queue_data, None),
+				BackendTrack::Source(queue_data, start) => (queue_data, start),
+			};
+			// Custom files and text to voice only exist as raw audio
+			let Some(source_url) = queue_data
+				.track_data
+				.optional_data
+				.as_ref()
+				.map(|optional_data| optional_data.source_url.clone())
+			else {
+				continue;
+			};
+			let json = to_value(queue_data)?;
+			queued.extend(
+				lavalink_load(
+					&bot_context().data.lavalink_client,
+					self.guild_id,
+					&source_url,
+				)
+				.await?
+				.into_iter()
+				.take(1)
+				.map(|loaded| {
+					let mut track = loaded.track;
+					track.track.user_data = Some(json.clone());
+					track.start_time = start;
+					track
+				}),
+			);
+		}
+		lavalink_enqueue(&self.player, queued).await
+	}
+
+	async fn pause(&self) -> AResult<()> {
+		let player_info = self.player.get_player().await?;
+		self.player.set_pause(!player_info.paused).await?;
+		Ok(())
+	}
+
+	async fn skip(&self) -> AResult<()> {
+		let queue = self.player.get_queue();
+		// Skipping ends the loop, so the copy waiting for a replay goes too
+		if self.stop_looping() {
+			queue.remove(0)?;
+		}
+		if queue.get_count().await? > 0 {
+			self.player.skip()?;
+		}
+		Ok(())
+	}
+
+	async fn clear(&self) -> AResult<()> {
+		self.stop_looping();
+		self.player.get_queue().clear()?;
+		self.player.stop_now().await?;
+		Ok(())
+	}
+
+	async fn seek(&self, seek_type: &SeekType, _song_duration: i64) -> AResult<Option<Duration>> {
+		let player_info = self.player.get_player().await?;
+		let Some(track) = player_info.track else {
+			return Ok(None);
+		};
+		let target = seek_type.target(
+			Duration::from_millis(player_info.state.position),
+			Duration::from_millis(track.info.length),
+		);
+		self.player.set_position(target).await?;
+		Ok(Some(target))
+	}
+
+	async fn toggle_loop(&self) -> AResult<()> {
+		let Some(track) = self.player.get_player().await?.track else {
+			return Ok(());
+		};
+		let Some(guild_cache) = bot_context().data.guilds.get(&self.guild_id) else {
+			return Ok(());
+		};
+		let queue = self.player.get_queue();
+		if guild_cache
+			.music_data
+			.lavalink_looping
+			.fetch_xor(true, Ordering::Relaxed)
+		{
+			queue.remove(0)?;
+		} else {
+			queue.push_to_front(track)?;
+		}
+		Ok(())
+	}
+
+	async fn set_volume(&self, volume: u16) -> AResult<()> {
+		let gain = self
+			.player
+			.get_player()
+			.await?
+			.track
+			.as_ref()
+			.and_then(lavalink_gain);
+		self.player
+			.set_volume(lavalink_volume(volume, gain))
+			.await?;
+		Ok(())
+	}
+
+	async fn queue(&self) -> AResult<Vec<QueueData>> {
+		let current = self.player.get_player().await?.track;
+		let queued = self.player.get_queue().get_queue().await?;
+		Ok(current
+			.into_iter()
+			.chain(
+				queued
+					.into_iter()
+					.skip(usize::from(self.is_looping()))
+					.map(|track| track.track),
+			)
+			.filter_map(|track| from_value(track.user_data?).ok())
+			.collect())
+	}
+
+	async fn playback_state(&self) -> AResult<Option<PlaybackState>> {
+		let player_info = self.player.get_player().await?;
+		if player_info.track.is_none() {
+			return Ok(None);
+		}
+		let looping = self.is_looping();
+		let queue = self.player.get_queue();
+		let next_title = queue
+			.get_track(usize::from(looping))
+			.await?
+			.map(|next| next.track.info.title);
+		Ok(Some(PlaybackState {
+			position: Duration::from_millis(player_info.state.position),
+			paused: player_info.paused,
+			looping,
+			queue_size: queue
+				.get_count()
+				.await?
+				.saturating_sub(usize::from(looping))
+				.saturating_add(1),
+			next_title,
+			effects: None,
+		}))
+	}
+
+	async fn leave(&self) -> AResult<()> {
+		bot_context()
+			.data
+			.lavalink_client
+			.delete_player(self.guild_id)
+			.await?;
+		remove_handler(self.guild_id).await
+	}
+}
+
+#[cfg(any(test, feature = "mock-backend"))]
+fn mocked_backend(guild_id: GuildId) -> Option<Box<dyn AudioBackend>> {
+	mock::installed(guild_id).map(|backend| Box::new(backend) as Box<dyn AudioBackend>)
+}
+
+#[cfg(not(any(test, feature = "mock-backend")))]
+const fn mocked_backend(_guild_id: GuildId) -> Option<Box<dyn AudioBackend>> {
+	None
+}
+
+/// Backend of the voice connection in the guild, if there is one
+#[must_use]
+pub fn fetch_backend(bot_data: &Data, guild_id: GuildId) -> Option<Box<dyn AudioBackend>> {
+	if let Some(backend) = mocked_backend(guild_id) {
+		return Some(backend);
+	}
+	if let Some(player) = bot_data.lavalink_client.get_player_context(guild_id) {
+		return Some(Box::new(LavalinkBackend { guild_id, player }));
+	}
+	bot_data
+		.music_manager
+		.get(guild_id)
+		.map(|call| Box::new(SongbirdBackend { guild_id, call }) as Box<dyn AudioBackend>)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use serenity::all::{ChannelId, GuildId, UserId};
+	use tokio::runtime::Builder;
+
+	use super::{BackendTrack, PlayerAction, mock, mocked_backend};
+	use crate::utils::voice::{QueueData, SeekType};
+
+	fn titles(queue: &[QueueData]) -> Vec<String> {
+		queue
+			.iter()
+			.filter_map(|queue_data| queue_data.track_data.optional_data.as_ref())
+			.map(|optional_data| optional_data.title.clone())
+			.collect()
+	}
+
+	fn song(title: &str) -> BackendTrack {
+		BackendTrack::Source(QueueData::test_song(title, 120, UserId::new(1)), None)
+	}
+
+	#[test]
+	fn installed_mock_replaces_the_connection() {
+		let guild_id = GuildId::new(20);
+		assert!(mocked_backend(guild_id).is_none());
+		let mock = mock::install(guild_id, ChannelId::new(21));
+		let runtime = Builder::new_current_thread().build().unwrap();
+		runtime.block_on(async {
+			let backend = mocked_backend(guild_id).unwrap();
+			backend.join(ChannelId::new(22)).await.unwrap();
+			backend.set_volume(80).await.unwrap();
+			let state = mock.state();
+			assert_eq!(state.channel_id, Some(ChannelId::new(22)));
+			assert_eq!(state.volume, Some(80));
+			backend.leave().await.unwrap();
+		});
+		assert!(mocked_backend(guild_id).is_none());
+	}
+
+	#[test]
+	fn player_actions_drive_the_queue() {
+		let guild_id = GuildId::new(30);
+		let mock = mock::install(guild_id, ChannelId::new(31));
+		let runtime = Builder::new_current_thread().build().unwrap();
+		runtime.block_on(async {
+			let backend = mocked_backend(guild_id).unwrap();
+			backend
+				.enqueue(vec![song("First"), song("Second"), song("Third")])
+				.await
+				.unwrap();
+			assert_eq!(
+				titles(&backend.queue().await.unwrap()),
+				["First", "Second", "Third"]
+			);
+
+			backend.apply(&PlayerAction::Pause).await.unwrap();
+			backend.apply(&PlayerAction::Loop).await.unwrap();
+			let playback = backend.playback_state().await.unwrap().unwrap();
+			assert!(playback.paused);
+			assert!(playback.looping);
+			assert_eq!(playback.queue_size, 3);
+			assert_eq!(playback.next_title.as_deref(), Some("Second"));
+
+			backend.apply(&PlayerAction::Skip).await.unwrap();
+			let playback = backend.playback_state().await.unwrap().unwrap();
+			assert!(!playback.looping);
+			assert_eq!(titles(&backend.queue().await.unwrap()), ["Second", "Third"]);
+
+			backend.apply(&PlayerAction::Clear).await.unwrap();
+			assert!(backend.playback_state().await.unwrap().is_none());
+			assert!(mock.state().queue.is_empty());
+		});
+		mock::uninstall(guild_id);
+	}
+
+	#[test]
+	fn seeking_stays_within_the_track() {
+		let guild_id = GuildId::new(40);
+		let mock = mock::install(guild_id, ChannelId::new(41));
+		let runtime = Builder::new_current_thread().build().unwrap();
+		runtime.block_on(async {
+			let backend = mocked_backend(guild_id).unwrap();
+			let step = Duration::from_secs(10);
+			assert_eq!(
+				backend.seek(&SeekType::Forward(step), 120).await.unwrap(),
+				None
+			);
+
+			backend.enqueue(vec![song("First")]).await.unwrap();
+			mock.set_position(Duration::from_secs(115));
+			assert_eq!(
+				backend.seek(&SeekType::Forward(step), 120).await.unwrap(),
+				Some(Duration::from_secs(120))
+			);
+			backend
+				.apply(&PlayerAction::Seek(SeekType::Backwards(step), 120))
+				.await
+				.unwrap();
+			assert_eq!(mock.state().position, Duration::from_secs(110));
+			// Without a known length the target isn't clamped at all
+			assert_eq!(
+				backend
+					.seek(&SeekType::To(Duration::from_secs(500)), 0)
+					.await
+					.unwrap(),
+				Some(Duration::from_secs(500))
+			);
+		});
+		mock::uninstall(guild_id);
+	}
+}
@@ -0,0 +1,186 @@
+use std::{
+	collections::VecDeque,
+	sync::{Arc, LazyLock, Mutex, MutexGuard, PoisonError},
+	time::Duration,
+};
+
+use anyhow::Result as AResult;
+use dashmap::DashMap;
+use serde_json::from_value;
+use serenity::{
+	all::{ChannelId, GuildId},
+	async_trait,
+};
+
+use crate::utils::voice::{
+	QueueData, SeekType,
+	backend::{AudioBackend, BackendTrack, PlaybackState},
+};
+
+/// Mocks that stand in for the voice connection of their guild
+static MOCKS: LazyLock<DashMap<GuildId, MockBackend>> = LazyLock::new(DashMap::new);
+
+/// What a mocked voice connection would be doing right now
+#[derive(Clone, Default)]
+pub struct MockState {
+	pub channel_id: Option<ChannelId>,
+	/// The current track followed by everything queued after it
+	pub queue: VecDeque<QueueData>,
+	pub position: Duration,
+	pub paused: bool,
+	pub looping: bool,
+	pub volume: Option<u16>,
+}
+
+/// Voice connection kept entirely in memory, so music logic runs without
+/// Discord or Lavalink
+#[derive(Clone)]
+pub struct MockBackend {
+	guild_id: GuildId,
+	state: Arc<Mutex<MockState>>,
+}
+
+impl MockBackend {
+	fn state_mut(&self) -> MutexGuard<'_, MockState> {
+		self.state.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	#[must_use]
+	pub fn state(&self) -> MockState {
+		self.state_mut().clone()
+	}
+
+	/// Pretends the current track played up to the given position
+	pub fn set_position(&self, position: Duration) {
+		self.state_mut().position = position;
+	}
+}
+
+/// Replaces the voice connection of the guild with an empty mock, returning a
+/// handle to inspect it with
+pub fn install(guild_id: GuildId, channel_id: ChannelId) -> MockBackend {
+	let backend = MockBackend {
+		guild_id,
+		state: Arc::new(Mutex::new(MockState {
+			channel_id: Some(channel_id),
+			..Default::default()
+		})),
+	};
+	MOCKS.insert(guild_id, backend.clone());
+	backend
+}
+
+pub fn uninstall(guild_id: GuildId) {
+	MOCKS.remove(&guild_id);
+}
+
+pub(super) fn installed(guild_id: GuildId) -> Option<MockBackend> {
+	MOCKS.get(&guild_id).map(|backend| backend.clone())
+}
+
+#[async_trait]
+impl AudioBackend for MockBackend {
+	async fn join(&self, channel_id: ChannelId) -> AResult<()> {
+		self.state_mut().channel_id = Some(channel_id);
+		Ok(())
+	}
+
+	async fn enqueue(&self, tracks: Vec<BackendTrack>) -> AResult<()> {
+		let mut state = self.state_mut();
+		for track in tracks {
+			let queue_data = match track {
+				BackendTrack::Audio(_, queue_data) | BackendTrack::Source(queue_data, _) => {
+					Some(queue_data)
+				}
+				BackendTrack::Lavalink(track) => track
+					.track
+					.user_data
+					.and_then(|user_data| from_value(user_data).ok()),
+			};
+			state.queue.extend(queue_data);
+		}
+		Ok(())
+	}
+
+	async fn pause(&self) -> AResult<()> {
+		let mut state = self.state_mut();
+		if !state.queue.is_empty() {
+			state.paused = !state.paused;
+		}
+		Ok(())
+	}
+
+	async fn skip(&self) -> AResult<()> {
+		let mut state = self.state_mut();
+		state.queue.pop_front();
+		state.position = Duration::ZERO;
+		state.looping = false;
+		Ok(())
+	}
+
+	async fn clear(&self) -> AResult<()> {
+		let mut state = self.state_mut();
+		state.queue.clear();
+		state.position = Duration::ZERO;
+		state.paused = false;
+		state.looping = false;
+		Ok(())
+	}
+
+	async fn seek(&self, seek_type: &SeekType, song_duration: i64) -> AResult<Option<Duration>> {
+		let mut state = self.state_mut();
+		if state.queue.is_empty() {
+			return Ok(None);
+		}
+		let target = seek_type.target(
+			state.position,
+			Duration::from_secs(song_duration.cast_unsigned()),
+		);
+		state.position = target;
+		Ok(Some(target))
+	}
+
+	async fn toggle_loop(&self) -> AResult<()> {
+		let mut state = self.state_mut();
+		if !state.queue.is_empty() {
+			state.looping = !state.looping;
+		}
+		Ok(())
+	}
+
+	async fn set_volume(&self, volume: u16) -> AResult<()> {
+		self.state_mut().volume = Some(volume);
+		Ok(())
+	}
+
+	async fn queue(&self) -> AResult<Vec<QueueData>> {
+		Ok(self.state_mut().queue.iter().cloned().collect())
+	}
+
+	async fn playback_state(&self) -> AResult<Option<PlaybackState>> {
+		let state = self.state_mut();
+		if state.queue.is_empty() {
+			return Ok(None);
+		}
+		let next_title = state.queue.get(1).and_then(|next| {
+			next.track_data
+				.optional_data
+				.as_ref()
+				.map(|optional_data| optional_data.title.clone())
+		});
+		Ok(Some(PlaybackState {
+			position: state.position,
+			paused: state.paused,
+			looping: state.looping,
+			queue_size: state.queue.len(),
+			next_title,
+			effects: None,
+		}))
+	}
+
+	async fn leave(&self) -> AResult<()> {
+		*self.state_mut() = MockState::default();
+		uninstall(self.guild_id);
+		Ok(())
+	}
+}
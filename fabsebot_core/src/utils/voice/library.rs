@@ -0,0 +1,278 @@
+use std::{
+	ffi::OsStr,
+	fs::{File, read_dir},
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{Result as AResult, bail};
+use fabsebot_db::{
+	guild::fetch_music_settings,
+	music::{LibraryFile, unlist_missing_files, upsert_library_file},
+};
+use image::guess_format;
+use serenity::all::{GenericChannelId, GuildId, MessageId, UserId};
+use songbird::{
+	Call,
+	driver::Bitrate,
+	input::{File as FileInput, Input, cached::Compressed},
+};
+use sqlx::{Pool, Postgres};
+use symphonia::{
+	core::{
+		formats::FormatOptions,
+		io::{MediaSourceStream, MediaSourceStreamOptions},
+		meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey, Tag, Visual},
+		probe::Hint,
+	},
+	default::get_probe,
+};
+use tokio::{sync::Mutex, task::spawn_blocking};
+use tracing::warn;
+use url::Url;
+
+use crate::{
+	config::types::{bot_context, utils_config},
+	utils::voice::{
+		OptionalTrackData, PayloadType, QueueData, TrackPlayData, UNKNOWN_THUMBNAIL, enqueue,
+		loudness::{AudioSource, normalization_gain},
+		party::{MirroredTrack, mirror_to_party},
+		sessions::save_session,
+		sources::{AUDIO_EXTENSIONS, TrackSource},
+		track_uuid,
+	},
+};
+
+const UNKNOWN_ARTIST: &str = "Unknown artist";
+
+static SCANNING: AtomicBool = AtomicBool::new(false);
+
+pub struct LibraryScan {
+	pub indexed: usize,
+	pub unlisted: u64,
+}
+
+fn is_audio_file(path: &Path) -> bool {
+	path.file_name()
+		.and_then(OsStr::to_str)
+		.map(str::to_lowercase)
+		.is_some_and(|name| {
+			AUDIO_EXTENSIONS
+				.iter()
+				.any(|extension| name.ends_with(extension))
+		})
+}
+
+fn audio_files(dir: &Path, files: &mut Vec<PathBuf>) -> AResult<()> {
+	for entry in read_dir(dir)? {
+		let path = entry?.path();
+		if path.is_dir() {
+			audio_files(&path, files)?;
+		} else if is_audio_file(&path) {
+			files.push(path);
+		}
+	}
+	Ok(())
+}
+
+fn tag_value(tags: &[Tag], key: StandardTagKey) -> Option<String> {
+	tags.iter()
+		.find(|tag| tag.std_key == Some(key))
+		.map(|tag| tag.value.to_string())
+		.filter(|value| !value.trim().is_empty())
+}
+
+/// Reads the tags of a file, falling back to its name for the title
+fn read_file(path: &Path) -> AResult<LibraryFile> {
+	let Ok(source_url) = Url::from_file_path(path).map(String::from) else {
+		bail!("{} isn't an absolute path", path.display());
+	};
+	let mut hint = Hint::new();
+	if let Some(extension) = path.extension().and_then(OsStr::to_str) {
+		hint.with_extension(extension);
+	}
+	let source = MediaSourceStream::new(
+		Box::new(File::open(path)?),
+		MediaSourceStreamOptions::default(),
+	);
+	let mut probed = get_probe().format(
+		&hint,
+		source,
+		&FormatOptions::default(),
+		&MetadataOptions::default(),
+	)?;
+
+	let mut tags: Vec<Tag> = Vec::new();
+	let mut visuals: Vec<Visual> = Vec::new();
+	let mut collect = |revision: &MetadataRevision| {
+		tags.extend_from_slice(revision.tags());
+		visuals.extend_from_slice(revision.visuals());
+	};
+	// Tags can sit in front of the container, like ID3, or inside it
+	if let Some(metadata) = probed.metadata.get()
+		&& let Some(revision) = metadata.current()
+	{
+		collect(revision);
+	}
+	if let Some(revision) = probed.format.metadata().current() {
+		collect(revision);
+	}
+
+	let duration_sec = probed
+		.format
+		.default_track()
+		.and_then(|track| {
+			let time_base = track.codec_params.time_base?;
+			let frames = track.codec_params.n_frames?;
+			i64::try_from(time_base.calc_time(frames).seconds).ok()
+		})
+		.unwrap_or_default();
+	let cover_art = visuals
+		.iter()
+		.find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+		.or_else(|| visuals.first())
+		.map(|visual| visual.data.to_vec());
+	let title = tag_value(&tags, StandardTagKey::TrackTitle).unwrap_or_else(|| {
+		path.file_stem()
+			.map(|stem| stem.to_string_lossy().into_owned())
+			.unwrap_or_default()
+	});
+
+	Ok(LibraryFile {
+		track_uuid: track_uuid(Some(&source_url)),
+		title,
+		artist: tag_value(&tags, StandardTagKey::Artist)
+			.or_else(|| tag_value(&tags, StandardTagKey::AlbumArtist))
+			.unwrap_or_else(|| UNKNOWN_ARTIST.to_owned()),
+		album: tag_value(&tags, StandardTagKey::Album),
+		source_url,
+		duration_sec,
+		cover_art,
+	})
+}
+
+fn read_library(music_dir: &Path) -> AResult<Vec<LibraryFile>> {
+	let mut paths = Vec::new();
+	audio_files(&music_dir.canonicalize()?, &mut paths)?;
+	Ok(paths
+		.iter()
+		.filter_map(|path| {
+			read_file(path)
+				.inspect_err(|err| warn!("Skipping {} in music library: {err}", path.display()))
+				.ok()
+		})
+		.collect())
+}
+
+async fn index_library(music_dir: PathBuf, conn: &Pool<Postgres>) -> AResult<LibraryScan> {
+	let files = spawn_blocking(move || read_library(&music_dir)).await??;
+	let mut found = Vec::with_capacity(files.len());
+	for file in &files {
+		upsert_library_file(file, UNKNOWN_THUMBNAIL, conn).await?;
+		found.push(file.track_uuid);
+	}
+	let unlisted = unlist_missing_files(&found, conn).await?.rows_affected();
+
+	Ok(LibraryScan {
+		indexed: found.len(),
+		unlisted,
+	})
+}
+
+/// Indexes every audio file in the configured music directory, `None` means
+/// no directory is configured
+pub async fn scan_library(conn: &Pool<Postgres>) -> AResult<Option<LibraryScan>> {
+	let Some(music_dir) = utils_config().music_dir.clone() else {
+		return Ok(None);
+	};
+	if SCANNING.swap(true, Ordering::Relaxed) {
+		bail!("The music library is already being scanned");
+	}
+	let scan = index_library(music_dir, conn).await;
+	SCANNING.store(false, Ordering::Relaxed);
+	scan.map(Some)
+}
+
+fn local_path(source_url: &str) -> Option<PathBuf> {
+	let path = Url::parse(source_url).ok()?.to_file_path().ok()?;
+	path.is_file().then_some(path)
+}
+
+/// Songbird input for a `file://` source, if the file still exists
+pub(super) fn local_input(source_url: &str) -> Option<Input> {
+	local_path(source_url).map(|path| Input::from(FileInput::new(path)))
+}
+
+/// Name to attach cover art with, so the now playing message can show it
+#[must_use]
+pub fn cover_file_name(cover_art: &[u8]) -> String {
+	let extension = guess_format(cover_art)
+		.ok()
+		.and_then(|format| format.extensions_str().first().copied())
+		.unwrap_or("jpg");
+	format!("cover.{extension}")
+}
+
+pub async fn add_local_track(
+	file: LibraryFile,
+	handler_lock: &Mutex<Call>,
+	guild_id: GuildId,
+	msg_id: MessageId,
+	channel_id: GenericChannelId,
+	author_id: UserId,
+	conn: &Pool<Postgres>,
+) -> AResult<()> {
+	let Some(path) = local_path(&file.source_url) else {
+		bail!("Library file is gone: {}", file.source_url);
+	};
+	let target_lufs = fetch_music_settings(i64::from(guild_id), conn)
+		.await?
+		.unwrap_or_default()
+		.music_target_lufs;
+	let gain = normalization_gain(
+		file.track_uuid,
+		AudioSource::File(path.clone()),
+		target_lufs,
+	)
+	.await;
+	let compressed = Compressed::new(Input::from(FileInput::new(path)), Bitrate::Max).await?;
+	let thumbnail_url = file.cover_art.as_deref().map_or_else(
+		|| UNKNOWN_THUMBNAIL.to_owned(),
+		|cover_art| format!("attachment://{}", cover_file_name(cover_art)),
+	);
+
+	let queue_data = QueueData {
+		track_data: TrackPlayData {
+			optional_data: Some(OptionalTrackData {
+				title: file.title,
+				artist: file.artist,
+				source_url: file.source_url,
+				duration_sec: file.duration_sec,
+				thumbnail_url,
+				source: TrackSource::Local,
+			}),
+			requested_by: author_id,
+			requested_channel: channel_id,
+			request_message_id: msg_id,
+			uuid: file.track_uuid,
+			gain,
+		},
+		first_play: AtomicBool::new(true),
+		first_error: AtomicBool::new(true),
+		payload_type: PayloadType::Song,
+	};
+
+	enqueue(
+		queue_data.clone(),
+		Input::from(compressed.new_handle()),
+		handler_lock,
+		i64::from(guild_id),
+		Some((conn, i64::from(author_id))),
+	)
+	.await?;
+
+	mirror_to_party(guild_id, MirroredTrack::Audio(&compressed, &queue_data)).await;
+	save_session(&bot_context().data, guild_id).await;
+
+	Ok(())
+}
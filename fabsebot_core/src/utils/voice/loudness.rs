@@ -0,0 +1,311 @@
+use std::{
+	f64::consts::PI,
+	fs::File,
+	io::{Cursor, ErrorKind},
+	path::PathBuf,
+	sync::LazyLock,
+	time::Duration,
+};
+
+use anyhow::{Result as AResult, bail};
+use bytes::Bytes;
+use mini_moka::sync::Cache;
+use symphonia::{
+	core::{
+		audio::SampleBuffer,
+		codecs::DecoderOptions,
+		errors::Error as SymphoniaError,
+		formats::FormatOptions,
+		io::{MediaSource, MediaSourceStream, MediaSourceStreamOptions},
+		meta::MetadataOptions,
+		probe::Hint,
+	},
+	default::{get_codecs, get_probe},
+};
+use tokio::task::spawn_blocking;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Anything longer only gets measured up to this point
+const ANALYSIS_LIMIT: Duration = Duration::from_mins(20);
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = 10.0;
+/// Keeps quiet recordings from getting their noise floor blown up
+const MAX_BOOST_DB: f64 = 12.0;
+const MAX_CUT_DB: f64 = 30.0;
+const LOUDNESS_CACHE_CAPACITY: u64 = 2000;
+
+/// Integrated loudness by track, `None` when the audio couldn't be measured
+static LOUDNESS_CACHE: LazyLock<Cache<Uuid, Option<f64>>> = LazyLock::new(|| {
+	Cache::builder()
+		.max_capacity(LOUDNESS_CACHE_CAPACITY)
+		.time_to_idle(Duration::from_hours(24))
+		.build()
+});
+
+pub(super) enum AudioSource {
+	Bytes(Bytes),
+	File(PathBuf),
+}
+
+#[derive(Clone, Copy)]
+struct Biquad {
+	b0: f64,
+	b1: f64,
+	b2: f64,
+	a1: f64,
+	a2: f64,
+	z1: f64,
+	z2: f64,
+}
+
+impl Biquad {
+	const fn new(b0: f64, b1: f64, b2: f64, a1: f64, a2: f64) -> Self {
+		Self {
+			b0,
+			b1,
+			b2,
+			a1,
+			a2,
+			z1: 0.0,
+			z2: 0.0,
+		}
+	}
+
+	fn process(&mut self, input: f64) -> f64 {
+		let output = self.b0.mul_add(input, self.z1);
+		self.z1 = (-self.a1).mul_add(output, self.b1.mul_add(input, self.z2));
+		self.z2 = (-self.a2).mul_add(output, self.b2 * input);
+		output
+	}
+}
+
+/// K-weighting of ITU-R BS.1770: a high shelf modelling the head followed by
+/// a high pass, derived for the given sample rate
+#[expect(clippy::suboptimal_flops)]
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+	let k = (PI * 1_681.974_450_955_533 / sample_rate).tan();
+	let q = 0.707_175_236_955_419_6;
+	let vh = 10_f64.powf(3.999_843_853_973_347 / 20.0);
+	let vb = vh.powf(0.499_666_774_154_541_6);
+	let a0 = 1.0 + k / q + k * k;
+	let shelf = Biquad::new(
+		(vh + vb * k / q + k * k) / a0,
+		2.0 * (k * k - vh) / a0,
+		(vh - vb * k / q + k * k) / a0,
+		2.0 * (k * k - 1.0) / a0,
+		(1.0 - k / q + k * k) / a0,
+	);
+
+	let k = (PI * 38.135_470_876_024_44 / sample_rate).tan();
+	let q = 0.500_327_037_323_877_3;
+	let a0 = 1.0 + k / q + k * k;
+	let high_pass = Biquad::new(
+		1.0,
+		-2.0,
+		1.0,
+		2.0 * (k * k - 1.0) / a0,
+		(1.0 - k / q + k * k) / a0,
+	);
+
+	[shelf, high_pass]
+}
+
+fn loudness(power: f64) -> f64 {
+	10.0_f64.mul_add(power.log10(), -0.691)
+}
+
+fn mean(values: &[f64]) -> f64 {
+	values.iter().sum::<f64>() / f64::from(u32::try_from(values.len()).unwrap_or(u32::MAX))
+}
+
+/// Gated integrated loudness measurement of EBU R128
+struct LoudnessMeter {
+	filters: Vec<[Biquad; 2]>,
+	/// Frames in 100ms, four of which make up a gating block
+	sub_block_frames: usize,
+	frames: usize,
+	power: f64,
+	sub_blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+	fn new(sample_rate: u32, channels: usize) -> Self {
+		Self {
+			filters: vec![k_weighting(f64::from(sample_rate)); channels],
+			sub_block_frames: usize::try_from(sample_rate / 10).unwrap_or(usize::MAX),
+			frames: 0,
+			power: 0.0,
+			sub_blocks: Vec::new(),
+		}
+	}
+
+	fn push(&mut self, interleaved: &[f32]) {
+		for frame in interleaved.chunks_exact(self.filters.len()) {
+			for (sample, filters) in frame.iter().zip(self.filters.iter_mut()) {
+				let filtered = filters
+					.iter_mut()
+					.fold(f64::from(*sample), |value, filter| filter.process(value));
+				self.power = filtered.mul_add(filtered, self.power);
+			}
+			self.frames = self.frames.saturating_add(1);
+			if self.frames == self.sub_block_frames {
+				self.sub_blocks
+					.push(self.power / f64::from(u32::try_from(self.frames).unwrap_or(u32::MAX)));
+				self.frames = 0;
+				self.power = 0.0;
+			}
+		}
+	}
+
+	/// Blocks of 400ms overlapping by 75%, gated absolutely and then relative
+	/// to the loudness of what passed the first gate
+	fn integrated(&self) -> Option<f64> {
+		let blocks: Vec<f64> = self
+			.sub_blocks
+			.windows(4)
+			.map(mean)
+			.filter(|power| loudness(*power) > ABSOLUTE_GATE_LUFS)
+			.collect();
+		if blocks.is_empty() {
+			return None;
+		}
+		let relative_gate = loudness(mean(&blocks)) - RELATIVE_GATE_LU;
+		let gated: Vec<f64> = blocks
+			.into_iter()
+			.filter(|power| loudness(*power) > relative_gate)
+			.collect();
+		(!gated.is_empty()).then(|| loudness(mean(&gated)))
+	}
+}
+
+fn measure(source: AudioSource) -> AResult<f64> {
+	let media: Box<dyn MediaSource> = match source {
+		AudioSource::Bytes(bytes) => Box::new(Cursor::new(bytes)),
+		AudioSource::File(path) => Box::new(File::open(path)?),
+	};
+	let probed = get_probe().format(
+		&Hint::new(),
+		MediaSourceStream::new(media, MediaSourceStreamOptions::default()),
+		&FormatOptions::default(),
+		&MetadataOptions::default(),
+	)?;
+	let mut format = probed.format;
+	let Some(track) = format.default_track() else {
+		bail!("no audio track");
+	};
+	let track_id = track.id;
+	let mut decoder = get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+	// Some formats only reveal their layout once decoding starts
+	let mut decoding: Option<(LoudnessMeter, SampleBuffer<f32>, u64)> = None;
+	let mut frames: u64 = 0;
+
+	loop {
+		let packet = match format.next_packet() {
+			Ok(packet) => packet,
+			Err(SymphoniaError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+			Err(err) => return Err(err.into()),
+		};
+		if packet.track_id() != track_id {
+			continue;
+		}
+		let decoded = match decoder.decode(&packet) {
+			Ok(decoded) => decoded,
+			// A corrupt packet only costs its own samples
+			Err(SymphoniaError::DecodeError(_)) => continue,
+			Err(err) => return Err(err.into()),
+		};
+		frames = frames.saturating_add(u64::try_from(decoded.frames()).unwrap_or_default());
+		let (meter, buffer, max_frames) = decoding.get_or_insert_with(|| {
+			let spec = *decoded.spec();
+			(
+				LoudnessMeter::new(spec.rate, spec.channels.count()),
+				SampleBuffer::new(u64::try_from(decoded.capacity()).unwrap_or_default(), spec),
+				ANALYSIS_LIMIT
+					.as_secs()
+					.saturating_mul(u64::from(spec.rate)),
+			)
+		});
+		buffer.copy_interleaved_ref(decoded);
+		meter.push(buffer.samples());
+		if frames >= *max_frames {
+			break;
+		}
+	}
+
+	let Some(integrated) = decoding.and_then(|(meter, ..)| meter.integrated()) else {
+		bail!("audio is silent");
+	};
+	Ok(integrated)
+}
+
+/// Measures the integrated loudness of a track once and remembers it
+async fn track_loudness(track_uuid: Uuid, source: AudioSource) -> Option<f64> {
+	if let Some(loudness) = LOUDNESS_CACHE.get(&track_uuid) {
+		return loudness;
+	}
+	let loudness = match spawn_blocking(move || measure(source)).await {
+		Ok(Ok(loudness)) => Some(loudness),
+		Ok(Err(err)) => {
+			warn!("Failed to measure loudness: {err}");
+			None
+		}
+		Err(err) => {
+			warn!("Loudness measurement panicked: {err}");
+			None
+		}
+	};
+	LOUDNESS_CACHE.insert(track_uuid, loudness);
+	loudness
+}
+
+/// Volume multiplier that brings the track to the target loudness in LUFS,
+/// `None` if it couldn't be measured
+#[expect(clippy::as_conversions, clippy::cast_possible_truncation)]
+pub(super) async fn normalization_gain(
+	track_uuid: Uuid,
+	source: AudioSource,
+	target_lufs: i16,
+) -> Option<f32> {
+	let measured = track_loudness(track_uuid, source).await?;
+	let gain_db = (f64::from(target_lufs) - measured).clamp(-MAX_CUT_DB, MAX_BOOST_DB);
+	Some(10_f64.powf(gain_db / 20.0) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f64::consts::TAU;
+
+	use super::LoudnessMeter;
+
+	const SAMPLE_RATE: u32 = 48_000;
+
+	#[expect(clippy::as_conversions, clippy::cast_possible_truncation)]
+	fn sine(frequency: f64, peak_dbfs: f64, seconds: u32) -> Vec<f32> {
+		let amplitude = 10_f64.powf(peak_dbfs / 20.0);
+		(0..SAMPLE_RATE.saturating_mul(seconds))
+			.map(|frame| {
+				let phase = TAU * frequency * f64::from(frame) / f64::from(SAMPLE_RATE);
+				(amplitude * phase.sin()) as f32
+			})
+			.collect()
+	}
+
+	#[test]
+	fn reference_tone_measures_minus_23_lufs() {
+		let mut meter = LoudnessMeter::new(SAMPLE_RATE, 1);
+		meter.push(&sine(1_000.0, -20.0, 5));
+		let integrated = meter.integrated().unwrap();
+		assert!(
+			(integrated + 23.0).abs() < 0.1,
+			"measured {integrated} LUFS"
+		);
+	}
+
+	#[test]
+	fn silence_has_no_loudness() {
+		let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2);
+		meter.push(&vec![0.0; 2 * 48_000 * 5]);
+		assert!(meter.integrated().is_none());
+	}
+}
@@ -0,0 +1,220 @@
+use std::{
+	collections::VecDeque,
+	sync::{Arc, OnceLock, atomic::Ordering},
+	time::Duration,
+};
+
+use anyhow::{Result as AResult, bail};
+use lavalink_rs::{
+	client::LavalinkClient, model::GuildId as LavaGuildId, node::Node, player_context::TrackInQueue,
+};
+use metrics::{counter, gauge};
+use serenity::{all::GuildId, futures::future::BoxFuture};
+use tokio::time::interval;
+use tracing::warn;
+
+use crate::{
+	config::{settings::LavalinkNodeConfig, types::bot_context},
+	stats::counters::METRICS,
+	utils::voice::{DEFAULT_VOLUME, build_filters, lavalink_gain, lavalink_volume},
+};
+
+const NODE_HEALTH_INTERVAL: Duration = Duration::from_secs(15);
+
+struct NodeOptions {
+	weight: u32,
+	region: Option<String>,
+}
+
+/// Options of every configured node, indexed like `LavalinkClient::nodes`
+static NODE_OPTIONS: OnceLock<Vec<NodeOptions>> = OnceLock::new();
+
+pub(super) fn register_nodes(nodes: &[LavalinkNodeConfig]) {
+	let options = nodes
+		.iter()
+		.map(|node| NodeOptions {
+			weight: node.weight.max(1),
+			region: node.region.clone().filter(|region| !region.is_empty()),
+		})
+		.collect();
+	if NODE_OPTIONS.set(options).is_err() {
+		warn!("Lavalink nodes were already registered");
+	}
+}
+
+/// Voice region of the channel the bot sits in, if Discord doesn't pick it
+/// automatically
+fn voice_region(guild_id: GuildId) -> Option<String> {
+	let ctx = bot_context();
+	let bot_id = ctx.cache.current_user().id;
+	let guild = ctx.cache.guild(guild_id)?;
+	let channel_id = guild.voice_states.get(&bot_id)?.channel_id?;
+	guild
+		.channels
+		.get(&channel_id)?
+		.rtc_region
+		.as_ref()
+		.map(ToString::to_string)
+}
+
+/// Picks a connected node, preferring the guild's voice region and then the
+/// lowest amount of players relative to the node weight. Falls back to the
+/// first node when none is running, `setup_lavalink` makes sure there is one
+pub(super) fn pick_node(
+	client: &LavalinkClient,
+	guild_id: LavaGuildId,
+) -> BoxFuture<'_, Arc<Node>> {
+	Box::pin(async move {
+		let region = voice_region(GuildId::from(guild_id.0));
+		let options = NODE_OPTIONS.get();
+		client
+			.nodes
+			.iter()
+			.filter(|node| node.is_running.load(Ordering::Relaxed))
+			.min_by_key(|node| {
+				let node_options = options.and_then(|options| options.get(node.id));
+				let region_rank = match (
+					region.as_deref(),
+					node_options.and_then(|node_options| node_options.region.as_deref()),
+				) {
+					(Some(region), Some(node_region)) if region == node_region => 0,
+					(Some(_), Some(_)) => 2,
+					_ => 1,
+				};
+				let weight = node_options.map_or(1, |node_options| node_options.weight);
+				let load = node
+					.stats
+					.load()
+					.playing_players
+					.saturating_add(1)
+					.saturating_mul(100)
+					.checked_div(u64::from(weight))
+					.unwrap_or(u64::MAX);
+				(region_rank, load)
+			})
+			.or_else(|| client.nodes.first())
+			.cloned()
+			.unwrap()
+	})
+}
+
+/// Recreates the player of a guild on another node and requeues the last
+/// started track where it left off in front of the remaining queue
+async fn move_player(client: &LavalinkClient, guild_id: LavaGuildId) -> AResult<()> {
+	let bot_data = &bot_context().data;
+	let serenity_guild_id = GuildId::from(guild_id.0);
+	let Some(old_player) = client.get_player_context(guild_id) else {
+		return Ok(());
+	};
+	let queue = old_player.get_queue().get_queue().await?;
+	// Last position the dead node reported, the player state is kept locally
+	let position = old_player
+		.get_player()
+		.await
+		.ok()
+		.map(|player| Duration::from_millis(player.state.position));
+	let Some(handler_lock) = bot_data.music_manager.get(serenity_guild_id) else {
+		return Ok(());
+	};
+	let Some(connection_info) = handler_lock.lock().await.current_connection().cloned() else {
+		bail!("No voice connection to move for guild {serenity_guild_id}");
+	};
+	// The dead node can't be told about it, dropping the player locally is enough
+	let _ = client.delete_player(guild_id).await;
+
+	let player = client
+		.create_player_context(guild_id, connection_info)
+		.await?;
+	let mut tracks = VecDeque::with_capacity(queue.len().saturating_add(1));
+	if let Some(guild_cache) = bot_data.guilds.get(&serenity_guild_id) {
+		let music_data = &guild_cache.music_data;
+		let current = music_data
+			.lavalink_track
+			.read()
+			.ok()
+			.and_then(|track| track.clone());
+		let volume = lavalink_volume(
+			music_data.volume.load(Ordering::Relaxed),
+			current.as_ref().and_then(lavalink_gain),
+		);
+		if let Some(current) = current {
+			let mut track = TrackInQueue::from(current);
+			track.start_time = position;
+			tracks.push_back(track);
+		}
+		if volume != DEFAULT_VOLUME {
+			player.set_volume(volume).await?;
+		}
+		let active = music_data
+			.filters
+			.read()
+			.map(|filters| filters.clone())
+			.unwrap_or_default();
+		if !active.is_empty() {
+			player.set_filters(build_filters(&active)).await?;
+		}
+	}
+	// The replay of a looping track gets queued again once it restarts
+	let looping = bot_data
+		.guilds
+		.get(&serenity_guild_id)
+		.is_some_and(|guild_cache| {
+			guild_cache
+				.music_data
+				.lavalink_looping
+				.load(Ordering::Relaxed)
+		});
+	tracks.extend(queue.into_iter().skip(usize::from(looping)));
+
+	if !tracks.is_empty() {
+		player.get_queue().append(tracks)?;
+		player.skip()?;
+	}
+
+	Ok(())
+}
+
+async fn move_players(client: &LavalinkClient, dead_node: &Node) {
+	let guild_ids: Vec<LavaGuildId> = client
+		.players
+		.iter()
+		.filter(|entry| entry.value().1.id == dead_node.id)
+		.map(|entry| *entry.key())
+		.collect();
+	for guild_id in guild_ids {
+		if let Err(err) = move_player(client, guild_id).await {
+			warn!("Failed to move player to another Lavalink node: {err}");
+		} else {
+			counter!(METRICS.lavalink_failovers.as_str()).increment(1);
+		}
+	}
+}
+
+/// Exports node health and moves players off nodes that lost their connection
+pub async fn lavalink_health_task(client: LavalinkClient) {
+	let mut was_running = vec![true; client.nodes.len()];
+	let mut ticker = interval(NODE_HEALTH_INTERVAL);
+	loop {
+		ticker.tick().await;
+		for (node, was_running) in client.nodes.iter().zip(was_running.iter_mut()) {
+			let running = node.is_running.load(Ordering::Relaxed);
+			let stats = node.stats.load();
+			let address = node.websocket_address.clone();
+			gauge!(METRICS.lavalink_node_up.as_str(), "node" => address.clone())
+				.set(f64::from(u8::from(running)));
+			gauge!(METRICS.lavalink_node_players.as_str(), "node" => address.clone()).set(
+				f64::from(u32::try_from(stats.playing_players).unwrap_or(u32::MAX)),
+			);
+			gauge!(METRICS.lavalink_node_load.as_str(), "node" => address)
+				.set(stats.cpu.lavalink_load);
+			if *was_running && !running {
+				warn!(
+					"Lavalink node {} disconnected, moving its players",
+					node.websocket_address
+				);
+				move_players(&client, node).await;
+			}
+			*was_running = running;
+		}
+	}
+}
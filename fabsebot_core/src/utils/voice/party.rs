@@ -0,0 +1,279 @@
+use std::{iter::once, sync::LazyLock};
+
+use anyhow::Result as AResult;
+use dashmap::DashMap;
+use lavalink_rs::player_context::TrackInQueue;
+use serde_json::{from_value, to_value};
+use serenity::all::{CreateMessage, GenericChannelId, GuildId, Message};
+use songbird::input::{Input, cached::Compressed};
+use tracing::warn;
+
+use crate::{
+	config::{
+		constants::QUEUEING_MSG,
+		types::{Data, GuildCache, bot_context},
+	},
+	utils::voice::{
+		QueueData,
+		backend::{BackendTrack, PlayerAction, fetch_backend},
+	},
+};
+
+const JOIN_CODE_LENGTH: usize = 6;
+/// Leaves out characters that are easy to mix up when typing a code
+const JOIN_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+pub const PARTY_GUEST_DENIAL: &str = "Only the host server of the listening party can do that";
+
+struct ListeningParty {
+	host: GuildId,
+	guests: Vec<GuildId>,
+}
+
+impl ListeningParty {
+	fn members(&self) -> impl Iterator<Item = GuildId> {
+		once(self.host).chain(self.guests.iter().copied())
+	}
+}
+
+/// Open parties by join code
+static PARTIES: LazyLock<DashMap<String, ListeningParty>> = LazyLock::new(DashMap::new);
+
+pub enum PartyLeave {
+	/// The host left, so every guest got dropped
+	Disbanded(Vec<GuildId>),
+	Left,
+}
+
+fn join_code() -> String {
+	loop {
+		let code: String = (0..JOIN_CODE_LENGTH)
+			.filter_map(|_| fastrand::choice(JOIN_CODE_CHARS))
+			.map(|byte| char::from(*byte))
+			.collect();
+		if !PARTIES.contains_key(&code) {
+			return code;
+		}
+	}
+}
+
+fn party_code(guild_id: GuildId) -> Option<String> {
+	let guild_cache = bot_context().data.guilds.get(&guild_id)?;
+	guild_cache
+		.music_data
+		.party
+		.read()
+		.ok()
+		.and_then(|party| party.clone())
+}
+
+fn set_party_code(guild_id: GuildId, code: Option<String>) {
+	if let Some(guild_cache) = bot_context().data.guilds.get(&guild_id)
+		&& let Ok(mut party) = guild_cache.music_data.party.write()
+	{
+		*party = code;
+	}
+}
+
+/// Join code and members of the party the guild is in
+#[must_use]
+pub fn party_info(guild_id: GuildId) -> Option<(String, GuildId, Vec<GuildId>)> {
+	let code = party_code(guild_id)?;
+	let party = PARTIES.get(&code)?;
+	Some((code, party.host, party.guests.clone()))
+}
+
+/// Whether the guild listens along with a party it doesn't host
+#[must_use]
+pub fn is_party_guest(guild_id: GuildId) -> bool {
+	party_code(guild_id)
+		.and_then(|code| PARTIES.get(&code).map(|party| party.host != guild_id))
+		.unwrap_or(false)
+}
+
+/// Every other guild in the same party
+fn party_peers(guild_id: GuildId) -> Vec<GuildId> {
+	party_code(guild_id)
+		.and_then(|code| {
+			PARTIES.get(&code).map(|party| {
+				party
+					.members()
+					.filter(|member| *member != guild_id)
+					.collect()
+			})
+		})
+		.unwrap_or_default()
+}
+
+/// Opens a party hosted by the guild, returning its join code
+pub fn create_party(guild_cache: &GuildCache, guild_id: GuildId) -> Result<String, &'static str> {
+	let Ok(mut party) = guild_cache.music_data.party.write() else {
+		return Err("Failed to start a listening party");
+	};
+	if party.is_some() {
+		return Err("This server is already in a listening party");
+	}
+	let code = join_code();
+	PARTIES.insert(
+		code.clone(),
+		ListeningParty {
+			host: guild_id,
+			guests: Vec::new(),
+		},
+	);
+	*party = Some(code.clone());
+	Ok(code)
+}
+
+/// Adds the guild to the party with the given code, returning its host
+pub fn join_party(
+	guild_cache: &GuildCache,
+	guild_id: GuildId,
+	code: &str,
+) -> Result<GuildId, &'static str> {
+	let code = code.trim().to_uppercase();
+	let Ok(mut party) = guild_cache.music_data.party.write() else {
+		return Err("Failed to join the listening party");
+	};
+	if party.is_some() {
+		return Err("This server is already in a listening party");
+	}
+	let Some(mut listening_party) = PARTIES.get_mut(&code) else {
+		return Err("No listening party found with that code");
+	};
+	listening_party.guests.push(guild_id);
+	*party = Some(code);
+	Ok(listening_party.host)
+}
+
+/// Takes the guild out of its party, closing the party if it was the host
+pub fn leave_party(guild_id: GuildId) -> Option<PartyLeave> {
+	let code = party_code(guild_id)?;
+	set_party_code(guild_id, None);
+	let host = PARTIES.get(&code)?.host;
+	if host == guild_id {
+		let (_, party) = PARTIES.remove(&code)?;
+		for guest in &party.guests {
+			set_party_code(*guest, None);
+		}
+		return Some(PartyLeave::Disbanded(party.guests));
+	}
+	if let Some(mut party) = PARTIES.get_mut(&code) {
+		party.guests.retain(|guest| *guest != guild_id);
+	}
+	Some(PartyLeave::Left)
+}
+
+/// Audio that got queued in one guild of a party
+pub(super) enum MirroredTrack<'a> {
+	/// Fetched by songbird, along with its queue data
+	Audio(&'a Compressed, &'a QueueData),
+	/// Resolved by Lavalink, with the queue data in `user_data`
+	Lavalink(&'a [TrackInQueue]),
+}
+
+/// Posts the message the now playing info of mirrored tracks goes into, in
+/// the voice channel of the guild
+async fn queueing_message(bot_data: &Data, guild_id: GuildId) -> AResult<Option<Message>> {
+	let Some(handler_lock) = bot_data.music_manager.get(guild_id) else {
+		return Ok(None);
+	};
+	let Some(channel_id) = handler_lock.lock().await.current_channel() else {
+		return Ok(None);
+	};
+	let msg = GenericChannelId::new(channel_id.get())
+		.send_message(
+			&bot_context().http,
+			CreateMessage::new().content(QUEUEING_MSG),
+		)
+		.await?;
+	Ok(Some(msg))
+}
+
+async fn mirror_to_guild(
+	bot_data: &Data,
+	guild_id: GuildId,
+	track: &MirroredTrack<'_>,
+) -> AResult<()> {
+	let Some(backend) = fetch_backend(bot_data, guild_id) else {
+		return Ok(());
+	};
+	let Some(msg) = queueing_message(bot_data, guild_id).await? else {
+		return Ok(());
+	};
+	let tracks = match track {
+		MirroredTrack::Audio(compressed, queue_data) => vec![BackendTrack::Audio(
+			Input::from(compressed.new_handle()),
+			(*queue_data).clone().for_message(&msg),
+		)],
+		MirroredTrack::Lavalink(tracks) => {
+			let mut mirrored = Vec::with_capacity(tracks.len());
+			for track in *tracks {
+				let mut track = track.clone();
+				if let Some(user_data) = track.track.user_data.take() {
+					let queue_data: QueueData = from_value(user_data)?;
+					track.track.user_data = Some(to_value(queue_data.for_message(&msg))?);
+				}
+				mirrored.push(BackendTrack::Lavalink(track));
+			}
+			mirrored
+		}
+	};
+	backend.enqueue(tracks).await?;
+
+	Ok(())
+}
+
+/// Replaces the queue of a guild that just joined a party with the track the
+/// host is playing, at the host's position, followed by the host's queue
+pub async fn sync_with_host(bot_data: &Data, guild_id: GuildId, host: GuildId) -> AResult<()> {
+	let (Some(host_backend), Some(backend)) = (
+		fetch_backend(bot_data, host),
+		fetch_backend(bot_data, guild_id),
+	) else {
+		return Ok(());
+	};
+	let host_queue = host_backend.queue().await?;
+	let position = host_backend
+		.playback_state()
+		.await?
+		.map(|playback| playback.position);
+	backend.clear().await?;
+	if host_queue.is_empty() {
+		return Ok(());
+	}
+	let Some(msg) = queueing_message(bot_data, guild_id).await? else {
+		return Ok(());
+	};
+	let tracks = host_queue
+		.into_iter()
+		.enumerate()
+		.map(|(index, queue_data)| {
+			BackendTrack::Source(
+				queue_data.for_message(&msg),
+				position.filter(|_| index == 0),
+			)
+		})
+		.collect();
+	backend.enqueue(tracks).await
+}
+
+/// Queues what got added in one guild for every other guild of its party
+pub(super) async fn mirror_to_party(guild_id: GuildId, track: MirroredTrack<'_>) {
+	let bot_data = &bot_context().data;
+	for peer in party_peers(guild_id) {
+		if let Err(err) = mirror_to_guild(bot_data, peer, &track).await {
+			warn!("Failed to queue song for listening party: {err}");
+		}
+	}
+}
+
+/// Applies a player control of the host to every guest as well
+pub(super) async fn apply_to_peers(bot_data: &Data, guild_id: GuildId, action: &PlayerAction) {
+	for peer in party_peers(guild_id) {
+		if let Some(backend) = fetch_backend(bot_data, peer)
+			&& let Err(err) = backend.apply(action).await
+		{
+			warn!("Failed to apply control to listening party: {err}");
+		}
+	}
+}
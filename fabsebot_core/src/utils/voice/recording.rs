@@ -0,0 +1,627 @@
+use std::{
+	collections::HashMap,
+	io::ErrorKind,
+	mem::take,
+	path::{Path, PathBuf},
+	pin::pin,
+	sync::{
+		Arc, LazyLock,
+		atomic::{AtomicBool, Ordering},
+	},
+	time::{Duration, Instant},
+};
+
+use anyhow::Result as AResult;
+use audiopus::{
+	Application, Bitrate, Channels as OpusChannels, SampleRate as OpusSampleRate, coder::Encoder,
+};
+use dashmap::{DashMap, DashSet};
+use jiff::Timestamp;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+use poise::CreateReply;
+use serenity::{
+	all::{
+		ButtonStyle, Colour, ComponentInteraction, ComponentInteractionCollector,
+		Context as SerenityContext, CreateActionRow, CreateAttachment, CreateButton,
+		CreateContainer, CreateInteractionResponse, CreateInteractionResponseMessage,
+		CreateMessage, GuildId, Message, UserId,
+	},
+	async_trait,
+	builder::{CreateComponent, CreateContainerComponent},
+	futures::StreamExt as _,
+};
+use songbird::{
+	Config, CoreEvent, Event as SongBirdEvent, EventContext, EventHandler as VoiceEventHandler,
+	driver::{Channels, DecodeConfig, DecodeMode, SampleRate},
+	events::context_data::VoiceTick,
+};
+use tokio::{
+	fs::{File, create_dir_all, read, read_dir, remove_dir_all},
+	io::{AsyncWriteExt as _, BufWriter},
+	select, spawn,
+	sync::{Mutex, Notify},
+	time::sleep,
+};
+use tracing::warn;
+
+use crate::{
+	config::types::SContext,
+	utils::{
+		helpers::{edit_message_container, reply_container, separator, text_display},
+		voice::format_timestamp,
+	},
+};
+
+const RECORDINGS_DIR: &str = "recordings";
+const RECORDING_MAX_MINUTES: u64 = 15;
+const RECORDING_MAX_DURATION: Duration = Duration::from_mins(RECORDING_MAX_MINUTES);
+/// Recordings that couldn't be uploaded are deleted after this many days
+const RECORDING_RETENTION_DAYS: u64 = 7;
+const RECORDING_RETENTION: Duration = Duration::from_hours(RECORDING_RETENTION_DAYS * 24);
+/// Upload limit of a single message, files bigger than this stay on disk
+const ATTACHMENT_LIMIT: u64 = 10 * 1024 * 1024;
+const MAX_ATTACHMENTS: usize = 10;
+const SAMPLE_RATE: u32 = 48_000;
+/// Songbird hands over 20ms of mono audio per tick, which is one Opus frame
+const TICK_SAMPLES: usize = 960;
+const TICK_GRANULES: u64 = 960;
+const SILENT_TICK: [i16; TICK_SAMPLES] = [0; TICK_SAMPLES];
+/// Plenty for voice, a full recording of one speaker stays around 3.5 MiB
+const OPUS_BITRATE: i32 = 32_000;
+/// Largest packet libopus produces
+const MAX_OPUS_PACKET: usize = 4000;
+const OPUS_VENDOR: &str = "fabsebot";
+const CONSENT_BUTTON: &str = "record-consent";
+const WITHDRAW_BUTTON: &str = "record-withdraw";
+const STOP_BUTTON: &str = "record-stop";
+
+/// Active recordings by guild
+static RECORDINGS: LazyLock<DashMap<GuildId, Arc<Recording>>> = LazyLock::new(DashMap::new);
+
+fn opus_head(pre_skip: u16) -> Vec<u8> {
+	let mut head = Vec::with_capacity(19);
+	head.extend_from_slice(b"OpusHead");
+	// Version 1, mono
+	head.extend_from_slice(&[1, 1]);
+	head.extend_from_slice(&pre_skip.to_le_bytes());
+	head.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+	// No output gain and a single stream
+	head.extend_from_slice(&0_i16.to_le_bytes());
+	head.push(0);
+	head
+}
+
+fn opus_tags() -> Vec<u8> {
+	let mut tags = Vec::with_capacity(OPUS_VENDOR.len().saturating_add(16));
+	tags.extend_from_slice(b"OpusTags");
+	tags.extend_from_slice(
+		&u32::try_from(OPUS_VENDOR.len())
+			.unwrap_or(u32::MAX)
+			.to_le_bytes(),
+	);
+	tags.extend_from_slice(OPUS_VENDOR.as_bytes());
+	// No user comments
+	tags.extend_from_slice(&0_u32.to_le_bytes());
+	tags
+}
+
+/// Encodes ticks of audio into an Ogg Opus file as they come in
+struct OpusWriter {
+	file: BufWriter<File>,
+	encoder: Encoder,
+	pages: PacketWriter<'static, Vec<u8>>,
+	serial: u32,
+	granule: u64,
+	size: u64,
+}
+
+impl OpusWriter {
+	async fn create(path: &Path) -> AResult<Self> {
+		let mut encoder = Encoder::new(
+			OpusSampleRate::Hz48000,
+			OpusChannels::Mono,
+			Application::Voip,
+		)?;
+		encoder.set_bitrate(Bitrate::BitsPerSecond(OPUS_BITRATE))?;
+		let pre_skip = u16::try_from(encoder.lookahead()?).unwrap_or(u16::MAX);
+		let mut writer = Self {
+			file: BufWriter::new(File::create(path).await?),
+			encoder,
+			pages: PacketWriter::new(Vec::new()),
+			serial: fastrand::u32(..),
+			granule: 0,
+			size: 0,
+		};
+		writer
+			.write_packet(opus_head(pre_skip), PacketWriteEndInfo::EndPage)
+			.await?;
+		writer
+			.write_packet(opus_tags(), PacketWriteEndInfo::EndPage)
+			.await?;
+		Ok(writer)
+	}
+
+	async fn write_packet(&mut self, packet: Vec<u8>, info: PacketWriteEndInfo) -> AResult<()> {
+		self.pages
+			.write_packet(packet, self.serial, info, self.granule)?;
+		let pages = take(self.pages.inner_mut());
+		self.file.write_all(&pages).await?;
+		self.size = self
+			.size
+			.saturating_add(u64::try_from(pages.len()).unwrap_or(u64::MAX));
+		Ok(())
+	}
+
+	async fn encode(&mut self, samples: &[i16], info: PacketWriteEndInfo) -> AResult<()> {
+		// Opus only takes whole frames
+		let mut frame = SILENT_TICK;
+		for (frame_sample, sample) in frame.iter_mut().zip(samples) {
+			*frame_sample = *sample;
+		}
+		let mut packet = vec![0; MAX_OPUS_PACKET];
+		let len = self.encoder.encode(&frame, &mut packet)?;
+		packet.truncate(len);
+		self.granule = self.granule.saturating_add(TICK_GRANULES);
+		self.write_packet(packet, info).await
+	}
+
+	async fn write(&mut self, samples: &[i16]) -> AResult<()> {
+		self.encode(samples, PacketWriteEndInfo::NormalPacket).await
+	}
+
+	/// Ends the stream with a silent frame, returning the file size
+	async fn finish(mut self) -> AResult<u64> {
+		self.encode(&SILENT_TICK, PacketWriteEndInfo::EndStream)
+			.await?;
+		self.file.flush().await?;
+		Ok(self.size)
+	}
+}
+
+/// One file with everyone mixed together and one per speaker, all starting
+/// at the same moment so they line up
+struct RecordingFiles {
+	dir: PathBuf,
+	mixed: OpusWriter,
+	speakers: HashMap<UserId, OpusWriter>,
+	ticks: u64,
+}
+
+/// Finished file of a recording along with the name to upload it as
+struct RecordedFile {
+	name: String,
+	path: PathBuf,
+	size: u64,
+}
+
+impl RecordingFiles {
+	async fn create(dir: PathBuf) -> AResult<Self> {
+		create_dir_all(&dir).await?;
+		let mixed = OpusWriter::create(&dir.join("mixed.ogg")).await?;
+		Ok(Self {
+			dir,
+			mixed,
+			speakers: HashMap::new(),
+			ticks: 0,
+		})
+	}
+
+	async fn write_tick(&mut self, voices: &HashMap<UserId, &[i16]>) -> AResult<()> {
+		let mut mixed = SILENT_TICK;
+		for (user_id, voice) in voices {
+			for (mixed_sample, sample) in mixed.iter_mut().zip(voice.iter()) {
+				*mixed_sample = mixed_sample.saturating_add(*sample);
+			}
+			if !self.speakers.contains_key(user_id) {
+				let mut writer =
+					OpusWriter::create(&self.dir.join(format!("{user_id}.ogg"))).await?;
+				for _ in 0..self.ticks {
+					writer.write(&SILENT_TICK).await?;
+				}
+				self.speakers.insert(*user_id, writer);
+			}
+		}
+		for (user_id, writer) in &mut self.speakers {
+			writer
+				.write(voices.get(user_id).copied().unwrap_or(&SILENT_TICK))
+				.await?;
+		}
+		self.mixed.write(&mixed).await?;
+		self.ticks = self.ticks.saturating_add(1);
+		Ok(())
+	}
+
+	/// Returns the mixed file followed by the file of every speaker
+	async fn finish(self) -> AResult<Vec<RecordedFile>> {
+		let mut files = Vec::with_capacity(self.speakers.len().saturating_add(1));
+		files.push(RecordedFile {
+			name: "recording.ogg".to_owned(),
+			path: self.dir.join("mixed.ogg"),
+			size: self.mixed.finish().await?,
+		});
+		for (user_id, writer) in self.speakers {
+			files.push(RecordedFile {
+				name: format!("speaker-{user_id}.ogg"),
+				path: self.dir.join(format!("{user_id}.ogg")),
+				size: writer.finish().await?,
+			});
+		}
+		Ok(files)
+	}
+}
+
+/// Deletes the recordings that have been left on disk for too long
+async fn purge_old_recordings() -> AResult<()> {
+	let mut entries = match read_dir(RECORDINGS_DIR).await {
+		Ok(entries) => entries,
+		Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+		Err(err) => return Err(err.into()),
+	};
+	while let Some(entry) = entries.next_entry().await? {
+		let modified = entry.metadata().await?.modified()?;
+		if modified
+			.elapsed()
+			.is_ok_and(|elapsed| elapsed > RECORDING_RETENTION)
+		{
+			remove_dir_all(entry.path()).await?;
+		}
+	}
+	Ok(())
+}
+
+struct Recording {
+	started_by: UserId,
+	started_at: Instant,
+	consented: DashSet<UserId>,
+	/// Users behind the SSRCs of incoming audio
+	speakers: DashMap<u32, UserId>,
+	files: Mutex<Option<RecordingFiles>>,
+	active: AtomicBool,
+	stop: Notify,
+}
+
+impl Recording {
+	async fn record_tick(&self, tick: &VoiceTick) -> AResult<()> {
+		let voices: HashMap<UserId, &[i16]> = tick
+			.speaking
+			.iter()
+			.filter_map(|(ssrc, voice_data)| {
+				let user_id = *self.speakers.get(ssrc)?;
+				Some((user_id, voice_data.decoded_voice.as_deref()?))
+			})
+			.filter(|(user_id, _)| self.consented.contains(user_id))
+			.collect();
+		if let Some(files) = self.files.lock().await.as_mut() {
+			files.write_tick(&voices).await?;
+		}
+		Ok(())
+	}
+
+	fn container<'a>(&self) -> CreateContainer<'a> {
+		let mut text = format!(
+			"# 🔴 Recording\nOnly those who consent below get recorded, for at most \
+			 {RECORDING_MAX_MINUTES} minutes\n"
+		);
+		if self.consented.is_empty() {
+			text.push_str("*Nobody consented yet*");
+		} else {
+			let mentions: Vec<String> = self
+				.consented
+				.iter()
+				.map(|user_id| format!("<@{}>", *user_id))
+				.collect();
+			text.push_str("**Recording:** ");
+			text.push_str(&mentions.join(" "));
+		}
+		let buttons = vec![
+			CreateButton::new(CONSENT_BUTTON)
+				.label("Record me")
+				.style(ButtonStyle::Success),
+			CreateButton::new(WITHDRAW_BUTTON)
+				.label("Stop recording me")
+				.style(ButtonStyle::Secondary),
+			CreateButton::new(STOP_BUTTON)
+				.label("Stop")
+				.style(ButtonStyle::Danger),
+		];
+		CreateContainer::new(vec![text_display(text)])
+			.add_component(separator())
+			.add_component(CreateContainerComponent::ActionRow(
+				CreateActionRow::buttons(buttons),
+			))
+			.accent_colour(Colour::RED)
+	}
+}
+
+#[derive(Clone)]
+struct RecordingReceiver {
+	recording: Arc<Recording>,
+}
+
+#[async_trait]
+impl VoiceEventHandler for RecordingReceiver {
+	async fn act(&self, event: &EventContext<'_>) -> Option<SongBirdEvent> {
+		if !self.recording.active.load(Ordering::Relaxed) {
+			return Some(SongBirdEvent::Cancel);
+		}
+		match event {
+			EventContext::SpeakingStateUpdate(speaking) => {
+				if let Some(user_id) = speaking.user_id {
+					self.recording
+						.speakers
+						.insert(speaking.ssrc, UserId::new(user_id.0));
+				}
+			}
+			EventContext::VoiceTick(tick) => {
+				if let Err(err) = self.recording.record_tick(tick).await {
+					warn!("Failed to write recording: {err}");
+					self.recording.stop.notify_one();
+				}
+			}
+			_ => {}
+		}
+		None
+	}
+}
+
+fn decode_config(decode: bool) -> Config {
+	let decode_mode = if decode {
+		DecodeMode::Decode(DecodeConfig::new(Channels::Mono, SampleRate::Hz48000))
+	} else {
+		DecodeMode::Decrypt
+	};
+	Config::default().decode_mode(decode_mode)
+}
+
+async fn handle_recording_interaction(
+	ctx: &SerenityContext,
+	interaction: ComponentInteraction,
+	recording: &Recording,
+) -> AResult<()> {
+	let user_id = interaction.user.id;
+	match interaction.data.custom_id.as_str() {
+		CONSENT_BUTTON => {
+			recording.consented.insert(user_id);
+		}
+		WITHDRAW_BUTTON => {
+			recording.consented.remove(&user_id);
+		}
+		STOP_BUTTON if user_id == recording.started_by => {
+			recording.stop.notify_one();
+		}
+		STOP_BUTTON => {
+			interaction
+				.create_response(
+					&ctx.http,
+					CreateInteractionResponse::Message(
+						CreateInteractionResponseMessage::new()
+							.content("Only the one who started the recording can stop it")
+							.ephemeral(true),
+					),
+				)
+				.await?;
+			return Ok(());
+		}
+		_ => return Ok(()),
+	}
+	let component = [CreateComponent::Container(recording.container())];
+	interaction
+		.create_response(
+			&ctx.http,
+			CreateInteractionResponse::UpdateMessage(
+				CreateInteractionResponseMessage::new().components(&component),
+			),
+		)
+		.await?;
+	Ok(())
+}
+
+async fn finish_recording(
+	ctx: &SerenityContext,
+	guild_id: GuildId,
+	msg: &mut Message,
+	recording: &Recording,
+) -> AResult<()> {
+	recording.active.store(false, Ordering::Relaxed);
+	RECORDINGS.remove(&guild_id);
+	if let Some(handler_lock) = ctx.data().music_manager.get(guild_id) {
+		handler_lock.lock().await.set_config(decode_config(false));
+	}
+	let files = recording.files.lock().await.take();
+	let files = match files {
+		Some(files) => Some((files.dir.clone(), files.finish().await?)),
+		None => None,
+	};
+	// Only the indicator, the files get uploaded even if it's gone
+	let finished = [CreateComponent::Container(
+		CreateContainer::new(vec![text_display(format!(
+			"# ⏹️ Recording finished\nRecorded for {}",
+			format_timestamp(recording.started_at.elapsed())
+		))])
+		.accent_colour(Colour::DARK_GREY),
+	)];
+	if let Err(err) = msg.edit(&ctx.http, edit_message_container(&finished)).await {
+		warn!("Failed to mark recording as finished: {err}");
+	}
+
+	let Some((dir, files)) = files else {
+		return Ok(());
+	};
+	let mut uploads = Vec::new();
+	let mut upload_size = 0_u64;
+	let mut kept = false;
+	let mut first = true;
+	for file in files {
+		if file.size > ATTACHMENT_LIMIT {
+			kept = true;
+			continue;
+		}
+		if uploads.len() == MAX_ATTACHMENTS
+			|| upload_size.saturating_add(file.size) > ATTACHMENT_LIMIT
+		{
+			upload_recording(ctx, msg, take(&mut uploads), first).await?;
+			upload_size = 0;
+			first = false;
+		}
+		upload_size = upload_size.saturating_add(file.size);
+		uploads.push(CreateAttachment::bytes(read(&file.path).await?, file.name));
+	}
+	if !uploads.is_empty() {
+		upload_recording(ctx, msg, uploads, first).await?;
+	}
+	if kept {
+		msg.channel_id
+			.send_message(
+				&ctx.http,
+				CreateMessage::new().content(format!(
+					"Some files were too big to upload, they're kept in `{}` for \
+					 {RECORDING_RETENTION_DAYS} days",
+					dir.display()
+				)),
+			)
+			.await?;
+	} else {
+		remove_dir_all(&dir).await?;
+	}
+	Ok(())
+}
+
+async fn upload_recording(
+	ctx: &SerenityContext,
+	msg: &Message,
+	attachments: Vec<CreateAttachment<'static>>,
+	first: bool,
+) -> AResult<()> {
+	let content = if first {
+		"Here's the recording, every speaker also got their own file"
+	} else {
+		"More speakers of the recording"
+	};
+	msg.channel_id
+		.send_message(
+			&ctx.http,
+			CreateMessage::new().content(content).files(attachments),
+		)
+		.await?;
+	Ok(())
+}
+
+async fn watch_recording(
+	ctx: SerenityContext,
+	guild_id: GuildId,
+	mut msg: Message,
+	recording: Arc<Recording>,
+) -> AResult<()> {
+	let mut collector_stream = ComponentInteractionCollector::new(&ctx)
+		.timeout(RECORDING_MAX_DURATION)
+		.message_id(msg.id)
+		.stream();
+	let mut deadline = pin!(sleep(RECORDING_MAX_DURATION));
+
+	loop {
+		select! {
+			interaction = collector_stream.next() => {
+				let Some(interaction) = interaction else {
+					break;
+				};
+				if let Err(err) = handle_recording_interaction(&ctx, interaction, &recording).await {
+					warn!("Failed to handle recording button: {err}");
+				}
+			}
+			() = recording.stop.notified() => break,
+			() = &mut deadline => break,
+		}
+	}
+
+	finish_recording(&ctx, guild_id, &mut msg, &recording).await
+}
+
+/// Starts recording the voice channel the bot is in, capturing only those
+/// who opt in with the button on the recording message
+pub async fn start_recording(ctx: SContext<'_>) -> AResult<()> {
+	let guild_id = ctx.guild_id().unwrap();
+	let bot_data = ctx.data();
+	let Some(handler_lock) = bot_data.music_manager.get(guild_id).filter(|_| {
+		bot_data
+			.guilds
+			.get(&guild_id)
+			.is_some_and(|guild_cache| guild_cache.music_data.is_songbird_connected())
+	}) else {
+		ctx.send(
+			CreateReply::new()
+				.content("I have to be in a voice channel through join_voice_old to record")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	};
+	if RECORDINGS.contains_key(&guild_id) {
+		ctx.send(
+			CreateReply::new()
+				.content("I'm already recording here")
+				.ephemeral(true),
+		)
+		.await?;
+		return Ok(());
+	}
+
+	if let Err(err) = purge_old_recordings().await {
+		warn!("Failed to delete old recordings: {err}");
+	}
+	let dir =
+		Path::new(RECORDINGS_DIR).join(format!("{guild_id}-{}", Timestamp::now().as_second()));
+	let recording = Arc::new(Recording {
+		started_by: ctx.author().id,
+		started_at: Instant::now(),
+		consented: DashSet::new(),
+		speakers: DashMap::new(),
+		files: Mutex::new(Some(RecordingFiles::create(dir).await?)),
+		active: AtomicBool::new(true),
+		stop: Notify::new(),
+	});
+	RECORDINGS.insert(guild_id, recording.clone());
+
+	{
+		let mut handler = handler_lock.lock().await;
+		handler.set_config(decode_config(true));
+		let receiver = RecordingReceiver {
+			recording: recording.clone(),
+		};
+		handler.add_global_event(
+			SongBirdEvent::Core(CoreEvent::SpeakingStateUpdate),
+			receiver.clone(),
+		);
+		handler.add_global_event(SongBirdEvent::Core(CoreEvent::VoiceTick), receiver);
+	}
+
+	let component = [CreateComponent::Container(recording.container())];
+	let reply = ctx.send(reply_container(&component)).await?;
+	let msg = reply.message().await?.into_owned();
+	let serenity_context = ctx.serenity_context().clone();
+	spawn(async move {
+		if let Err(err) = watch_recording(serenity_context, guild_id, msg, recording).await {
+			warn!("Failed to finish recording: {err}");
+		}
+	});
+
+	Ok(())
+}
+
+/// Stops the recording in the guild, returning why it couldn't if it couldn't
+pub fn stop_recording(guild_id: GuildId, user_id: UserId) -> Option<&'static str> {
+	let Some(recording) = RECORDINGS.get(&guild_id) else {
+		return Some("I'm not recording here");
+	};
+	if recording.started_by != user_id {
+		return Some("Only the one who started the recording can stop it");
+	}
+	recording.stop.notify_one();
+	None
+}
+
+/// Ends the recording without a say from anyone, like when leaving the channel
+pub(super) fn end_recording(guild_id: GuildId) {
+	if let Some(recording) = RECORDINGS.get(&guild_id) {
+		recording.stop.notify_one();
+	}
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use serenity::all::{GenericChannelId, Message, MessageId};
+use tokio::{spawn, time::sleep};
+use tracing::warn;
+
+use crate::config::types::bot_context;
+
+/// Progress of a song request in the music channel, shown as a reaction on it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RequestStatus {
+	Queued,
+	Playing,
+	Failed,
+}
+
+impl RequestStatus {
+	const fn emoji(self) -> char {
+		match self {
+			Self::Queued => '⏳',
+			Self::Playing => '✅',
+			Self::Failed => '❌',
+		}
+	}
+}
+
+/// Reacts with the status of a request, taking back the queued reaction once
+/// it has played or failed
+pub async fn mark_request(
+	channel_id: GenericChannelId,
+	message_id: MessageId,
+	status: RequestStatus,
+) {
+	let http = &bot_context().http;
+	if status != RequestStatus::Queued {
+		// The request might have been cleaned up already
+		let _ = channel_id
+			.delete_reaction(http, message_id, None, RequestStatus::Queued.emoji())
+			.await;
+	}
+	if let Err(err) = channel_id
+		.create_reaction(http, message_id, status.emoji())
+		.await
+	{
+		warn!("Failed to react to song request: {err}");
+	}
+}
+
+/// Deletes a request from the music channel once the delay has passed
+pub fn clean_up_request(message: Message, delay: Duration) {
+	spawn(async move {
+		sleep(delay).await;
+		if let Err(err) = message.delete(&bot_context().http, None).await {
+			warn!("Failed to clean up song request: {err}");
+		}
+	});
+}
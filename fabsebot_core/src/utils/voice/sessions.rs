@@ -0,0 +1,375 @@
+use std::{
+	collections::VecDeque,
+	iter::once,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+	},
+	time::Duration,
+};
+
+use anyhow::{Result as AResult, bail};
+use lavalink_rs::{model::track::TrackData, player_context::TrackInQueue};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_value, to_value};
+use serenity::all::{ChannelId, Context as SerenityContext, GenericChannelId, GuildId, Message};
+use songbird::tracks::LoopState;
+use sqlx::{query, types::Json};
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::{
+	config::types::{BOT_CONTEXT, Data, GuildCache},
+	utils::{
+		helpers::{guild_cache, silent_message},
+		voice::{
+			ConnectionStatus, PayloadType, QueueData, add_voice_events, backend::source_input,
+			enqueue, join_handler, lavalink_connect, lavalink_enqueue,
+		},
+	},
+};
+
+const RESUMING_MSG: &str = "I'm back, resuming where we left off";
+/// Gives the shards a moment to receive their guilds before rejoining voice
+const RESUME_DELAY: Duration = Duration::from_secs(5);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Keeps voice disconnects caused by shutting down from forgetting sessions
+pub fn mark_shutdown() {
+	SHUTTING_DOWN.store(true, Ordering::Relaxed);
+}
+
+pub(super) fn is_shutting_down() -> bool {
+	SHUTTING_DOWN.load(Ordering::Relaxed)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "backend", content = "track", rename_all = "lowercase")]
+enum SavedTrack {
+	Lavalink(TrackData),
+	Songbird(QueueData),
+}
+
+impl SavedTrack {
+	fn queue_data(&self) -> Option<QueueData> {
+		match self {
+			Self::Lavalink(track) => from_value(track.user_data.clone()?).ok(),
+			Self::Songbird(queue_data) => Some(queue_data.clone()),
+		}
+	}
+}
+
+struct Session {
+	voice_channel_id: i64,
+	text_channel_id: i64,
+	position: Duration,
+	looping: bool,
+	tracks: Vec<SavedTrack>,
+}
+
+impl QueueData {
+	/// Custom files and text to voice only live in memory
+	fn is_resumable(&self) -> bool {
+		self.payload_type == PayloadType::Song && self.track_data.optional_data.is_some()
+	}
+}
+
+async fn capture_session(bot_data: &Data, guild_id: GuildId) -> AResult<Option<Session>> {
+	let Some(handler_lock) = bot_data.music_manager.get(guild_id) else {
+		return Ok(None);
+	};
+	let Some(voice_channel_id) = handler_lock.lock().await.current_channel() else {
+		return Ok(None);
+	};
+	let (position, looping, tracks) = match bot_data.lavalink_client.get_player_context(guild_id) {
+		Some(player) => {
+			let player_data = player.get_player().await?;
+			let Some(current) = player_data.track else {
+				return Ok(None);
+			};
+			let looping = bot_data.guilds.get(&guild_id).is_some_and(|guild_cache| {
+				guild_cache
+					.music_data
+					.lavalink_looping
+					.load(Ordering::Relaxed)
+			});
+			let queue = player.get_queue().get_queue().await?;
+			// The copy waiting to replay the looping track gets queued again on
+			// resume
+			let tracks = once(current)
+				.chain(
+					queue
+						.into_iter()
+						.skip(usize::from(looping))
+						.map(|track| track.track),
+				)
+				.map(SavedTrack::Lavalink)
+				.collect();
+			(
+				Duration::from_millis(player_data.state.position),
+				looping,
+				tracks,
+			)
+		}
+		None => {
+			let handles = handler_lock.lock().await.queue().current_queue();
+			let Some(current) = handles.first() else {
+				return Ok(None);
+			};
+			let current_data: Arc<QueueData> = current.data();
+			let info = current.get_info().await?;
+			let position = if current_data.is_resumable() {
+				info.position
+			} else {
+				Duration::ZERO
+			};
+			let tracks = handles
+				.iter()
+				.map(|handle| handle.data::<QueueData>())
+				.filter(|queue_data| queue_data.is_resumable())
+				.map(|queue_data| SavedTrack::Songbird(queue_data.as_ref().clone()))
+				.collect();
+			(position, info.loops != LoopState::Finite(0), tracks)
+		}
+	};
+	let Some(queue_data) = tracks.first().and_then(SavedTrack::queue_data) else {
+		return Ok(None);
+	};
+
+	Ok(Some(Session {
+		voice_channel_id: voice_channel_id.get().cast_signed(),
+		text_channel_id: queue_data.track_data.requested_channel.get().cast_signed(),
+		position,
+		looping,
+		tracks,
+	}))
+}
+
+async fn try_save_session(bot_data: &Data, guild_id: GuildId) -> AResult<()> {
+	let Some(session) = capture_session(bot_data, guild_id).await? else {
+		return delete_session(bot_data, guild_id).await;
+	};
+	query!(
+		r#"
+		INSERT INTO music_sessions (guild_id, voice_channel_id, text_channel_id, position_ms, looping, tracks)
+		VALUES ($1, $2, $3, $4, $5, $6)
+		ON CONFLICT (guild_id)
+		DO UPDATE SET voice_channel_id = $2,
+			text_channel_id = $3,
+			position_ms = $4,
+			looping = $5,
+			tracks = $6,
+			updated_at = NOW()
+		"#,
+		i64::from(guild_id),
+		session.voice_channel_id,
+		session.text_channel_id,
+		i64::try_from(session.position.as_millis()).unwrap_or_default(),
+		session.looping,
+		Json(&session.tracks) as _
+	)
+	.execute(&bot_data.db)
+	.await?;
+
+	Ok(())
+}
+
+/// Stores what is playing in the guild so it survives a restart, or forgets
+/// the session once nothing is left to resume
+pub async fn save_session(bot_data: &Data, guild_id: GuildId) {
+	if let Err(err) = try_save_session(bot_data, guild_id).await {
+		warn!("Failed to save music session: {err}");
+	}
+}
+
+pub async fn delete_session(bot_data: &Data, guild_id: GuildId) -> AResult<()> {
+	query!(
+		r#"
+		DELETE FROM music_sessions
+		WHERE guild_id = $1
+		"#,
+		i64::from(guild_id)
+	)
+	.execute(&bot_data.db)
+	.await?;
+
+	Ok(())
+}
+
+/// Saves every connected guild right before shutting down, so positions are
+/// as fresh as possible
+pub async fn save_sessions() {
+	let Some(ctx) = BOT_CONTEXT.get() else {
+		return;
+	};
+	let guild_ids: Vec<GuildId> = ctx
+		.data
+		.guilds
+		.iter()
+		.filter(|guild_cache| !guild_cache.music_data.is_disconnected())
+		.map(|guild_cache| *guild_cache.key())
+		.collect();
+	for guild_id in guild_ids {
+		save_session(&ctx.data, guild_id).await;
+	}
+}
+
+async fn resume_lavalink(
+	bot_data: &Data,
+	guild_id: GuildId,
+	voice_channel_id: ChannelId,
+	guild_cache: Arc<GuildCache>,
+	session: Session,
+	msg: &Message,
+) -> AResult<()> {
+	// Set before the first track starts, so its start queues the replay
+	guild_cache
+		.music_data
+		.lavalink_looping
+		.store(session.looping, Ordering::Relaxed);
+	let player = lavalink_connect(
+		bot_data,
+		guild_id,
+		voice_channel_id,
+		msg.channel_id,
+		guild_cache,
+	)
+	.await?;
+	let mut tracks = VecDeque::with_capacity(session.tracks.len());
+	for saved_track in session.tracks {
+		let SavedTrack::Lavalink(mut track) = saved_track else {
+			continue;
+		};
+		if let Some(user_data) = track.user_data.take() {
+			let queue_data: QueueData = from_value(user_data)?;
+			track.user_data = Some(to_value(queue_data.for_message(msg))?);
+		}
+		let mut track = TrackInQueue::from(track);
+		if tracks.is_empty() {
+			track.start_time = Some(session.position);
+		}
+		tracks.push_back(track);
+	}
+
+	lavalink_enqueue(&player, tracks).await
+}
+
+async fn resume_songbird(
+	ctx: &SerenityContext,
+	guild_id: GuildId,
+	voice_channel_id: ChannelId,
+	guild_cache: Arc<GuildCache>,
+	session: Session,
+	msg: &Message,
+) -> AResult<()> {
+	let bot_data: Arc<Data> = ctx.data();
+	let Some(handler_lock) =
+		join_handler(&bot_data.music_manager, guild_id, voice_channel_id).await
+	else {
+		bail!("Failed to rejoin voice channel {voice_channel_id}");
+	};
+	guild_cache
+		.music_data
+		.connected(ConnectionStatus::SongbirdConnected);
+	add_voice_events(ctx, guild_id, msg.channel_id, &handler_lock, guild_cache).await;
+
+	let mut first = true;
+	for saved_track in session.tracks {
+		let SavedTrack::Songbird(queue_data) = saved_track else {
+			continue;
+		};
+		let Some(input) = source_input(&queue_data) else {
+			continue;
+		};
+		enqueue(
+			queue_data.for_message(msg),
+			input,
+			&handler_lock,
+			i64::from(guild_id),
+			None,
+		)
+		.await?;
+		if first {
+			first = false;
+			let Some(current) = handler_lock.lock().await.queue().current() else {
+				continue;
+			};
+			if !session.position.is_zero() {
+				drop(current.seek(session.position));
+			}
+			if session.looping {
+				current.enable_loop()?;
+			}
+		}
+	}
+
+	Ok(())
+}
+
+async fn resume_session(ctx: &SerenityContext, guild_id: GuildId, session: Session) -> AResult<()> {
+	let bot_data: Arc<Data> = ctx.data();
+	let guild_cache = guild_cache(&bot_data, guild_id, None, ctx).await?;
+	if !guild_cache.music_data.is_disconnected() {
+		return Ok(());
+	}
+	let voice_channel_id = ChannelId::new(session.voice_channel_id.cast_unsigned());
+	let msg = GenericChannelId::new(session.text_channel_id.cast_unsigned())
+		.send_message(&ctx.http, silent_message(RESUMING_MSG))
+		.await?;
+
+	match session.tracks.first() {
+		Some(SavedTrack::Lavalink(_)) => {
+			resume_lavalink(
+				&bot_data,
+				guild_id,
+				voice_channel_id,
+				guild_cache,
+				session,
+				&msg,
+			)
+			.await
+		}
+		Some(SavedTrack::Songbird(_)) => {
+			resume_songbird(ctx, guild_id, voice_channel_id, guild_cache, session, &msg).await
+		}
+		None => Ok(()),
+	}
+}
+
+/// Rejoins and resumes every session that was active when the bot went down,
+/// unless the guild opted out
+pub async fn resume_sessions(ctx: &SerenityContext) -> AResult<()> {
+	sleep(RESUME_DELAY).await;
+	let bot_data: Arc<Data> = ctx.data();
+	let sessions = query!(
+		r#"
+		DELETE FROM music_sessions ms
+		RETURNING ms.guild_id, ms.voice_channel_id, ms.text_channel_id, ms.position_ms,
+			ms.looping, ms.tracks as "tracks: Json<Vec<SavedTrack>>",
+			(
+				SELECT COALESCE(gs.music_resume, TRUE)
+				FROM (SELECT ms.guild_id) g
+				LEFT JOIN guild_settings gs ON gs.guild_id = g.guild_id
+			) as "music_resume!"
+		"#
+	)
+	.fetch_all(&bot_data.db)
+	.await?;
+
+	for session in sessions.into_iter().filter(|session| session.music_resume) {
+		let guild_id = GuildId::new(session.guild_id.cast_unsigned());
+		let session = Session {
+			voice_channel_id: session.voice_channel_id,
+			text_channel_id: session.text_channel_id,
+			position: Duration::from_millis(session.position_ms.cast_unsigned()),
+			looping: session.looping,
+			tracks: session.tracks.0,
+		};
+		if let Err(err) = resume_session(ctx, guild_id, session).await {
+			warn!("Failed to resume music session: {err}");
+		}
+	}
+
+	Ok(())
+}
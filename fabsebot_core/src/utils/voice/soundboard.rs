@@ -0,0 +1,210 @@
+use std::{
+	borrow::Cow,
+	io::{Cursor, ErrorKind},
+	sync::{
+		Arc, LazyLock,
+		atomic::{AtomicBool, Ordering},
+	},
+	time::Duration,
+};
+
+use anyhow::{Result as AResult, bail};
+use bytes::Bytes;
+use fabsebot_db::music::fetch_sound_audio;
+use mini_moka::sync::Cache;
+use serenity::{
+	all::{ButtonStyle, Colour, CreateActionRow, CreateButton, CreateContainer, GuildId, UserId},
+	builder::CreateContainerComponent,
+};
+use songbird::{
+	driver::Bitrate,
+	input::{Input, cached::Compressed},
+	tracks::Track,
+};
+use symphonia::{
+	core::{
+		codecs::DecoderOptions,
+		errors::Error as SymphoniaError,
+		formats::FormatOptions,
+		io::{MediaSourceStream, MediaSourceStreamOptions},
+		meta::MetadataOptions,
+		probe::Hint,
+	},
+	default::{get_codecs, get_probe},
+};
+use tokio::task::spawn_blocking;
+
+use crate::{
+	config::types::Data,
+	utils::{
+		helpers::{separator, text_display},
+		voice::{PayloadType, QueueData, TrackPlayData, voice_listeners, volume_ratio},
+	},
+};
+
+pub const SOUND_MAX_DURATION: Duration = Duration::from_secs(10);
+pub const SOUND_MAX_MIB: u32 = 2;
+pub const SOUND_MAX_BYTES: u32 = SOUND_MAX_MIB * 1024 * 1024;
+/// Also the most buttons a single soundboard message can hold
+pub const SOUND_LIMIT: i64 = 25;
+pub const SOUND_NAME_LIMIT: usize = 32;
+pub const SOUND_BUTTON_PREFIX: &str = "sound-button:";
+const SOUNDS_PER_ROW: usize = 5;
+const SOUND_CACHE_CAPACITY: u64 = 200;
+
+/// Clips compressed once and replayed from memory afterwards
+static SOUND_CACHE: LazyLock<Cache<(GuildId, String), Compressed>> = LazyLock::new(|| {
+	Cache::builder()
+		.max_capacity(SOUND_CACHE_CAPACITY)
+		.time_to_idle(Duration::from_hours(1))
+		.build()
+});
+
+fn decoded_duration(bytes: Bytes) -> AResult<Duration> {
+	let source = MediaSourceStream::new(
+		Box::new(Cursor::new(bytes)),
+		MediaSourceStreamOptions::default(),
+	);
+	let probed = get_probe().format(
+		&Hint::new(),
+		source,
+		&FormatOptions::default(),
+		&MetadataOptions::default(),
+	)?;
+	let mut format = probed.format;
+	let Some(track) = format.default_track() else {
+		bail!("it has no audio track");
+	};
+	let track_id = track.id;
+	let Some(sample_rate) = track.codec_params.sample_rate.map(u64::from) else {
+		bail!("its sample rate is unknown");
+	};
+	let mut decoder = get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+	let max_frames = SOUND_MAX_DURATION.as_secs().saturating_mul(sample_rate);
+
+	let mut frames: u64 = 0;
+	loop {
+		let packet = match format.next_packet() {
+			Ok(packet) => packet,
+			Err(SymphoniaError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+			Err(err) => return Err(err.into()),
+		};
+		if packet.track_id() != track_id {
+			continue;
+		}
+		let decoded = decoder.decode(&packet)?;
+		frames = frames.saturating_add(u64::try_from(decoded.frames()).unwrap_or_default());
+		if frames > max_frames {
+			bail!("it's longer than {} seconds", SOUND_MAX_DURATION.as_secs());
+		}
+	}
+	if frames == 0 {
+		bail!("it doesn't contain any audio");
+	}
+
+	Ok(Duration::from_millis(
+		frames
+			.saturating_mul(1000)
+			.checked_div(sample_rate)
+			.unwrap_or_default(),
+	))
+}
+
+/// Decodes the whole clip to make sure it plays and is short enough,
+/// returning its length
+pub async fn validate_sound(bytes: Bytes) -> AResult<Duration> {
+	spawn_blocking(move || decoded_duration(bytes)).await?
+}
+
+/// Drops the cached audio of a clip that got replaced or deleted
+pub fn forget_sound(guild_id: GuildId, name: &str) {
+	SOUND_CACHE.invalidate(&(guild_id, name.to_owned()));
+}
+
+async fn sound_audio(
+	bot_data: &Data,
+	guild_id: GuildId,
+	name: &str,
+) -> AResult<Option<Compressed>> {
+	let key = (guild_id, name.to_owned());
+	if let Some(compressed) = SOUND_CACHE.get(&key) {
+		return Ok(Some(compressed));
+	}
+	let Some(audio) = fetch_sound_audio(i64::from(guild_id), name, &bot_data.db).await? else {
+		return Ok(None);
+	};
+	let compressed = Compressed::new(Input::from(Bytes::from(audio)), Bitrate::Max).await?;
+	SOUND_CACHE.insert(key, compressed.clone());
+	Ok(Some(compressed))
+}
+
+/// Plays a clip on top of whatever is playing, without touching the queue;
+/// returns why it couldn't if it couldn't. Clips live in the database where
+/// Lavalink can't load them from, so only songbird connections mix them in
+pub async fn play_sound(
+	bot_data: &Data,
+	guild_id: GuildId,
+	name: &str,
+	user_id: UserId,
+) -> AResult<Option<&'static str>> {
+	let Some(guild_cache) = bot_data.guilds.get(&guild_id) else {
+		return Ok(Some("I'm not in a voice channel here"));
+	};
+	if guild_cache.music_data.is_lavalink_connected() {
+		return Ok(Some(
+			"Sounds can't be mixed into Lavalink playback, use join_voice_old instead",
+		));
+	}
+	let Some(handler_lock) = bot_data
+		.music_manager
+		.get(guild_id)
+		.filter(|_| guild_cache.music_data.is_songbird_connected())
+	else {
+		return Ok(Some("I'm not in a voice channel here"));
+	};
+	if !voice_listeners(guild_id).contains(&user_id) {
+		return Ok(Some("Join my voice channel to play sounds"));
+	}
+	let Some(compressed) = sound_audio(bot_data, guild_id, name).await? else {
+		return Ok(Some("No sound with that name exists here"));
+	};
+
+	let queue_data = QueueData {
+		track_data: TrackPlayData {
+			requested_by: user_id,
+			..Default::default()
+		},
+		first_play: AtomicBool::new(false),
+		first_error: AtomicBool::new(false),
+		payload_type: PayloadType::Sound,
+	};
+	let volume = guild_cache.music_data.volume.load(Ordering::Relaxed);
+	handler_lock.lock().await.play(
+		Track::new_with_data(Input::from(compressed.new_handle()), Arc::new(queue_data))
+			.volume(volume_ratio(volume)),
+	);
+
+	Ok(None)
+}
+
+/// Container with a button per clip, pressing one plays it
+#[must_use]
+pub fn soundboard_container<'a>(names: &[String]) -> CreateContainer<'a> {
+	let mut container = CreateContainer::new(vec![text_display("# Soundboard")])
+		.add_component(separator())
+		.accent_colour(Colour::PURPLE);
+	for row in names.chunks(SOUNDS_PER_ROW) {
+		let buttons: Vec<CreateButton> = row
+			.iter()
+			.map(|name| {
+				CreateButton::new(format!("{SOUND_BUTTON_PREFIX}{name}"))
+					.label(name.clone())
+					.style(ButtonStyle::Secondary)
+			})
+			.collect();
+		container = container.add_component(CreateContainerComponent::ActionRow(
+			CreateActionRow::Buttons(Cow::Owned(buttons)),
+		));
+	}
+	container
+}
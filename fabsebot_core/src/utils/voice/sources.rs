@@ -0,0 +1,435 @@
+use anyhow::{Result as AResult, bail};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{config::types::HTTP_CLIENT, utils::helpers::fetch_and_parse};
+
+pub(super) const AUDIO_EXTENSIONS: [&str; 8] = [
+	".mp3", ".flac", ".wav", ".ogg", ".opus", ".m4a", ".aac", ".webm",
+];
+
+/// Query parameters that only record where a link was shared from
+const TRACKING_PARAMS: [&str; 7] = ["si", "feature", "pp", "fbclid", "gclid", "igshid", "ref"];
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackSource {
+	#[default]
+	YouTube,
+	SoundCloud,
+	Bandcamp,
+	Direct,
+	Spotify,
+	AppleMusic,
+	Deezer,
+	/// File from the music directory on disk
+	Local,
+}
+
+impl TrackSource {
+	/// Detects the source of a link, `None` means the input is a search query
+	#[must_use]
+	pub fn detect(input: &str) -> Option<Self> {
+		let url = Url::parse(input.trim()).ok()?;
+		let domain = url.domain()?.trim_start_matches("www.");
+		let source = match domain {
+			"youtube.com" | "m.youtube.com" | "music.youtube.com" | "youtu.be" => Self::YouTube,
+			"soundcloud.com" | "m.soundcloud.com" | "on.soundcloud.com" => Self::SoundCloud,
+			"open.spotify.com" => Self::Spotify,
+			"music.apple.com" => Self::AppleMusic,
+			"deezer.com" => Self::Deezer,
+			_ if domain.ends_with(".bandcamp.com") => Self::Bandcamp,
+			_ if AUDIO_EXTENSIONS
+				.iter()
+				.any(|extension| url.path().to_lowercase().ends_with(extension)) =>
+			{
+				Self::Direct
+			}
+			_ => return None,
+		};
+		Some(source)
+	}
+
+	/// Maps the `sourceName` Lavalink reports for a loaded track
+	#[must_use]
+	pub fn from_lavalink(source_name: &str) -> Self {
+		match source_name {
+			"soundcloud" => Self::SoundCloud,
+			"bandcamp" => Self::Bandcamp,
+			"http" => Self::Direct,
+			_ => Self::YouTube,
+		}
+	}
+
+	#[must_use]
+	pub const fn name(self) -> &'static str {
+		match self {
+			Self::YouTube => "YouTube",
+			Self::SoundCloud => "SoundCloud",
+			Self::Bandcamp => "Bandcamp",
+			Self::Direct => "Direct link",
+			Self::Spotify => "Spotify",
+			Self::AppleMusic => "Apple Music",
+			Self::Deezer => "Deezer",
+			Self::Local => "Local library",
+		}
+	}
+
+	/// Value stored in `tracks.source`
+	#[must_use]
+	pub const fn db_name(self) -> &'static str {
+		match self {
+			Self::YouTube => "youtube",
+			Self::SoundCloud => "soundcloud",
+			Self::Bandcamp => "bandcamp",
+			Self::Direct => "direct",
+			Self::Spotify => "spotify",
+			Self::AppleMusic => "applemusic",
+			Self::Deezer => "deezer",
+			Self::Local => "local",
+		}
+	}
+
+	/// Streaming services without playable audio, which get searched on
+	/// YouTube instead
+	#[must_use]
+	pub const fn is_metadata_only(self) -> bool {
+		matches!(self, Self::Spotify | Self::AppleMusic | Self::Deezer)
+	}
+}
+
+/// Discord attachment links, which stop working once their signature expires
+#[must_use]
+pub fn is_attachment_url(input: &str) -> bool {
+	Url::parse(input.trim()).ok().is_some_and(|url| {
+		matches!(
+			url.domain(),
+			Some("cdn.discordapp.com" | "media.discordapp.net")
+		)
+	})
+}
+
+fn strip_tracking(url: &mut Url) {
+	// Filtered as raw text, as re-encoding the rest could break signed links
+	let query = url.query().map(|query| {
+		query
+			.split('&')
+			.filter(|pair| {
+				let key = pair.split_once('=').map_or(*pair, |(key, _)| key);
+				!key.is_empty() && !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key)
+			})
+			.collect::<Vec<_>>()
+			.join("&")
+	});
+	url.set_query(query.as_deref().filter(|query| !query.is_empty()));
+	url.set_fragment(None);
+}
+
+fn youtube_id(url: &Url) -> Option<String> {
+	let mut segments = url.path_segments()?;
+	let id = if url.domain()? == "youtu.be" {
+		segments.next().map(ToOwned::to_owned)
+	} else if let Some((_, id)) = url.query_pairs().find(|(key, _)| key == "v") {
+		Some(id.into_owned())
+	} else {
+		match segments.next()? {
+			"shorts" | "embed" | "live" | "v" => segments.next().map(ToOwned::to_owned),
+			_ => None,
+		}
+	};
+	id.filter(|id| {
+		!id.is_empty()
+			&& id
+				.bytes()
+				.all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+	})
+}
+
+/// Path segments that pick a storefront language rather than the track
+fn is_locale_segment(segment: &str) -> bool {
+	segment.starts_with("intl-")
+		|| (segment.len() == 2 && segment.bytes().all(|b| b.is_ascii_lowercase()))
+}
+
+/// Rewrites a link into the one form its source knows the track by, so a
+/// song maps to the same track no matter how it was shared
+#[must_use]
+pub fn canonical_url(input: &str) -> String {
+	let input = input.trim();
+	let Ok(mut url) = Url::parse(input) else {
+		return input.to_owned();
+	};
+	let Some(source) = TrackSource::detect(input) else {
+		// Streams and pages Lavalink plays over plain http
+		if matches!(url.scheme(), "http" | "https") {
+			strip_tracking(&mut url);
+			return url.into();
+		}
+		return input.to_owned();
+	};
+	match source {
+		TrackSource::YouTube => {
+			if let Some(id) = youtube_id(&url) {
+				return format!("https://www.youtube.com/watch?v={id}");
+			}
+			strip_tracking(&mut url);
+		}
+		TrackSource::SoundCloud => {
+			url.set_query(None);
+			url.set_fragment(None);
+			// Short links only resolve on their own domain
+			if url.domain() != Some("on.soundcloud.com") {
+				return format!("https://soundcloud.com{}", url.path().trim_end_matches('/'));
+			}
+		}
+		TrackSource::Spotify | TrackSource::Deezer => {
+			let path: Vec<&str> = url
+				.path_segments()
+				.into_iter()
+				.flatten()
+				.filter(|segment| !segment.is_empty() && !is_locale_segment(segment))
+				.collect();
+			let host = if source == TrackSource::Spotify {
+				"open.spotify.com"
+			} else {
+				"www.deezer.com"
+			};
+			return format!("https://{host}/{}", path.join("/"));
+		}
+		TrackSource::AppleMusic => {
+			let song = url
+				.query_pairs()
+				.find(|(key, _)| key == "i")
+				.map(|(_, value)| value.into_owned());
+			url.set_query(None);
+			url.set_fragment(None);
+			if let Some(song) = song {
+				url.query_pairs_mut().append_pair("i", &song);
+			}
+		}
+		TrackSource::Bandcamp => {
+			url.set_query(None);
+			url.set_fragment(None);
+		}
+		TrackSource::Direct | TrackSource::Local => strip_tracking(&mut url),
+	}
+	url.into()
+}
+
+#[derive(Clone)]
+pub struct StreamingMetadata {
+	pub title: String,
+	pub artist: String,
+	pub thumbnail_url: Option<String>,
+}
+
+impl StreamingMetadata {
+	#[must_use]
+	pub fn search_query(&self) -> String {
+		format!("{} - {}", self.artist, self.title)
+	}
+}
+
+fn meta_content<'a>(html: &'a str, key: &str) -> Option<&'a str> {
+	let marker = format!("=\"{key}\" content=\"");
+	let start = html.find(&marker)?.saturating_add(marker.len());
+	let rest = html.get(start..)?;
+	let end = rest.find('"')?;
+	rest.get(..end)
+}
+
+fn unescape_html(text: &str) -> String {
+	text.replace("&amp;", "&")
+		.replace("&quot;", "\"")
+		.replace("&#x27;", "'")
+		.replace("&#39;", "'")
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+}
+
+async fn spotify_metadata(url: &str) -> AResult<StreamingMetadata> {
+	let html = HTTP_CLIENT
+		.get(url)
+		.send()
+		.await?
+		.error_for_status()?
+		.text()
+		.await?;
+	let (Some(title), Some(artist)) = (
+		meta_content(&html, "og:title"),
+		meta_content(&html, "music:musician_description"),
+	) else {
+		bail!("Spotify page has no track metadata: {url}");
+	};
+	Ok(StreamingMetadata {
+		title: unescape_html(title),
+		artist: unescape_html(artist),
+		thumbnail_url: meta_content(&html, "og:image").map(unescape_html),
+	})
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ITunesTrack {
+	track_name: String,
+	artist_name: String,
+	artwork_url100: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ITunesLookup {
+	results: Vec<ITunesTrack>,
+}
+
+async fn apple_music_metadata(url: &str) -> AResult<StreamingMetadata> {
+	let parsed = Url::parse(url)?;
+	let track_id = parsed
+		.query_pairs()
+		.find(|(key, _)| key == "i")
+		.map(|(_, value)| value.into_owned())
+		.or_else(|| {
+			parsed
+				.path_segments()?
+				.next_back()
+				.filter(|segment| segment.bytes().all(|b| b.is_ascii_digit()))
+				.map(ToOwned::to_owned)
+		});
+	let Some(track_id) = track_id else {
+		bail!("Apple Music link doesn't point to a song: {url}");
+	};
+	let lookup: ITunesLookup = fetch_and_parse(
+		HTTP_CLIENT
+			.get("https://itunes.apple.com/lookup")
+			.query(&[("id", track_id.as_str()), ("entity", "song")])
+			.send(),
+	)
+	.await?;
+	let Some(track) = lookup.results.into_iter().next() else {
+		bail!("No Apple Music song found for: {url}");
+	};
+	Ok(StreamingMetadata {
+		title: track.track_name,
+		artist: track.artist_name,
+		thumbnail_url: track
+			.artwork_url100
+			.map(|artwork| artwork.replace("100x100", "600x600")),
+	})
+}
+
+#[derive(Deserialize)]
+struct DeezerArtist {
+	name: String,
+}
+
+#[derive(Deserialize)]
+struct DeezerAlbum {
+	cover_xl: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeezerTrack {
+	title: String,
+	artist: DeezerArtist,
+	album: Option<DeezerAlbum>,
+}
+
+async fn deezer_metadata(url: &str) -> AResult<StreamingMetadata> {
+	let parsed = Url::parse(url)?;
+	let Some(track_id) = parsed.path_segments().and_then(|segments| {
+		let mut segments = segments.skip_while(|segment| *segment != "track");
+		segments.next()?;
+		segments.next()
+	}) else {
+		bail!("Deezer link doesn't point to a track: {url}");
+	};
+	let track: DeezerTrack = fetch_and_parse(
+		HTTP_CLIENT
+			.get(format!("https://api.deezer.com/track/{track_id}"))
+			.send(),
+	)
+	.await?;
+	Ok(StreamingMetadata {
+		title: track.title,
+		artist: track.artist.name,
+		thumbnail_url: track.album.and_then(|album| album.cover_xl),
+	})
+}
+
+/// Looks up title and artist of a streaming service link
+pub async fn streaming_metadata(source: TrackSource, url: &str) -> AResult<StreamingMetadata> {
+	match source {
+		TrackSource::Spotify => spotify_metadata(url).await,
+		TrackSource::AppleMusic => apple_music_metadata(url).await,
+		TrackSource::Deezer => deezer_metadata(url).await,
+		_ => bail!("{} links carry their own audio", source.name()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use url::Url;
+
+	use super::{canonical_url, youtube_id};
+
+	const VIDEO: &str = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+
+	#[test]
+	fn youtube_links_share_one_form() {
+		for link in [
+			"https://youtu.be/dQw4w9WgXcQ?si=Xk3_a8bQ",
+			"https://www.youtube.com/shorts/dQw4w9WgXcQ?feature=share",
+			"https://m.youtube.com/watch?v=dQw4w9WgXcQ&si=Xk3_a8bQ&feature=youtu.be",
+			"https://music.youtube.com/watch?feature=shared&v=dQw4w9WgXcQ",
+			" https://www.youtube.com/embed/dQw4w9WgXcQ ",
+		] {
+			assert_eq!(canonical_url(link), VIDEO, "{link}");
+		}
+	}
+
+	#[test]
+	fn youtube_ids_must_look_like_ids() {
+		let id = |link: &str| youtube_id(&Url::parse(link).unwrap());
+		assert_eq!(
+			id("https://youtu.be/dQw4w9WgXcQ").as_deref(),
+			Some("dQw4w9WgXcQ")
+		);
+		assert_eq!(id("https://www.youtube.com/watch?v=bad%20id"), None);
+		assert_eq!(
+			id("https://www.youtube.com/channel/UCuAXFkgsw1L7xaCfnd5JJOw"),
+			None
+		);
+		assert_eq!(id("https://youtu.be/"), None);
+	}
+
+	#[test]
+	fn soundcloud_links_drop_mobile_domain_and_trailing_slash() {
+		assert_eq!(
+			canonical_url("https://m.soundcloud.com/artist/song/?si=abc&utm_source=clipboard"),
+			"https://soundcloud.com/artist/song"
+		);
+		assert_eq!(
+			canonical_url("https://soundcloud.com/artist/song#t=1:00"),
+			"https://soundcloud.com/artist/song"
+		);
+	}
+
+	#[test]
+	fn tracking_keys_are_stripped_whole() {
+		assert_eq!(
+			canonical_url(
+				"https://example.com/song.mp3?utm_source=x&ref=abc&reference=keep&utm_medium=y"
+			),
+			"https://example.com/song.mp3?reference=keep"
+		);
+		assert_eq!(
+			canonical_url("https://example.com/song.mp3?fbclid=abc&ref"),
+			"https://example.com/song.mp3"
+		);
+	}
+
+	#[test]
+	fn signed_direct_links_pass_through() {
+		let link = "https://cdn.example.com/audio/track.mp3?Expires=1700000000&Signature=abc%2Bdef~ghi&Key-Pair-Id=K2";
+		assert_eq!(canonical_url(link), link);
+	}
+}
@@ -28,6 +28,84 @@ pub async fn set_music_channel(
 	.await
 }
 
+pub struct MusicSettings {
+	pub music_dj_role: Option<i64>,
+	pub music_requester_skip: bool,
+	pub music_vote_skip_percent: i16,
+	pub music_idle_timeout: i16,
+	pub music_seek_step: i16,
+	pub music_resume: bool,
+	/// Loudness in LUFS that uploaded and local audio gets normalized to
+	pub music_target_lufs: i16,
+	/// Seconds until requests in the music channel get deleted, 0 keeps them
+	pub music_request_cleanup: i16,
+}
+
+impl Default for MusicSettings {
+	fn default() -> Self {
+		Self {
+			music_dj_role: None,
+			music_requester_skip: true,
+			music_vote_skip_percent: 50,
+			music_idle_timeout: 5,
+			music_seek_step: 10,
+			music_resume: true,
+			music_target_lufs: -14,
+			music_request_cleanup: 0,
+		}
+	}
+}
+
+pub async fn fetch_music_settings(
+	guild_id: i64,
+	conn: &Pool<Postgres>,
+) -> Result<Option<MusicSettings>, Error> {
+	query_as!(
+		MusicSettings,
+		r#"
+		SELECT music_dj_role, music_requester_skip, music_vote_skip_percent, music_idle_timeout,
+			music_seek_step, music_resume, music_target_lufs, music_request_cleanup
+		FROM guild_settings
+		WHERE guild_id = $1
+		"#,
+		guild_id
+	)
+	.fetch_optional(conn)
+	.await
+}
+
+pub async fn update_music_settings(
+	guild_id: i64,
+	settings: &MusicSettings,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		UPDATE guild_settings
+		SET music_dj_role = $2,
+			music_requester_skip = $3,
+			music_vote_skip_percent = $4,
+			music_idle_timeout = $5,
+			music_seek_step = $6,
+			music_resume = $7,
+			music_target_lufs = $8,
+			music_request_cleanup = $9
+		WHERE guild_id = $1
+		"#,
+		guild_id,
+		settings.music_dj_role,
+		settings.music_requester_skip,
+		settings.music_vote_skip_percent,
+		settings.music_idle_timeout,
+		settings.music_seek_step,
+		settings.music_resume,
+		settings.music_target_lufs,
+		settings.music_request_cleanup
+	)
+	.execute(conn)
+	.await
+}
+
 pub async fn set_spoiler_channel(
 	guild_id: i64,
 	channel_id: i64,
@@ -76,12 +154,19 @@ pub async fn reset_guild(
         ai_chat_channel = NULL,
         global_chat_channel = NULL,
         global_chat = FALSE,
-        global_call = FALSE,
         music_channel = NULL,
         waifu_channel = NULL,
         waifu_rate = NULL,
         last_waifu = NULL,
-        chatbot_role = NULL
+        chatbot_role = NULL,
+        music_dj_role = NULL,
+        music_requester_skip = TRUE,
+        music_vote_skip_percent = 50,
+        music_idle_timeout = 5,
+        music_seek_step = 10,
+        music_resume = TRUE,
+        music_target_lufs = -14,
+        music_request_cleanup = 0
     	WHERE guild_id = $1
     	"#,
 		guild_id
@@ -190,3 +275,241 @@ pub async fn insert_guild_settings(
 	.execute(conn)
 	.await
 }
+
+/// Quote theme a guild saved, colours are stored as `0xRRGGBB`
+pub struct GuildQuoteTheme {
+	pub name: String,
+	/// One of `solid`, `linear`, `radial` or `image`
+	pub style: String,
+	pub primary_colour: i32,
+	pub secondary_colour: Option<i32>,
+	pub angle: i16,
+	pub background: Option<Vec<u8>>,
+	pub blur: f32,
+	pub dim: i16,
+	pub text_colour: i32,
+	pub outline_colour: Option<i32>,
+}
+
+pub async fn insert_quote_theme(
+	guild_id: i64,
+	theme: &GuildQuoteTheme,
+	created_by: i64,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		INSERT INTO quote_themes (
+			guild_id, name, style, primary_colour, secondary_colour, angle, background, blur,
+			dim, text_colour, outline_colour, created_by
+		)
+		VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+		ON CONFLICT (guild_id, name)
+		DO UPDATE SET style = $3, primary_colour = $4, secondary_colour = $5, angle = $6,
+			background = $7, blur = $8, dim = $9, text_colour = $10, outline_colour = $11,
+			created_by = $12, created_at = NOW()
+		"#,
+		guild_id,
+		theme.name,
+		theme.style,
+		theme.primary_colour,
+		theme.secondary_colour,
+		theme.angle,
+		theme.background,
+		theme.blur,
+		theme.dim,
+		theme.text_colour,
+		theme.outline_colour,
+		created_by
+	)
+	.execute(conn)
+	.await
+}
+
+pub async fn fetch_quote_theme(
+	guild_id: i64,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<Option<GuildQuoteTheme>, Error> {
+	query_as!(
+		GuildQuoteTheme,
+		r#"
+		SELECT name, style, primary_colour, secondary_colour, angle, background, blur, dim,
+			text_colour, outline_colour
+		FROM quote_themes
+		WHERE guild_id = $1
+			AND name = $2
+		"#,
+		guild_id,
+		name
+	)
+	.fetch_optional(conn)
+	.await
+}
+
+pub async fn fetch_quote_theme_names(
+	guild_id: i64,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<String>, Error> {
+	query_scalar!(
+		r#"
+		SELECT name
+		FROM quote_themes
+		WHERE guild_id = $1
+		ORDER BY name
+		"#,
+		guild_id
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn quote_theme_exists(
+	guild_id: i64,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<bool, Error> {
+	query_scalar!(
+		r#"
+		SELECT EXISTS(
+			SELECT 1 FROM quote_themes WHERE guild_id = $1 AND name = $2
+		) AS "exists!"
+		"#,
+		guild_id,
+		name
+	)
+	.fetch_one(conn)
+	.await
+}
+
+pub async fn quote_theme_count(guild_id: i64, conn: &Pool<Postgres>) -> Result<i64, Error> {
+	query_scalar!(
+		r#"
+		SELECT COUNT(*) AS "count!"
+		FROM quote_themes
+		WHERE guild_id = $1
+		"#,
+		guild_id
+	)
+	.fetch_one(conn)
+	.await
+}
+
+pub async fn delete_quote_theme(
+	guild_id: i64,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		DELETE FROM quote_themes
+		WHERE guild_id = $1
+			AND name = $2
+		"#,
+		guild_id,
+		name
+	)
+	.execute(conn)
+	.await
+}
+
+pub async fn insert_font(
+	guild_id: i64,
+	name: &str,
+	font: &[u8],
+	uploaded_by: i64,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		INSERT INTO guild_fonts (guild_id, name, font, uploaded_by)
+		VALUES ($1, $2, $3, $4)
+		ON CONFLICT (guild_id, name)
+		DO UPDATE SET font = $3, uploaded_by = $4, created_at = NOW()
+		"#,
+		guild_id,
+		name,
+		font,
+		uploaded_by
+	)
+	.execute(conn)
+	.await
+}
+
+pub async fn fetch_font(
+	guild_id: i64,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<Option<Vec<u8>>, Error> {
+	query_scalar!(
+		r#"
+		SELECT font
+		FROM guild_fonts
+		WHERE guild_id = $1
+			AND name = $2
+		"#,
+		guild_id,
+		name
+	)
+	.fetch_optional(conn)
+	.await
+}
+
+pub async fn fetch_font_names(guild_id: i64, conn: &Pool<Postgres>) -> Result<Vec<String>, Error> {
+	query_scalar!(
+		r#"
+		SELECT name
+		FROM guild_fonts
+		WHERE guild_id = $1
+		ORDER BY name
+		"#,
+		guild_id
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn font_exists(guild_id: i64, name: &str, conn: &Pool<Postgres>) -> Result<bool, Error> {
+	query_scalar!(
+		r#"
+		SELECT EXISTS(
+			SELECT 1 FROM guild_fonts WHERE guild_id = $1 AND name = $2
+		) AS "exists!"
+		"#,
+		guild_id,
+		name
+	)
+	.fetch_one(conn)
+	.await
+}
+
+pub async fn font_count(guild_id: i64, conn: &Pool<Postgres>) -> Result<i64, Error> {
+	query_scalar!(
+		r#"
+		SELECT COUNT(*) AS "count!"
+		FROM guild_fonts
+		WHERE guild_id = $1
+		"#,
+		guild_id
+	)
+	.fetch_one(conn)
+	.await
+}
+
+pub async fn delete_font(
+	guild_id: i64,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		DELETE FROM guild_fonts
+		WHERE guild_id = $1
+			AND name = $2
+		"#,
+		guild_id,
+		name
+	)
+	.execute(conn)
+	.await
+}
@@ -0,0 +1,577 @@
+use sqlx::{
+	Error, Pool, Postgres,
+	postgres::PgQueryResult,
+	query, query_as, query_scalar,
+	types::{Uuid, time::OffsetDateTime},
+};
+
+#[derive(Clone, Copy)]
+pub enum PlaylistOwner {
+	User(i64),
+	Guild(i64),
+}
+
+impl PlaylistOwner {
+	const fn ids(self) -> (Option<i64>, Option<i64>) {
+		match self {
+			Self::User(user_id) => (Some(user_id), None),
+			Self::Guild(guild_id) => (None, Some(guild_id)),
+		}
+	}
+}
+
+pub struct Playlist {
+	pub playlist_id: i64,
+	pub name: String,
+	pub track_count: i64,
+}
+
+pub struct PlaylistTrack {
+	pub track_uuid: Uuid,
+	pub title: String,
+	pub artist: String,
+	pub source_url: String,
+	pub duration_sec: i64,
+}
+
+pub async fn create_playlist(
+	owner: PlaylistOwner,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<Option<i64>, Error> {
+	let (user_id, guild_id) = owner.ids();
+	query_scalar!(
+		r#"
+		WITH ensured_user AS (
+			INSERT INTO users (user_id)
+			SELECT $1::BIGINT WHERE $1::BIGINT IS NOT NULL
+			ON CONFLICT (user_id) DO NOTHING
+		), ensured_guild AS (
+			INSERT INTO guilds (guild_id)
+			SELECT $2::BIGINT WHERE $2::BIGINT IS NOT NULL
+			ON CONFLICT (guild_id) DO NOTHING
+		)
+		INSERT INTO playlists (owner_user_id, owner_guild_id, name)
+		VALUES ($1, $2, $3)
+		ON CONFLICT DO NOTHING
+		RETURNING playlist_id
+		"#,
+		user_id,
+		guild_id,
+		name
+	)
+	.fetch_optional(conn)
+	.await
+}
+
+pub async fn fetch_playlist_id(
+	owner: PlaylistOwner,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<Option<i64>, Error> {
+	let (user_id, guild_id) = owner.ids();
+	query_scalar!(
+		r#"
+		SELECT playlist_id
+		FROM playlists
+		WHERE owner_user_id IS NOT DISTINCT FROM $1
+			AND owner_guild_id IS NOT DISTINCT FROM $2
+			AND name = $3
+		"#,
+		user_id,
+		guild_id,
+		name
+	)
+	.fetch_optional(conn)
+	.await
+}
+
+/// Playlists whose name starts with the prefix, all of them without a limit
+pub async fn fetch_playlists(
+	owner: PlaylistOwner,
+	prefix: &str,
+	limit: Option<i64>,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<Playlist>, Error> {
+	let (user_id, guild_id) = owner.ids();
+	query_as!(
+		Playlist,
+		r#"
+		SELECT p.playlist_id, p.name, COUNT(pt.track_uuid) AS "track_count!"
+		FROM playlists p
+		LEFT JOIN playlist_tracks pt ON pt.playlist_id = p.playlist_id
+		WHERE p.owner_user_id IS NOT DISTINCT FROM $1
+			AND p.owner_guild_id IS NOT DISTINCT FROM $2
+			AND p.name ILIKE $3 || '%'
+		GROUP BY p.playlist_id
+		ORDER BY p.name
+		LIMIT $4
+		"#,
+		user_id,
+		guild_id,
+		prefix,
+		limit
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn delete_playlist(
+	owner: PlaylistOwner,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	let (user_id, guild_id) = owner.ids();
+	query!(
+		r#"
+		DELETE FROM playlists
+		WHERE owner_user_id IS NOT DISTINCT FROM $1
+			AND owner_guild_id IS NOT DISTINCT FROM $2
+			AND name = $3
+		"#,
+		user_id,
+		guild_id,
+		name
+	)
+	.execute(conn)
+	.await
+}
+
+/// Appends the tracks while the playlist has room for them, locking it so
+/// concurrent additions can't claim the same positions
+pub async fn add_playlist_tracks(
+	playlist_id: i64,
+	track_uuids: &[Uuid],
+	track_limit: i64,
+	conn: &Pool<Postgres>,
+) -> Result<u64, Error> {
+	let mut tx = conn.begin().await?;
+	query!(
+		r#"
+		SELECT playlist_id
+		FROM playlists
+		WHERE playlist_id = $1
+		FOR UPDATE
+		"#,
+		playlist_id
+	)
+	.fetch_optional(&mut *tx)
+	.await?;
+	let added = query!(
+		r#"
+		INSERT INTO playlist_tracks (playlist_id, position, track_uuid)
+		SELECT $1,
+			(COALESCE(
+				(SELECT MAX(position) FROM playlist_tracks WHERE playlist_id = $1),
+				-1
+			) + t.ord)::INT,
+			t.track_uuid
+		FROM UNNEST($2::uuid[]) WITH ORDINALITY AS t(track_uuid, ord)
+		WHERE t.ord <= $3 - (SELECT COUNT(*) FROM playlist_tracks WHERE playlist_id = $1)
+		"#,
+		playlist_id,
+		track_uuids,
+		track_limit
+	)
+	.execute(&mut *tx)
+	.await?
+	.rows_affected();
+	tx.commit().await?;
+	Ok(added)
+}
+
+pub async fn fetch_playlist_tracks(
+	playlist_id: i64,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<PlaylistTrack>, Error> {
+	query_as!(
+		PlaylistTrack,
+		r#"
+		SELECT t.track_uuid, t.title, t.artist, t.source_url, t.duration_sec
+		FROM playlist_tracks pt
+		JOIN tracks t ON t.track_uuid = pt.track_uuid
+		WHERE pt.playlist_id = $1
+		ORDER BY pt.position
+		"#,
+		playlist_id
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub struct PlaySummary {
+	pub plays: i64,
+	pub listening_secs: i64,
+}
+
+pub struct TrackStat {
+	pub title: String,
+	pub artist: String,
+	pub plays: i64,
+}
+
+pub struct ArtistStat {
+	pub artist: String,
+	pub plays: i64,
+}
+
+pub struct RequesterStat {
+	pub requested_by: i64,
+	pub plays: i64,
+}
+
+pub struct HourStat {
+	pub hour: i32,
+	pub plays: i64,
+}
+
+pub async fn fetch_play_summary(
+	guild_id: i64,
+	user_id: Option<i64>,
+	since: Option<OffsetDateTime>,
+	conn: &Pool<Postgres>,
+) -> Result<PlaySummary, Error> {
+	query_as!(
+		PlaySummary,
+		r#"
+		SELECT
+			COUNT(*) AS "plays!",
+			COALESCE(SUM(t.duration_sec), 0)::BIGINT AS "listening_secs!"
+		FROM song_plays sp
+		JOIN tracks t ON t.track_uuid = sp.track_uuid
+		WHERE sp.guild_id = $1
+			AND ($2::BIGINT IS NULL OR sp.requested_by = $2)
+			AND ($3::TIMESTAMPTZ IS NULL OR sp.played_at >= $3)
+		"#,
+		guild_id,
+		user_id,
+		since
+	)
+	.fetch_one(conn)
+	.await
+}
+
+pub async fn fetch_top_tracks(
+	guild_id: i64,
+	user_id: Option<i64>,
+	since: Option<OffsetDateTime>,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<TrackStat>, Error> {
+	query_as!(
+		TrackStat,
+		r#"
+		SELECT t.title, t.artist, COUNT(*) AS "plays!"
+		FROM song_plays sp
+		JOIN tracks t ON t.track_uuid = sp.track_uuid
+		WHERE sp.guild_id = $1
+			AND ($2::BIGINT IS NULL OR sp.requested_by = $2)
+			AND ($3::TIMESTAMPTZ IS NULL OR sp.played_at >= $3)
+		GROUP BY t.track_uuid
+		ORDER BY COUNT(*) DESC, t.title
+		LIMIT 10
+		"#,
+		guild_id,
+		user_id,
+		since
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn fetch_top_artists(
+	guild_id: i64,
+	user_id: Option<i64>,
+	since: Option<OffsetDateTime>,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<ArtistStat>, Error> {
+	query_as!(
+		ArtistStat,
+		r#"
+		SELECT t.artist, COUNT(*) AS "plays!"
+		FROM song_plays sp
+		JOIN tracks t ON t.track_uuid = sp.track_uuid
+		WHERE sp.guild_id = $1
+			AND ($2::BIGINT IS NULL OR sp.requested_by = $2)
+			AND ($3::TIMESTAMPTZ IS NULL OR sp.played_at >= $3)
+		GROUP BY t.artist
+		ORDER BY COUNT(*) DESC, t.artist
+		LIMIT 10
+		"#,
+		guild_id,
+		user_id,
+		since
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn fetch_top_requesters(
+	guild_id: i64,
+	since: Option<OffsetDateTime>,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<RequesterStat>, Error> {
+	query_as!(
+		RequesterStat,
+		r#"
+		SELECT sp.requested_by, COUNT(*) AS "plays!"
+		FROM song_plays sp
+		WHERE sp.guild_id = $1
+			AND ($2::TIMESTAMPTZ IS NULL OR sp.played_at >= $2)
+		GROUP BY sp.requested_by
+		ORDER BY COUNT(*) DESC
+		LIMIT 10
+		"#,
+		guild_id,
+		since
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn fetch_busiest_hours(
+	guild_id: i64,
+	user_id: Option<i64>,
+	since: Option<OffsetDateTime>,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<HourStat>, Error> {
+	query_as!(
+		HourStat,
+		r#"
+		SELECT
+			EXTRACT(HOUR FROM sp.played_at AT TIME ZONE 'UTC')::INT AS "hour!",
+			COUNT(*) AS "plays!"
+		FROM song_plays sp
+		WHERE sp.guild_id = $1
+			AND ($2::BIGINT IS NULL OR sp.requested_by = $2)
+			AND ($3::TIMESTAMPTZ IS NULL OR sp.played_at >= $3)
+		GROUP BY 1
+		ORDER BY 2 DESC, 1
+		LIMIT 5
+		"#,
+		guild_id,
+		user_id,
+		since
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub struct GuildSound {
+	pub name: String,
+	pub duration_ms: i32,
+}
+
+pub async fn insert_sound(
+	guild_id: i64,
+	name: &str,
+	audio: &[u8],
+	duration_ms: i32,
+	uploaded_by: i64,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		INSERT INTO guild_sounds (guild_id, name, audio, duration_ms, uploaded_by)
+		VALUES ($1, $2, $3, $4, $5)
+		ON CONFLICT (guild_id, name)
+		DO UPDATE SET audio = $3, duration_ms = $4, uploaded_by = $5, created_at = NOW()
+		"#,
+		guild_id,
+		name,
+		audio,
+		duration_ms,
+		uploaded_by
+	)
+	.execute(conn)
+	.await
+}
+
+pub async fn fetch_sound_audio(
+	guild_id: i64,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<Option<Vec<u8>>, Error> {
+	query_scalar!(
+		r#"
+		SELECT audio
+		FROM guild_sounds
+		WHERE guild_id = $1
+			AND name = $2
+		"#,
+		guild_id,
+		name
+	)
+	.fetch_optional(conn)
+	.await
+}
+
+pub async fn fetch_sounds(guild_id: i64, conn: &Pool<Postgres>) -> Result<Vec<GuildSound>, Error> {
+	query_as!(
+		GuildSound,
+		r#"
+		SELECT name, duration_ms
+		FROM guild_sounds
+		WHERE guild_id = $1
+		ORDER BY name
+		"#,
+		guild_id
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn sound_exists(guild_id: i64, name: &str, conn: &Pool<Postgres>) -> Result<bool, Error> {
+	query_scalar!(
+		r#"
+		SELECT EXISTS(
+			SELECT 1 FROM guild_sounds WHERE guild_id = $1 AND name = $2
+		) AS "exists!"
+		"#,
+		guild_id,
+		name
+	)
+	.fetch_one(conn)
+	.await
+}
+
+pub async fn sound_count(guild_id: i64, conn: &Pool<Postgres>) -> Result<i64, Error> {
+	query_scalar!(
+		r#"
+		SELECT COUNT(*) AS "count!"
+		FROM guild_sounds
+		WHERE guild_id = $1
+		"#,
+		guild_id
+	)
+	.fetch_one(conn)
+	.await
+}
+
+pub async fn delete_sound(
+	guild_id: i64,
+	name: &str,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		DELETE FROM guild_sounds
+		WHERE guild_id = $1
+			AND name = $2
+		"#,
+		guild_id,
+		name
+	)
+	.execute(conn)
+	.await
+}
+
+pub struct LibraryTrack {
+	pub track_uuid: Uuid,
+	pub title: String,
+	pub artist: String,
+	pub album: Option<String>,
+	pub duration_sec: i64,
+}
+
+/// Local file along with everything needed to play it
+pub struct LibraryFile {
+	pub track_uuid: Uuid,
+	pub title: String,
+	pub artist: String,
+	pub album: Option<String>,
+	pub source_url: String,
+	pub duration_sec: i64,
+	pub cover_art: Option<Vec<u8>>,
+}
+
+pub async fn upsert_library_file(
+	file: &LibraryFile,
+	thumbnail_url: &str,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		INSERT INTO tracks (track_uuid, title, artist, album, source_url, duration_sec, thumbnail_url, cover_art, source, in_library)
+		VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'local', TRUE)
+		ON CONFLICT (track_uuid)
+		DO UPDATE SET title = $2,
+			artist = $3,
+			album = $4,
+			duration_sec = $6,
+			cover_art = $8,
+			in_library = TRUE,
+			last_seen = NOW()
+		"#,
+		file.track_uuid,
+		file.title,
+		file.artist,
+		file.album,
+		file.source_url,
+		file.duration_sec,
+		thumbnail_url,
+		file.cover_art
+	)
+	.execute(conn)
+	.await
+}
+
+/// Hides local tracks whose file wasn't found by the latest scan, keeping
+/// them around for play history and playlists
+pub async fn unlist_missing_files(
+	found: &[Uuid],
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		UPDATE tracks
+		SET in_library = FALSE
+		WHERE in_library
+			AND NOT (track_uuid = ANY($1))
+		"#,
+		found
+	)
+	.execute(conn)
+	.await
+}
+
+pub async fn search_library(
+	search: &str,
+	limit: i64,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<LibraryTrack>, Error> {
+	query_as!(
+		LibraryTrack,
+		r#"
+		SELECT track_uuid, title, artist, album, duration_sec
+		FROM tracks
+		WHERE in_library
+			AND (title ILIKE '%' || $1 || '%'
+				OR artist ILIKE '%' || $1 || '%'
+				OR album ILIKE '%' || $1 || '%')
+		ORDER BY artist, album, title
+		LIMIT $2
+		"#,
+		search,
+		limit
+	)
+	.fetch_all(conn)
+	.await
+}
+
+pub async fn fetch_library_file(
+	track_uuid: Uuid,
+	conn: &Pool<Postgres>,
+) -> Result<Option<LibraryFile>, Error> {
+	query_as!(
+		LibraryFile,
+		r#"
+		SELECT track_uuid, title, artist, album, source_url, duration_sec, cover_art
+		FROM tracks
+		WHERE track_uuid = $1
+			AND in_library
+		"#,
+		track_uuid
+	)
+	.fetch_optional(conn)
+	.await
+}
@@ -71,3 +71,150 @@ pub async fn insert_user_settings(
 	.execute(conn)
 	.await
 }
+
+pub struct YearlyStats {
+	pub message_count: i64,
+	pub quotes_made: i64,
+}
+
+pub struct WordStat {
+	pub word: String,
+	pub count: i64,
+}
+
+pub async fn record_user_message(
+	guild_id: i64,
+	user_id: i64,
+	words: &[String],
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		WITH ensured_user AS (
+			INSERT INTO users (user_id)
+			VALUES ($2)
+			ON CONFLICT (user_id) DO NOTHING
+		), ensured_guild AS (
+			INSERT INTO guilds (guild_id)
+			VALUES ($1)
+			ON CONFLICT (guild_id) DO NOTHING
+		), yearly AS (
+			INSERT INTO user_yearly_stats (guild_id, user_id, year, messages_before)
+			SELECT $1, $2, EXTRACT(YEAR FROM NOW())::INT, COALESCE(MAX(message_count), 0)
+			FROM user_settings
+			WHERE guild_id = $1
+				AND user_id = $2
+			ON CONFLICT (guild_id, user_id, year) DO NOTHING
+		)
+		INSERT INTO user_word_tracking (guild_id, user_id, year, word, count)
+		SELECT $1, $2, EXTRACT(YEAR FROM NOW())::INT, gwt.word, COUNT(*)
+		FROM guild_word_tracking gwt
+		JOIN UNNEST($3::TEXT[]) AS w(word) ON w.word ILIKE gwt.word
+		WHERE gwt.guild_id = $1
+		GROUP BY gwt.word
+		ON CONFLICT (guild_id, user_id, year, word)
+		DO UPDATE SET count = user_word_tracking.count + EXCLUDED.count
+		"#,
+		guild_id,
+		user_id,
+		words
+	)
+	.execute(conn)
+	.await
+}
+
+pub async fn record_quote(
+	guild_id: i64,
+	user_id: i64,
+	conn: &Pool<Postgres>,
+) -> Result<PgQueryResult, Error> {
+	query!(
+		r#"
+		WITH ensured_user AS (
+			INSERT INTO users (user_id)
+			VALUES ($2)
+			ON CONFLICT (user_id) DO NOTHING
+		), ensured_guild AS (
+			INSERT INTO guilds (guild_id)
+			VALUES ($1)
+			ON CONFLICT (guild_id) DO NOTHING
+		)
+		INSERT INTO user_yearly_stats (guild_id, user_id, year, messages_before, quotes_made)
+		SELECT $1, $2, EXTRACT(YEAR FROM NOW())::INT, COALESCE(MAX(message_count), 0), 1
+		FROM user_settings
+		WHERE guild_id = $1
+			AND user_id = $2
+		ON CONFLICT (guild_id, user_id, year)
+		DO UPDATE SET quotes_made = user_yearly_stats.quotes_made + 1
+		"#,
+		guild_id,
+		user_id
+	)
+	.execute(conn)
+	.await
+}
+
+pub async fn fetch_yearly_stats(
+	guild_id: i64,
+	user_id: i64,
+	year: i32,
+	conn: &Pool<Postgres>,
+) -> Result<Option<YearlyStats>, Error> {
+	query_as!(
+		YearlyStats,
+		r#"
+		SELECT
+			GREATEST(
+				COALESCE(
+					(
+						SELECT MIN(later.messages_before)
+						FROM user_yearly_stats later
+						WHERE later.guild_id = yearly.guild_id
+							AND later.user_id = yearly.user_id
+							AND later.year > yearly.year
+					),
+					settings.message_count
+				) - yearly.messages_before,
+				0
+			) AS "message_count!",
+			yearly.quotes_made
+		FROM user_yearly_stats yearly
+		JOIN user_settings settings
+			ON settings.guild_id = yearly.guild_id
+			AND settings.user_id = yearly.user_id
+		WHERE yearly.guild_id = $1
+			AND yearly.user_id = $2
+			AND yearly.year = $3
+		"#,
+		guild_id,
+		user_id,
+		year
+	)
+	.fetch_optional(conn)
+	.await
+}
+
+pub async fn fetch_top_words(
+	guild_id: i64,
+	user_id: i64,
+	year: i32,
+	conn: &Pool<Postgres>,
+) -> Result<Vec<WordStat>, Error> {
+	query_as!(
+		WordStat,
+		r#"
+		SELECT word, count
+		FROM user_word_tracking
+		WHERE guild_id = $1
+			AND user_id = $2
+			AND year = $3
+		ORDER BY count DESC, word
+		LIMIT 3
+		"#,
+		guild_id,
+		user_id,
+		year
+	)
+	.fetch_all(conn)
+	.await
+}
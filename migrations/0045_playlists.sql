@@ -0,0 +1,23 @@
+CREATE TABLE playlists (
+    playlist_id BIGSERIAL PRIMARY KEY,
+    owner_user_id BIGINT NULL DEFAULT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    owner_guild_id BIGINT NULL DEFAULT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT playlists_single_owner
+        CHECK ((owner_user_id IS NULL) <> (owner_guild_id IS NULL))
+);
+
+CREATE UNIQUE INDEX idx_playlists_user_name ON playlists(owner_user_id, name)
+    WHERE owner_user_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_playlists_guild_name ON playlists(owner_guild_id, name)
+    WHERE owner_guild_id IS NOT NULL;
+
+CREATE TABLE playlist_tracks (
+    playlist_id BIGINT NOT NULL REFERENCES playlists(playlist_id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    track_uuid UUID NOT NULL REFERENCES tracks(track_uuid) ON DELETE CASCADE,
+    PRIMARY KEY (playlist_id, position)
+);
+
+CREATE INDEX idx_playlist_tracks_track ON playlist_tracks(track_uuid);
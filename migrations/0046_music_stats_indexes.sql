@@ -0,0 +1,2 @@
+CREATE INDEX idx_song_plays_guild_user_recent ON song_plays(guild_id, requested_by, played_at);
+CREATE INDEX idx_tracks_artist ON tracks(artist);
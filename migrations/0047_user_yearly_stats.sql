@@ -0,0 +1,23 @@
+CREATE TABLE user_yearly_stats (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    year INT NOT NULL,
+    -- Yearly message counts are derived from user_settings.message_count, so
+    -- only the total at the start of each year is kept
+    messages_before BIGINT NOT NULL DEFAULT 0,
+    quotes_made BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (guild_id, user_id, year)
+);
+
+CREATE TABLE user_word_tracking (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    year INT NOT NULL,
+    word TEXT NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (guild_id, user_id, year, word),
+    FOREIGN KEY (guild_id, user_id, year)
+        REFERENCES user_yearly_stats(guild_id, user_id, year) ON DELETE CASCADE,
+    FOREIGN KEY (guild_id, word)
+        REFERENCES guild_word_tracking(guild_id, word) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+ALTER TABLE guild_settings
+    ADD COLUMN music_dj_role BIGINT NULL DEFAULT NULL,
+    ADD COLUMN music_requester_skip BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN music_vote_skip_percent SMALLINT NOT NULL DEFAULT 50
+        CHECK (music_vote_skip_percent BETWEEN 1 AND 100);
@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+    ADD COLUMN music_idle_timeout SMALLINT NOT NULL DEFAULT 5
+        CHECK (music_idle_timeout BETWEEN 0 AND 120);
@@ -0,0 +1,2 @@
+ALTER TABLE tracks
+    ADD COLUMN source TEXT NOT NULL DEFAULT 'youtube';
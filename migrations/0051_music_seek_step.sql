@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+    ADD COLUMN music_seek_step SMALLINT NOT NULL DEFAULT 10
+        CHECK (music_seek_step BETWEEN 1 AND 300);
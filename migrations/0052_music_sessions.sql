@@ -0,0 +1,12 @@
+ALTER TABLE guild_settings
+    ADD COLUMN music_resume BOOLEAN NOT NULL DEFAULT TRUE;
+
+CREATE TABLE music_sessions (
+    guild_id BIGINT PRIMARY KEY REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    voice_channel_id BIGINT NOT NULL,
+    text_channel_id BIGINT NOT NULL,
+    position_ms BIGINT NOT NULL DEFAULT 0,
+    looping BOOLEAN NOT NULL DEFAULT FALSE,
+    tracks JSONB NOT NULL DEFAULT '[]',
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
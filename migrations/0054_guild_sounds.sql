@@ -0,0 +1,9 @@
+CREATE TABLE guild_sounds (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    audio BYTEA NOT NULL,
+    duration_ms INT NOT NULL,
+    uploaded_by BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, name)
+);
@@ -0,0 +1,6 @@
+ALTER TABLE tracks
+    ADD COLUMN album TEXT NULL DEFAULT NULL,
+    ADD COLUMN cover_art BYTEA NULL DEFAULT NULL,
+    ADD COLUMN in_library BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_tracks_library ON tracks(artist, title) WHERE in_library;
@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+    ADD COLUMN music_target_lufs SMALLINT NOT NULL DEFAULT -14
+        CHECK (music_target_lufs BETWEEN -40 AND -5);
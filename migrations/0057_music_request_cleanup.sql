@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+    ADD COLUMN music_request_cleanup SMALLINT NOT NULL DEFAULT 0
+        CHECK (music_request_cleanup BETWEEN 0 AND 3600);
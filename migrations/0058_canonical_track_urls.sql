@@ -0,0 +1,90 @@
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+-- Same rules as canonical_url for the sources that actually get stored, tracking
+-- parameters only match whole keys
+CREATE TEMPORARY TABLE track_merges AS
+WITH parts AS (
+    SELECT
+        track_uuid,
+        source_url,
+        substring(source_url FROM '^[^?#]*') AS base,
+        regexp_replace(
+            coalesce(substring(source_url FROM '\?([^#]*)'), ''),
+            '(^|&)(utm_[^=&]*|si|feature|pp|fbclid|gclid|igshid|ref)(=[^&]*)?(?=&|$)',
+            '',
+            'g'
+        ) AS query,
+        substring(
+            source_url
+            FROM '(?:youtu\.be/|[?&]v=|/shorts/|/embed/|/live/|/v/)([A-Za-z0-9_-]+)'
+        ) AS youtube_id
+    FROM tracks
+),
+canonical AS (
+    SELECT
+        track_uuid,
+        CASE
+            WHEN source_url ~* '^https?://([a-z]+\.)?(youtube\.com|youtu\.be)/'
+                AND youtube_id IS NOT NULL
+                THEN 'https://www.youtube.com/watch?v=' || youtube_id
+            WHEN source_url ~* '^https?://(www\.|m\.)?soundcloud\.com/'
+                THEN 'https://soundcloud.com' || rtrim(substring(base FROM '^https?://[^/]+(/.*)$'), '/')
+            WHEN source_url ~* '^https?://(on\.soundcloud\.com|[^/]+\.bandcamp\.com)/'
+                THEN base
+            WHEN source_url ~* '^https?://'
+                THEN base || CASE
+                    WHEN ltrim(query, '&') = '' THEN ''
+                    ELSE '?' || ltrim(query, '&')
+                END
+            ELSE source_url
+        END AS canonical_url
+    FROM parts
+)
+SELECT
+    track_uuid AS old_uuid,
+    uuid_generate_v5(uuid_ns_url(), canonical_url) AS new_uuid,
+    canonical_url
+FROM canonical;
+
+DELETE FROM track_merges WHERE old_uuid = new_uuid;
+
+-- Duplicates collapse into the one row the canonical url maps to, keeping
+-- the most recently seen metadata
+INSERT INTO tracks (
+    track_uuid, title, artist, source_url, duration_sec, thumbnail_url, source,
+    album, cover_art, in_library, first_seen, last_seen
+)
+SELECT DISTINCT ON (m.new_uuid)
+    m.new_uuid, t.title, t.artist, m.canonical_url, t.duration_sec, t.thumbnail_url, t.source,
+    t.album, t.cover_art, t.in_library, t.first_seen, t.last_seen
+FROM track_merges m
+JOIN tracks t ON t.track_uuid = m.old_uuid
+ORDER BY m.new_uuid, t.last_seen DESC
+ON CONFLICT DO NOTHING;
+
+UPDATE tracks t
+SET first_seen = LEAST(t.first_seen, merged.first_seen),
+    last_seen = GREATEST(t.last_seen, merged.last_seen)
+FROM (
+    SELECT m.new_uuid, MIN(o.first_seen) AS first_seen, MAX(o.last_seen) AS last_seen
+    FROM track_merges m
+    JOIN tracks o ON o.track_uuid = m.old_uuid
+    GROUP BY m.new_uuid
+) merged
+WHERE t.track_uuid = merged.new_uuid;
+
+UPDATE song_plays sp
+SET track_uuid = m.new_uuid
+FROM track_merges m
+WHERE sp.track_uuid = m.old_uuid;
+
+UPDATE playlist_tracks pt
+SET track_uuid = m.new_uuid
+FROM track_merges m
+WHERE pt.track_uuid = m.old_uuid;
+
+DELETE FROM tracks t
+USING track_merges m
+WHERE t.track_uuid = m.old_uuid;
+
+DROP TABLE track_merges;
@@ -0,0 +1,18 @@
+CREATE TABLE quote_themes (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    style TEXT NOT NULL CHECK (style IN ('solid', 'linear', 'radial', 'image')),
+    primary_colour INT NOT NULL,
+    secondary_colour INT NULL DEFAULT NULL,
+    angle SMALLINT NOT NULL DEFAULT 0 CHECK (angle BETWEEN 0 AND 359),
+    background BYTEA NULL DEFAULT NULL,
+    blur REAL NOT NULL DEFAULT 0 CHECK (blur BETWEEN 0 AND 50),
+    dim SMALLINT NOT NULL DEFAULT 0 CHECK (dim BETWEEN 0 AND 100),
+    text_colour INT NOT NULL,
+    outline_colour INT NULL DEFAULT NULL,
+    created_by BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, name),
+    CONSTRAINT quote_themes_image_background
+        CHECK ((style = 'image') = (background IS NOT NULL))
+);
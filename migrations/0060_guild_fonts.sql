@@ -0,0 +1,8 @@
+CREATE TABLE guild_fonts (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    font BYTEA NOT NULL,
+    uploaded_by BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, name)
+);
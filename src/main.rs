@@ -112,6 +112,7 @@ async fn main() -> AResult<()> {
 			bot_name: bot_config.username.clone(),
 			error_webhook: bot_config.error_webhook.clone(),
 			feedback_webhook: bot_config.feedback_webhook.clone(),
+			music_dir: bot_config.music_dir.clone(),
 		})
 		.is_err()
 	{